@@ -43,9 +43,9 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                             Credential::new(&protocol.ck, &protocol.pp, &attributes, r);
 
                         // Issue credential
-                        let proof = credential.prove_commitment(&protocol.pp, &mut rng);
-                        let signature = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
-                        credential.add_signature(signature);
+                        protocol
+                            .obtain_and_issue(&mut credential, &issuer_sk, &mut rng)
+                            .unwrap();
 
                         credentials.push(credential);
                     }
@@ -87,7 +87,7 @@ fn benchmark_verification_methods(c: &mut Criterion) {
 
                         let proof = credential.prove_commitment(&protocol.pp, &mut rng);
                         let signature = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
-                        credential.add_signature(signature);
+                        credential.add_signature(signature).unwrap();
 
                         credentials.push(credential);
                     }
@@ -131,19 +131,21 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                         // Issue credential
                         let proof = credential.prove_commitment(&protocol.pp, &mut rng);
                         let signature = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
-                        credential.add_signature(signature);
+                        credential.add_signature(signature).unwrap();
 
                         credentials.push(credential);
                     }
 
                     // Create an aggregate presentation - still part of setup
                     b.iter(|| {
-                        let aggregate = CredentialAggregation::aggregate_credentials(
-                            &credentials,
-                            &protocol.pp,
-                            &mut rng,
+                        black_box(
+                            CredentialAggregation::aggregate_credentials(
+                                &credentials,
+                                &protocol.pp,
+                                &mut rng,
+                            )
+                            .unwrap(),
                         )
-                        .unwrap();
                     });
                 },
             );
@@ -176,7 +178,7 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                         // Issue credential
                         let proof = credential.prove_commitment(&protocol.pp, &mut rng);
                         let signature = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
-                        credential.add_signature(signature);
+                        credential.add_signature(signature).unwrap();
 
                         credentials.push(credential);
                     }
@@ -192,7 +194,7 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                     // NOW we benchmark ONLY the verification
                     b.iter(|| {
                         // Use batch verification with privacy features
-                        black_box(aggregate.batch_verify(&protocol.pp, &issuer_vk))
+                        black_box(aggregate.batch_verify(&protocol.pp, &issuer_vk, &mut rng))
                     });
                 },
             );
@@ -250,7 +252,7 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                         let signature = protocols[issuer_idx]
                             .issue(&proof, &issuer_sks[issuer_idx], &mut rng)
                             .unwrap();
-                        credential.add_signature(signature);
+                        credential.add_signature(signature).unwrap();
 
                         // Simple verification check
                         assert!(
@@ -275,7 +277,10 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                         black_box(
                             // Create the linked presentation
                             mimc_abc::linked_credentials::LinkedCredentialPresentation::create(
-                                &cred_refs, &pp_refs, &mut rng,
+                                &cred_refs,
+                                &pp_refs,
+                                &vec![Vec::new(); cred_refs.len()],
+                                &mut rng,
                             )
                             .unwrap(),
                         )
@@ -336,7 +341,7 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                         let signature = protocols[issuer_idx]
                             .issue(&proof, &issuer_sks[issuer_idx], &mut rng)
                             .unwrap();
-                        credential.add_signature(signature);
+                        credential.add_signature(signature).unwrap();
 
                         // Simple verification check
                         assert!(
@@ -360,7 +365,10 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                     // Create the linked presentation
                     let linked_presentation =
                         mimc_abc::linked_credentials::LinkedCredentialPresentation::create(
-                            &cred_refs, &pp_refs, &mut rng,
+                            &cred_refs,
+                            &pp_refs,
+                            &vec![Vec::new(); cred_refs.len()],
+                            &mut rng,
                         )
                         .unwrap();
 
@@ -372,12 +380,14 @@ fn benchmark_verification_methods(c: &mut Criterion) {
 
                     // Validate that the linked presentation works before benchmarking
                     assert!(
-                        linked_presentation.verify(&pp_refs, &vk_refs).unwrap(),
+                        linked_presentation.verify(&pp_refs, &vk_refs, 0).unwrap(),
                         "Linked presentation verification failed"
                     );
 
                     // NOW we benchmark ONLY the verification
-                    b.iter(|| black_box(linked_presentation.verify(&pp_refs, &vk_refs).unwrap()));
+                    b.iter(|| {
+                        black_box(linked_presentation.verify(&pp_refs, &vk_refs, 0).unwrap())
+                    });
                 },
             );
         }
@@ -385,5 +395,126 @@ fn benchmark_verification_methods(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, benchmark_verification_methods);
+// Batch verification of 32 credentials with 32 attributes, sequential against rayon tasks
+// The parallel variant only runs on several threads with `--features parallel`
+fn benchmark_batch_verify_parallel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_verify_parallel");
+    let mut rng = ark_std::test_rng();
+    let (cred_count, attr_count) = (32, 32);
+    let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(attr_count, &mut rng);
+    let credentials: Vec<Credential<Bls12_381>> = (0..cred_count)
+        .map(|_| {
+            let attributes: Vec<Fr> = (0..attr_count).map(|_| Fr::rand(&mut rng)).collect();
+            let mut credential =
+                Credential::new(&protocol.ck, &protocol.pp, &attributes, Fr::rand(&mut rng));
+            let proof = credential.prove_commitment(&protocol.pp, &mut rng);
+            credential
+                .add_signature(protocol.issue(&proof, &issuer_sk, &mut rng).unwrap())
+                .unwrap();
+            credential
+        })
+        .collect();
+    let aggregate =
+        CredentialAggregation::aggregate_credentials(&credentials, &protocol.pp, &mut rng).unwrap();
+    let id_suffix = format!("{}creds_{}attrs", cred_count, attr_count);
+
+    group.bench_function(BenchmarkId::new("serial", &id_suffix), |b| {
+        b.iter(|| black_box(aggregate.batch_verify(&protocol.pp, &issuer_vk, &mut rng)))
+    });
+    group.bench_function(BenchmarkId::new("parallel", &id_suffix), |b| {
+        b.iter(|| black_box(aggregate.batch_verify_par(&protocol.pp, &issuer_vk, &mut rng)))
+    });
+    group.finish();
+}
+
+// Linked presentation of 16 credentials from 8 issuers with 16 attributes,
+// sequential verify against one weighted pairing check over all credentials
+fn benchmark_linked_verify_batched(c: &mut Criterion) {
+    let mut group = c.benchmark_group("linked_verify");
+    let mut rng = ark_std::test_rng();
+    let (cred_count, issuer_count, attr_count) = (16, 8, 16);
+    let issuers: Vec<_> = (0..issuer_count)
+        .map(|_| MimcAbc::<Bls12_381>::setup(attr_count, &mut rng))
+        .collect();
+    let user_id = Fr::rand(&mut rng);
+    let credentials: Vec<Credential<Bls12_381>> = (0..cred_count)
+        .map(|i| {
+            let (protocol, issuer_sk, _) = &issuers[i % issuer_count];
+            let mut attributes = vec![user_id];
+            attributes.extend((1..attr_count).map(|_| Fr::rand(&mut rng)));
+            let mut credential =
+                Credential::new(&protocol.ck, &protocol.pp, &attributes, Fr::rand(&mut rng));
+            let proof = credential.prove_commitment(&protocol.pp, &mut rng);
+            credential
+                .add_signature(protocol.issue(&proof, issuer_sk, &mut rng).unwrap())
+                .unwrap();
+            credential
+        })
+        .collect();
+    let cred_refs: Vec<&Credential<Bls12_381>> = credentials.iter().collect();
+    let pp_refs: Vec<&PublicParams<Bls12_381>> = (0..cred_count)
+        .map(|i| &issuers[i % issuer_count].0.pp)
+        .collect();
+    let vk_refs: Vec<&VerificationKey<Bls12_381>> = (0..cred_count)
+        .map(|i| &issuers[i % issuer_count].2)
+        .collect();
+    let presentation = mimc_abc::linked_credentials::LinkedCredentialPresentation::create(
+        &cred_refs,
+        &pp_refs,
+        &vec![Vec::new(); cred_count],
+        &mut rng,
+    )
+    .unwrap();
+    assert!(presentation
+        .verify_batched(&pp_refs, &vk_refs, 0, &mut rng)
+        .unwrap());
+    let id_suffix = format!("{}creds_{}attrs", cred_count, attr_count);
+
+    group.bench_function(BenchmarkId::new("sequential", &id_suffix), |b| {
+        b.iter(|| black_box(presentation.verify(&pp_refs, &vk_refs, 0).unwrap()))
+    });
+    group.bench_function(BenchmarkId::new("batched", &id_suffix), |b| {
+        b.iter(|| {
+            black_box(
+                presentation
+                    .verify_batched(&pp_refs, &vk_refs, 0, &mut rng)
+                    .unwrap(),
+            )
+        })
+    });
+    group.finish();
+}
+
+// Single signature: 4 pairings (verify) against 3 Miller loops and one final exponentiation
+fn benchmark_signature_verify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("signature_verify");
+    let mut rng = ark_std::test_rng();
+    let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(4, &mut rng);
+    let attributes: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+    let mut credential =
+        Credential::new(&protocol.ck, &protocol.pp, &attributes, Fr::rand(&mut rng));
+    let proof = credential.prove_commitment(&protocol.pp, &mut rng);
+    credential
+        .add_signature(protocol.issue(&proof, &issuer_sk, &mut rng).unwrap())
+        .unwrap();
+    let signature = credential.signature.as_ref().unwrap();
+
+    group.bench_function("verify", |b| {
+        b.iter(|| black_box(issuer_vk.verify(signature, &credential.commitment, &protocol.pp)))
+    });
+    group.bench_function("verify_optimized", |b| {
+        b.iter(|| {
+            black_box(issuer_vk.verify_optimized(signature, &credential.commitment, &protocol.pp))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_verification_methods,
+    benchmark_signature_verify,
+    benchmark_batch_verify_parallel,
+    benchmark_linked_verify_batched
+);
 criterion_main!(benches);
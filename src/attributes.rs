@@ -0,0 +1,275 @@
+use crate::error::Error;
+use ark_ff::{BigInteger, PrimeField};
+use ark_std::ops::Range;
+
+/// Encode an integer attribute (timestamps, amounts) as a scalar
+/// Errors rather than wrapping when the value is at or above the field modulus,
+/// since two inputs that wrap to the same scalar would be indistinguishable
+pub fn encode_u128<F: PrimeField>(v: u128) -> Result<F, Error> {
+    let encoded = F::from(v);
+    // Reduction only happened if the round trip doesn't give v back
+    match decode_u128(&encoded) {
+        Ok(decoded) if decoded == v => Ok(encoded),
+        _ => Err(Error::AttributeOutOfRange(v)),
+    }
+}
+
+/// Decode a scalar back to an integer, erroring if it doesn't fit in 128 bits
+pub fn decode_u128<F: PrimeField>(value: &F) -> Result<u128, Error> {
+    let bytes = value.into_bigint().to_bytes_le();
+    if bytes.iter().skip(16).any(|b| *b != 0) {
+        return Err(Error::Other(
+            "Scalar is too large to decode as u128".to_string(),
+        ));
+    }
+    let mut buf = [0u8; 16];
+    let len = bytes.len().min(16);
+    buf[..len].copy_from_slice(&bytes[..len]);
+    Ok(u128::from_le_bytes(buf))
+}
+
+/// A value too wide for one scalar (a 256-bit hash, a coordinate pair), split over
+/// consecutive attribute slots. Bytes are packed little-endian, `bytes_per_slot` at a time,
+/// so every slot is below the modulus and packing never reduces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiFieldValue<F: PrimeField> {
+    pub slots: Vec<F>,
+}
+
+impl<F: PrimeField> MultiFieldValue<F> {
+    /// Whole bytes that fit below the modulus: 31 for BLS12-381
+    pub fn bytes_per_slot() -> usize {
+        (F::MODULUS_BIT_SIZE as usize - 1) / 8
+    }
+
+    /// Slots needed for a value of `byte_len` bytes
+    pub fn width_for(byte_len: usize) -> usize {
+        byte_len.div_ceil(Self::bytes_per_slot()).max(1)
+    }
+
+    pub fn pack(bytes: &[u8]) -> Self {
+        let mut slots: Vec<F> = bytes
+            .chunks(Self::bytes_per_slot())
+            .map(F::from_le_bytes_mod_order)
+            .collect();
+        if slots.is_empty() {
+            slots.push(F::zero());
+        }
+        Self { slots }
+    }
+
+    /// Recover the first `byte_len` packed bytes
+    /// Errors if a slot holds more than `bytes_per_slot` bytes or the slots are too few
+    pub fn unpack(&self, byte_len: usize) -> Result<Vec<u8>, Error> {
+        let per_slot = Self::bytes_per_slot();
+        if self.slots.len() < Self::width_for(byte_len) {
+            return Err(Error::Other(format!(
+                "{} slots can't hold {} bytes",
+                self.slots.len(),
+                byte_len
+            )));
+        }
+        let mut bytes = Vec::with_capacity(self.slots.len() * per_slot);
+        for slot in &self.slots {
+            let slot_bytes = slot.into_bigint().to_bytes_le();
+            if slot_bytes.iter().skip(per_slot).any(|b| *b != 0) {
+                return Err(Error::Other(format!(
+                    "Slot holds more than {} bytes",
+                    per_slot
+                )));
+            }
+            bytes.extend_from_slice(&slot_bytes[..per_slot]);
+        }
+        if bytes[byte_len..].iter().any(|b| *b != 0) {
+            return Err(Error::Other(format!(
+                "Value is longer than {} bytes",
+                byte_len
+            )));
+        }
+        bytes.truncate(byte_len);
+        Ok(bytes)
+    }
+}
+
+/// Layout of named attributes over a credential's slots, each spanning `width` consecutive
+/// slots in declaration order
+/// ```ignore
+/// let schema = AttributeSchema::new()
+///     .attribute("user_id", 1)
+///     .attribute("document_hash", MultiFieldValue::<Fr>::width_for(32));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AttributeSchema {
+    attributes: Vec<(String, usize)>, // (name, width)
+}
+
+impl AttributeSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an attribute occupying `width` slots
+    pub fn attribute(mut self, name: &str, width: usize) -> Self {
+        self.attributes.push((name.to_string(), width));
+        self
+    }
+
+    /// Total slots, the `n` the issuer's parameters need
+    pub fn len(&self) -> usize {
+        self.attributes.iter().map(|(_, width)| width).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn width(&self, name: &str) -> Option<usize> {
+        self.slots(name).map(|slots| slots.len())
+    }
+
+    /// Slot indices held by `name`
+    pub fn slots(&self, name: &str) -> Option<Range<usize>> {
+        let mut start = 0;
+        for (attribute, width) in &self.attributes {
+            if attribute == name {
+                return Some(start..start + width);
+            }
+            start += width;
+        }
+        None
+    }
+
+    /// Read `name` out of a credential's messages
+    pub fn read<F: PrimeField>(
+        &self,
+        messages: &[F],
+        name: &str,
+    ) -> Result<MultiFieldValue<F>, Error> {
+        let slots = self.checked_slots(messages.len(), name)?;
+        Ok(MultiFieldValue {
+            slots: messages[slots].to_vec(),
+        })
+    }
+
+    /// Write `value` into the slots of `name`; the widths have to match
+    pub fn write<F: PrimeField>(
+        &self,
+        messages: &mut [F],
+        name: &str,
+        value: &MultiFieldValue<F>,
+    ) -> Result<(), Error> {
+        let slots = self.checked_slots(messages.len(), name)?;
+        if value.slots.len() != slots.len() {
+            return Err(Error::Other(format!(
+                "Attribute {} spans {} slots, value has {}",
+                name,
+                slots.len(),
+                value.slots.len()
+            )));
+        }
+        messages[slots].copy_from_slice(&value.slots);
+        Ok(())
+    }
+
+    fn checked_slots(&self, message_count: usize, name: &str) -> Result<Range<usize>, Error> {
+        if message_count != self.len() {
+            return Err(Error::Other(format!(
+                "Schema has {} slots, got {} messages",
+                self.len(),
+                message_count
+            )));
+        }
+        self.slots(name)
+            .ok_or_else(|| Error::Other(format!("Unknown attribute {}", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use small_field::SmallField;
+
+    // 64-bit field so u128 values can exceed the modulus
+    mod small_field {
+        // the derive expands to an impl inside a const block
+        #![allow(non_local_definitions)]
+        use ark_ff::fields::{Fp64, MontBackend, MontConfig};
+
+        #[derive(MontConfig)]
+        #[modulus = "18446744069414584321"]
+        #[generator = "7"]
+        pub struct SmallFieldConfig;
+        pub type SmallField = Fp64<MontBackend<SmallFieldConfig, 1>>;
+    }
+
+    #[test]
+    fn test_encode_decode_u128() {
+        let values = [0u128, 1, 42, u64::MAX as u128, 1 << 100, u128::MAX];
+        for v in values {
+            let encoded: Fr =
+                encode_u128(v).expect("Every u128 fits in the BLS12-381 scalar field");
+            assert_eq!(decode_u128(&encoded).unwrap(), v);
+        }
+
+        // Too large to decode
+        let big = Fr::from(u128::MAX) * Fr::from(4u64);
+        assert!(decode_u128(&big).is_err());
+    }
+
+    #[test]
+    fn test_encode_out_of_range() {
+        let modulus: u128 = 18446744069414584321;
+        let below: SmallField = encode_u128(modulus - 1).expect("Below the modulus should encode");
+        assert_eq!(decode_u128(&below).unwrap(), modulus - 1);
+
+        // Values at or above the modulus would wrap and collide
+        assert!(matches!(
+            encode_u128::<SmallField>(modulus),
+            Err(Error::AttributeOutOfRange(v)) if v == modulus
+        ));
+        assert!(encode_u128::<SmallField>(modulus + 5).is_err());
+    }
+
+    #[test]
+    fn test_multi_field_value_in_schema() {
+        let hash: Vec<u8> = (0..32u8).map(|i| i.wrapping_mul(37) ^ 0xa5).collect();
+        let width = MultiFieldValue::<Fr>::width_for(hash.len());
+        assert_eq!(width, 2);
+
+        let schema = AttributeSchema::new()
+            .attribute("user_id", 1)
+            .attribute("document_hash", width)
+            .attribute("age", 1);
+        assert_eq!(schema.len(), 4);
+        assert_eq!(schema.slots("document_hash"), Some(1..3));
+        assert_eq!(schema.width("age"), Some(1));
+
+        let mut messages = vec![
+            Fr::from(7u64),
+            Fr::from(0u64),
+            Fr::from(0u64),
+            Fr::from(42u64),
+        ];
+        schema
+            .write(
+                &mut messages,
+                "document_hash",
+                &MultiFieldValue::pack(&hash),
+            )
+            .unwrap();
+        assert_eq!(messages[3], Fr::from(42u64));
+
+        let value = schema.read(&messages, "document_hash").unwrap();
+        assert_eq!(value.unpack(hash.len()).unwrap(), hash);
+
+        // One slot can't take a two-slot value
+        let one_slot = MultiFieldValue {
+            slots: vec![Fr::from(1u64)],
+        };
+        assert!(schema
+            .write(&mut messages, "document_hash", &one_slot)
+            .is_err());
+        assert!(schema.read(&messages, "unknown").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use crate::credential::ShowCredential;
+use crate::pairing::{nonzero_scalar, PairingCheck};
+use crate::public_params::PublicParams;
+use crate::signature::VerificationKey;
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::One;
+use ark_std::ops::{Add, Neg};
+
+/// Progress report from a call to `BatchVerifier::verify_partial`
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartialResult {
+    /// Some presentations are still waiting to be processed
+    Pending { processed: usize, remaining: usize },
+    /// Every Schnorr proof passed, `finalize` runs the accumulated pairing check
+    Ready,
+    /// The presentation at this index has an invalid proof or an identity signature
+    Failed(usize),
+}
+
+/// Resumable batch verifier for presentations from a single issuer
+/// Schnorr proofs are checked eagerly, pairing equations are accumulated and checked once in `finalize`
+/// Every equation is scaled by its own weight squeezed from a transcript over the whole batch,
+/// so errors in different presentations or equations can't cancel out
+pub struct BatchVerifier<'a, E: Pairing> {
+    pp: &'a PublicParams<E>,
+    vk: &'a VerificationKey<E>,
+    presentations: &'a [ShowCredential<E>],
+    next: usize,
+    check: PairingCheck<E>,
+    weights: Transcript,
+    failed: Option<usize>,
+}
+
+impl<'a, E: Pairing> BatchVerifier<'a, E> {
+    pub fn new(
+        pp: &'a PublicParams<E>,
+        vk: &'a VerificationKey<E>,
+        presentations: &'a [ShowCredential<E>],
+    ) -> Self {
+        let mut weights = Transcript::new(b"mimc-abc-batch-verifier");
+        weights.append_serializable(b"vk_tilde", &vk.vk_tilde);
+        for presentation in presentations {
+            let signature = &presentation.randomized_signature;
+            let commitment = &presentation.randomized_commitment;
+            weights.append_serializable(b"sigma1", &signature.sigma1);
+            weights.append_serializable(b"sigma2", &signature.sigma2);
+            weights.append_serializable(b"cm", &commitment.cm);
+            weights.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+        }
+        Self {
+            pp,
+            vk,
+            presentations,
+            next: 0,
+            check: PairingCheck::<E>::new(),
+            weights,
+            failed: None,
+        }
+    }
+
+    /// Process up to `max_items` presentations, can be called repeatedly until `Ready`
+    pub fn verify_partial(&mut self, max_items: usize) -> PartialResult {
+        if let Some(index) = self.failed {
+            return PartialResult::Failed(index);
+        }
+
+        let end = self
+            .next
+            .saturating_add(max_items)
+            .min(self.presentations.len());
+        while self.next < end {
+            let presentation = &self.presentations[self.next];
+            if presentation.randomized_signature.sigma1.is_zero()
+                || !presentation.verify_bound_proof(self.pp)
+            {
+                self.failed = Some(self.next);
+                return PartialResult::Failed(self.next);
+            }
+            self.accumulate(presentation);
+            self.next += 1;
+        }
+
+        let remaining = self.presentations.len() - self.next;
+        if remaining == 0 {
+            PartialResult::Ready
+        } else {
+            PartialResult::Pending {
+                processed: self.next,
+                remaining,
+            }
+        }
+    }
+
+    /// Process anything left and run the single final pairing check
+    pub fn finalize(mut self) -> bool {
+        match self.verify_partial(usize::MAX) {
+            PartialResult::Ready => self.check.verify(),
+            _ => false,
+        }
+    }
+
+    // Add the signature and commitment consistency equations to the running check, each
+    // with its own weight
+    fn accumulate(&mut self, presentation: &ShowCredential<E>) {
+        let signature = &presentation.randomized_signature;
+        let commitment = &presentation.randomized_commitment;
+        let vk_plus_cm_tilde = self.vk.vk_tilde.add(commitment.cm_tilde).into_affine();
+        let sig_weight = nonzero_scalar(|| self.weights.challenge_scalar(b"sig_weight"));
+        let cm_weight = nonzero_scalar(|| self.weights.challenge_scalar(b"cm_weight"));
+
+        let sig_check = PairingCheck::<E>::scaled(
+            sig_weight,
+            &[
+                (&signature.sigma2, &self.pp.g_tilde),
+                (
+                    &signature.sigma1.into_group().neg().into_affine(),
+                    &vk_plus_cm_tilde,
+                ),
+            ],
+            &E::TargetField::one(),
+        );
+        let cm_check = PairingCheck::<E>::scaled(
+            cm_weight,
+            &[
+                (&commitment.cm, &self.pp.g_tilde),
+                (
+                    &self.pp.g.into_group().neg().into_affine(),
+                    &commitment.cm_tilde,
+                ),
+            ],
+            &E::TargetField::one(),
+        );
+
+        self.check.merge(&sig_check);
+        self.check.merge(&cm_check);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credential::Credential;
+    use crate::protocol::MimcAbc;
+    use ark_bls12_381::{Bls12_381, Fr, G1Affine};
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_partial_verification_matches_full() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let mut presentations = Vec::new();
+        for _ in 0..5 {
+            let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+            let r = Fr::rand(&mut rng);
+            let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+            let proof = credential.prove_commitment(&protocol.pp, &mut rng);
+            let signature = protocol.issue(&proof, &sk, &mut rng).unwrap();
+            credential.add_signature(signature).unwrap();
+            presentations.push(protocol.show(&credential, &mut rng).unwrap());
+        }
+
+        // All at once
+        let all_at_once = BatchVerifier::new(&protocol.pp, &vk, &presentations).finalize();
+        assert!(all_at_once, "Batch should verify");
+
+        // In two chunks
+        let mut verifier = BatchVerifier::new(&protocol.pp, &vk, &presentations);
+        assert_eq!(
+            verifier.verify_partial(3),
+            PartialResult::Pending {
+                processed: 3,
+                remaining: 2
+            }
+        );
+        assert_eq!(verifier.verify_partial(3), PartialResult::Ready);
+        assert_eq!(verifier.finalize(), all_at_once);
+
+        // A bad proof is reported with its index and sticks
+        let mut bad = presentations.clone();
+        bad[3].proof.responses[0] += Fr::from(1u64);
+        let mut verifier = BatchVerifier::new(&protocol.pp, &vk, &bad);
+        assert!(matches!(
+            verifier.verify_partial(2),
+            PartialResult::Pending { .. }
+        ));
+        assert_eq!(verifier.verify_partial(2), PartialResult::Failed(3));
+        assert_eq!(verifier.verify_partial(2), PartialResult::Failed(3));
+        assert!(!verifier.finalize());
+    }
+
+    #[test]
+    fn test_rejects_cancelling_and_unbound_presentations() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let mut presentations = Vec::new();
+        for _ in 0..2 {
+            let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+            let mut credential =
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+            let proof = credential.prove_commitment(&protocol.pp, &mut rng);
+            let signature = protocol.issue(&proof, &sk, &mut rng).unwrap();
+            credential.add_signature(signature).unwrap();
+            presentations.push(protocol.show(&credential, &mut rng).unwrap());
+        }
+        assert!(BatchVerifier::new(&protocol.pp, &vk, &presentations).finalize());
+
+        // sigma2 + D and sigma2 - D: each presentation is invalid, their errors cancel
+        // under equal weights
+        let shift = G1Affine::rand(&mut rng);
+        let mut shifted = presentations.clone();
+        let sigma2 = &mut shifted[0].randomized_signature.sigma2;
+        *sigma2 = (*sigma2 + shift).into_affine();
+        let sigma2 = &mut shifted[1].randomized_signature.sigma2;
+        *sigma2 = (*sigma2 - shift).into_affine();
+        assert!(!shifted[0].verify(&protocol.pp, &vk));
+        assert!(!shifted[1].verify(&protocol.pp, &vk));
+        assert!(!BatchVerifier::new(&protocol.pp, &vk, &shifted).finalize());
+
+        // Another holder's captured signature and commitment next to one's own valid proof
+        let mut replayed = presentations.clone();
+        replayed[1].randomized_signature = presentations[0].randomized_signature.clone();
+        replayed[1].randomized_commitment = presentations[0].randomized_commitment.clone();
+        let mut verifier = BatchVerifier::new(&protocol.pp, &vk, &replayed);
+        assert_eq!(verifier.verify_partial(2), PartialResult::Failed(1));
+    }
+}
@@ -1,9 +1,15 @@
 use crate::public_params::PublicParams;
+use crate::schnorr::SchnorrProtocol;
+use crate::transcript::Transcript;
 use ark_ec::pairing::Pairing;
-use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::{Add, Mul};
+use ark_std::rand::Rng;
+use std::collections::{BTreeMap, BTreeSet};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct Commitment<E: Pairing> {
     pub cm: E::G1Affine,
     pub cm_tilde: E::G2Affine,
@@ -16,8 +22,61 @@ impl<E: Pairing> Commitment<E> {
             cm_tilde: self.cm_tilde.add(pp.g_tilde.mul(delta_r)).into_affine(),
         }
     }
+
+    /// e(cm, g_tilde) == e(g, cm_tilde): both halves commit to the same opening
+    pub fn verify_consistency(&self, pp: &PublicParams<E>) -> bool {
+        E::pairing(self.cm, pp.g_tilde) == E::pairing(pp.g, self.cm_tilde)
+    }
+
+    /// cm - sum(m_i * g_i) over the disclosed attributes
+    /// If every disclosed m_i is the committed value, what remains commits to the hidden
+    /// attributes and r alone, so a proof over the hidden bases verifies against it;
+    /// a disclosed value that doesn't fit leaves a point no such proof opens.
+    /// Indices must be below `pp.n`.
+    pub fn subtract_disclosed(
+        &self,
+        pp: &PublicParams<E>,
+        disclosed: &BTreeMap<usize, E::ScalarField>,
+    ) -> E::G1Affine {
+        let bases: Vec<E::G1Affine> = disclosed.keys().map(|&i| pp.ck[i]).collect();
+        let values: Vec<E::ScalarField> = disclosed.values().copied().collect();
+        (self.cm.into_group() - E::G1::msm_unchecked(&bases, &values)).into_affine()
+    }
+
+    /// This commitment with `values[i]` added to attribute i, in both groups.
+    /// The issuer of `MimcAbc::issue_blind` writes its attributes into slots the holder
+    /// left at zero; the randomizer is unchanged. Indices must be below `pp.n`.
+    pub fn add_attributes(&self, pp: &PublicParams<E>, values: &[(usize, E::ScalarField)]) -> Self {
+        let ck: Vec<E::G1Affine> = values.iter().map(|(i, _)| pp.ck[*i]).collect();
+        let ck_tilde: Vec<E::G2Affine> = values.iter().map(|(i, _)| pp.ck_tilde[*i]).collect();
+        let scalars: Vec<E::ScalarField> = values.iter().map(|(_, v)| *v).collect();
+        Self {
+            cm: (self.cm.into_group() + E::G1::msm_unchecked(&ck, &scalars)).into_affine(),
+            cm_tilde: (self.cm_tilde.into_group() + E::G2::msm_unchecked(&ck_tilde, &scalars))
+                .into_affine(),
+        }
+    }
+
+    /// The part of this commitment carrying the attributes at `indices`:
+    /// sum(values_i * g_i) and sum(values_i * g_tilde_i), with no randomizer.
+    /// A verifier can log it as an anchor for what was disclosed; `SubsetProof` ties it
+    /// to the full commitment.
+    pub fn subset_commitment(
+        &self,
+        pp: &PublicParams<E>,
+        indices: &[usize],
+        values: &[E::ScalarField],
+    ) -> Commitment<E> {
+        let ck: Vec<E::G1Affine> = indices.iter().map(|&i| pp.ck[i]).collect();
+        let ck_tilde: Vec<E::G2Affine> = indices.iter().map(|&i| pp.ck_tilde[i]).collect();
+        Commitment {
+            cm: E::G1::msm_unchecked(&ck, values).into_affine(),
+            cm_tilde: E::G2::msm_unchecked(&ck_tilde, values).into_affine(),
+        }
+    }
 }
 
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct CommitmentKey<E: Pairing> {
     pub ck: Vec<E::G1Affine>,
     pub ck_tilde: Vec<E::G2Affine>,
@@ -38,4 +97,203 @@ impl<E: Pairing> CommitmentKey<E> {
             .into_affine();
         Commitment { cm, cm_tilde }
     }
+
+    // Commitment to no messages, (g^r, g_tilde^r), for use as a nonce
+    pub fn commit_nonce(&self, pp: &PublicParams<E>, r: &E::ScalarField) -> Commitment<E> {
+        Commitment {
+            cm: pp.g.mul(r).into_affine(),
+            cm_tilde: pp.g_tilde.mul(r).into_affine(),
+        }
+    }
+}
+
+/// Schnorr proof of knowledge of r in a nonce commitment g^r
+#[derive(Debug, Clone)]
+pub struct NonceProof<E: Pairing> {
+    pub announcement: E::G1Affine,
+    pub response: E::ScalarField,
+}
+
+impl<E: Pairing> NonceProof<E> {
+    pub fn prove(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        r: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let blinding = E::ScalarField::rand(rng);
+        let announcement = pp.g.mul(blinding).into_affine();
+        let challenge = Self::challenge(commitment, &announcement);
+        Self {
+            announcement,
+            response: blinding + challenge * r,
+        }
+    }
+
+    // Checks knowledge of r for cm; cm_tilde is covered by the consistency pairing
+    pub fn verify(&self, pp: &PublicParams<E>, commitment: &Commitment<E>) -> bool {
+        let challenge = Self::challenge(commitment, &self.announcement);
+        pp.g.mul(self.response) == self.announcement.add(commitment.cm.mul(challenge))
+    }
+
+    fn challenge(commitment: &Commitment<E>, announcement: &E::G1Affine) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-nonce-commitment");
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+        transcript.append_serializable(b"announcement", announcement);
+        transcript.challenge_scalar(b"c")
+    }
+}
+
+/// Proof that a full commitment is a subset commitment plus a commitment to the other
+/// attributes and r, i.e. knowledge of an opening of cm - subset over the hidden bases
+#[derive(Debug, Clone)]
+pub struct SubsetProof<E: Pairing> {
+    pub announcement: E::G1Affine,
+    pub responses: Vec<E::ScalarField>, // hidden attributes in index order, then r
+}
+
+impl<E: Pairing> SubsetProof<E> {
+    pub fn prove(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        indices: &[usize],
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let subset = commitment.subset_commitment(
+            pp,
+            indices,
+            &indices.iter().map(|&i| messages[i]).collect::<Vec<_>>(),
+        );
+        let mut exponents: Vec<E::ScalarField> = (0..messages.len())
+            .filter(|i| !indices.contains(i))
+            .map(|i| messages[i])
+            .collect();
+        exponents.push(*r);
+
+        let opening = SchnorrProtocol::commit(&Self::hidden_bases(pp, indices), rng);
+        let challenge = Self::challenge(commitment, &subset, indices, &opening.commited_blindings);
+        Self {
+            announcement: opening.commited_blindings,
+            responses: SchnorrProtocol::prove(&opening, &exponents, &challenge).0,
+        }
+    }
+
+    /// Check `subset` is the part of `commitment` carrying the attributes at `indices`
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        indices: &[usize],
+        subset: &Commitment<E>,
+    ) -> bool {
+        let distinct: BTreeSet<usize> = indices.iter().copied().collect();
+        if distinct.len() != indices.len() || distinct.iter().any(|&i| i >= pp.n) {
+            return false;
+        }
+        let bases = Self::hidden_bases(pp, indices);
+        if self.responses.len() != bases.len() || !subset.verify_consistency(pp) {
+            return false;
+        }
+        let challenge = Self::challenge(commitment, subset, indices, &self.announcement);
+        let statement = (commitment.cm.into_group() - subset.cm).into_affine();
+        SchnorrProtocol::verify_schnorr(
+            &bases,
+            &statement,
+            &self.announcement,
+            &self.responses,
+            &challenge,
+        )
+    }
+
+    // Bases of the attributes outside `indices`, then g for r
+    fn hidden_bases(pp: &PublicParams<E>, indices: &[usize]) -> Vec<E::G1Affine> {
+        let mut bases: Vec<E::G1Affine> = (0..pp.n)
+            .filter(|i| !indices.contains(i))
+            .map(|i| pp.ck[i])
+            .collect();
+        bases.push(pp.g);
+        bases
+    }
+
+    fn challenge(
+        commitment: &Commitment<E>,
+        subset: &Commitment<E>,
+        indices: &[usize],
+        announcement: &E::G1Affine,
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-subset-commitment");
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+        transcript.append_serializable(b"subset_cm", &subset.cm);
+        transcript.append_serializable(b"subset_cm_tilde", &subset.cm_tilde);
+        for &i in indices {
+            transcript.append_message(b"index", &(i as u64).to_le_bytes());
+        }
+        transcript.append_serializable(b"announcement", announcement);
+        transcript.challenge_scalar(b"c")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr, G1Projective as E1};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_nonce_commitment() {
+        let mut rng = test_rng();
+        let pp = PublicParams::<Bls12_381>::new(&4, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+
+        let r = Fr::rand(&mut rng);
+        let nonce = ck.commit_nonce(&pp, &r);
+        assert_eq!(nonce.cm, ck.commit(&pp, &[], &r).cm);
+        assert_eq!(
+            Bls12_381::pairing(nonce.cm, pp.g_tilde),
+            Bls12_381::pairing(pp.g, nonce.cm_tilde)
+        );
+
+        let proof = NonceProof::prove(&pp, &nonce, &r, &mut rng);
+        assert!(proof.verify(&pp, &nonce));
+
+        // Not a proof for a different nonce
+        let other = ck.commit_nonce(&pp, &Fr::rand(&mut rng));
+        assert!(!proof.verify(&pp, &other));
+    }
+
+    #[test]
+    fn test_subset_commitment() {
+        let mut rng = test_rng();
+        let n = 5;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let commitment = ck.commit(&pp, &messages, &r);
+
+        let indices = [3, 1];
+        let values = [messages[3], messages[1]];
+        let subset = commitment.subset_commitment(&pp, &indices, &values);
+        let proof = SubsetProof::prove(&pp, &commitment, &indices, &messages, &r, &mut rng);
+        assert!(proof.verify(&pp, &commitment, &indices, &subset));
+
+        // The subset and the hidden attributes with r add up to the full commitment
+        let hidden = [messages[0], messages[2], messages[4], r];
+        let hidden_part = E1::msm_unchecked(&SubsetProof::hidden_bases(&pp, &indices), &hidden);
+        assert_eq!((hidden_part + subset.cm).into_affine(), commitment.cm);
+
+        // Another value for a disclosed attribute isn't tied to the commitment
+        let wrong = commitment.subset_commitment(&pp, &indices, &[messages[3], Fr::rand(&mut rng)]);
+        assert!(!proof.verify(&pp, &commitment, &indices, &wrong));
+    }
 }
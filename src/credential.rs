@@ -1,9 +1,26 @@
 use crate::commitment::{Commitment, CommitmentKey};
+use crate::error::Error;
+use crate::issuer_or::IssuerOrProof;
+use crate::nullifier::NullifierProof;
+use crate::pairing::nonzero_scalar;
+use crate::presentation::DisclosureRequest;
+use crate::projection::{projected_key, LinkProof};
 use crate::proof::CommitmentProof;
 use crate::public_params::PublicParams;
+use crate::range_proof::AttributeRangeProof;
+use crate::reissue::valid_changes;
+use crate::serialization::{read_version, write_version};
 use crate::signature::{Signature, VerificationKey};
 use ark_ec::pairing::Pairing;
-use ark_std::rand::Rng;
+use ark_ec::AffineRepr;
+use ark_ff::{UniformRand, Zero};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
+use ark_std::rand::{Rng, RngCore};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum CredentialState {
@@ -13,12 +30,18 @@ pub enum CredentialState {
     Randomized,  // Has been shown/randomized
 }
 
+// Randomized signature, randomized commitment and the commitment's new randomness
+pub(crate) type Randomized<E> = (Signature<E>, Commitment<E>, <E as Pairing>::ScalarField);
+
+#[derive(Clone)]
 pub struct Credential<E: Pairing> {
     pub commitment: Commitment<E>,
     messages: Vec<E::ScalarField>,
     r: E::ScalarField,
     pub signature: Option<Signature<E>>,
     state: CredentialState,
+    tag: Option<Vec<u8>>, // Cleartext metadata (type, version) carried in every proof
+    id_index: usize,      // Attribute holding the user ID, used to link credentials
 }
 
 impl<E: Pairing> Credential<E> {
@@ -36,18 +59,144 @@ impl<E: Pairing> Credential<E> {
             r,
             signature: None,
             state: CredentialState::Committed,
+            tag: None,
+            id_index: 0,
         }
     }
 
+    // Attach a cleartext tag; it's bound into each proof's challenge, so nobody can re-tag a
+    // presentation in transit. Verifiers check it with `ShowCredential::verify_tagged`
+    pub fn with_tag(mut self, tag: &[u8]) -> Self {
+        self.tag = Some(tag.to_vec());
+        self
+    }
+
+    pub fn get_tag(&self) -> Option<&[u8]> {
+        self.tag.as_deref()
+    }
+
+    // Keep the user ID at `index` instead of the first attribute
+    // Errors if there is no attribute at `index`
+    pub fn with_id_index(mut self, index: usize) -> Result<Self, Error> {
+        if index >= self.messages.len() {
+            return Err(Error::Other(format!("ID index {} out of range", index)));
+        }
+        self.id_index = index;
+        Ok(self)
+    }
+
+    pub fn id_index(&self) -> usize {
+        self.id_index
+    }
+
     // Method for creating proof for issuance
     pub fn prove_commitment(&self, pp: &PublicParams<E>, rng: &mut impl Rng) -> CommitmentProof<E> {
-        CommitmentProof::prove(pp, &self.commitment, &self.messages, &self.r, rng)
+        CommitmentProof::prove_with_tag(
+            pp,
+            &self.commitment,
+            &self.messages,
+            &self.r,
+            self.get_tag(),
+            rng,
+        )
+    }
+
+    // Issuance proof revealing the attributes at `disclosed` to the issuer
+    pub fn prove_commitment_disclosing(
+        &self,
+        pp: &PublicParams<E>,
+        disclosed: &[usize],
+        rng: &mut impl Rng,
+    ) -> CommitmentProof<E> {
+        CommitmentProof::prove_disclosing(
+            pp,
+            &self.commitment,
+            &self.messages,
+            &self.r,
+            self.get_tag(),
+            disclosed,
+            rng,
+        )
     }
 
     // Add signature after issuance
-    pub fn add_signature(&mut self, signature: Signature<E>) {
+    // Only a Committed credential takes one, so a credential can't be signed twice
+    pub fn add_signature(&mut self, signature: Signature<E>) -> Result<(), Error> {
+        if self.state != CredentialState::Committed {
+            return Err(Error::InvalidCredentialState {
+                expected: CredentialState::Committed,
+                actual: self.state.clone(),
+            });
+        }
         self.signature = Some(signature);
         self.state = CredentialState::Signed;
+        Ok(())
+    }
+
+    pub fn state(&self) -> &CredentialState {
+        &self.state
+    }
+
+    // Finish blind issuance: fill in the issuer's attributes and attach its signature
+    // The slots must still be zero, see `MimcAbc::issue_blind`
+    pub fn complete_credential(
+        &mut self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        issuer_attrs: &[(usize, E::ScalarField)],
+        signature: Signature<E>,
+    ) -> Result<(), Error> {
+        if self.state != CredentialState::Committed {
+            return Err(Error::InvalidCredentialState {
+                expected: CredentialState::Committed,
+                actual: self.state.clone(),
+            });
+        }
+        if !valid_changes(self.messages.len(), issuer_attrs)
+            || issuer_attrs
+                .iter()
+                .any(|(i, _)| !self.messages[*i].is_zero())
+        {
+            return Err(Error::Other(
+                "Issuer attributes must fill distinct zero slots".to_string(),
+            ));
+        }
+        let commitment = self.commitment.add_attributes(pp, issuer_attrs);
+        if !vk.verify(&signature, &commitment, pp) {
+            return Err(Error::SignatureVerificationFailed);
+        }
+        for (i, v) in issuer_attrs {
+            self.messages[*i] = *v;
+        }
+        self.commitment = commitment;
+        self.add_signature(signature)
+    }
+
+    // Replace attribute `index` with `new_value` and rerandomize by `delta_r`, updating the
+    // commitment in place: the old value's share is swapped for the new one's. The old
+    // signature no longer fits, so it's dropped and the credential must be issued again.
+    pub fn update_attribute(
+        &mut self,
+        pp: &PublicParams<E>,
+        index: usize,
+        new_value: E::ScalarField,
+        delta_r: &E::ScalarField,
+    ) -> Result<(), Error> {
+        if index >= self.messages.len() {
+            return Err(Error::Other(format!(
+                "Attribute index {} out of range",
+                index
+            )));
+        }
+        self.commitment = self
+            .commitment
+            .add_attributes(pp, &[(index, new_value - self.messages[index])])
+            .randomize(pp, delta_r);
+        self.messages[index] = new_value;
+        self.r += delta_r;
+        self.signature = None;
+        self.state = CredentialState::Committed;
+        Ok(())
     }
 
     // get messages from credential
@@ -55,54 +204,584 @@ impl<E: Pairing> Credential<E> {
         &self.messages
     }
 
-    // Randomize credential for showing
+    pub(crate) fn get_r(&self) -> &E::ScalarField {
+        &self.r
+    }
+
+    // Split off a credential over the user ID and the attributes at `indices`
+    // The projection is re-committed under fresh randomness with no issuer involved, so it
+    // carries no signature and means nothing alone. The LinkProof ties it to this signed
+    // credential; it shows all n attributes' responses, so it's no smaller than a show
+    pub fn project(
+        &self,
+        indices: &[usize],
+        pp: &PublicParams<E>,
+        rng: &mut impl Rng,
+    ) -> Result<(Credential<E>, LinkProof<E>), Error> {
+        // Slot 0 of the projection always carries the user ID
+        let mut slots = vec![self.id_index];
+        for &index in indices {
+            if index >= self.messages.len() {
+                return Err(Error::Other(format!(
+                    "Projected index {} out of range",
+                    index
+                )));
+            }
+            if slots.contains(&index) {
+                if index == self.id_index {
+                    continue;
+                }
+                return Err(Error::Other(format!(
+                    "Projected index {} is repeated",
+                    index
+                )));
+            }
+            slots.push(index);
+        }
+
+        let messages: Vec<E::ScalarField> = slots.iter().map(|&i| self.messages[i]).collect();
+        let ck = projected_key(pp, slots.len());
+        let mut projected = Credential::new(&ck, pp, &messages, E::ScalarField::rand(rng));
+        projected.tag = self.tag.clone();
+
+        let link = LinkProof::prove(pp, self, &projected, &slots, rng)?;
+        Ok((projected, link))
+    }
+
+    // Randomize credential for showing with fresh non-zero deltas
+    // Errors if the credential isn't signed, like `show`
+    pub fn show_auto(
+        &self,
+        pp: &PublicParams<E>,
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        let delta_r = nonzero_scalar(|| E::ScalarField::rand(rng));
+        let delta_u = nonzero_scalar(|| E::ScalarField::rand(rng));
+        self.show_in_context(pp, &delta_r, &delta_u, None, pp.n, rng)
+    }
+
+    // Show with all randomness (deltas and proof blindings) derived from the holder's secret r
+    // and `counter`, so retrying a show with the same counter sends the same presentation.
+    // The counter must increase monotonically across shows, persisted across restarts:
+    // presentations for distinct counters are unlinkable, reusing one links them.
+    pub fn show_counter(
+        &self,
+        pp: &PublicParams<E>,
+        counter: u64,
+    ) -> Result<ShowCredential<E>, Error> {
+        let mut secret = Vec::new();
+        self.r
+            .serialize_compressed(&mut secret)
+            .and_then(|_| self.messages.serialize_compressed(&mut secret))
+            .expect("serializing into a Vec cannot fail");
+        let seed = Sha256::new()
+            .chain_update(b"mimc-abc-show-counter")
+            .chain_update(&secret)
+            .chain_update(counter.to_le_bytes())
+            .finalize();
+        self.show_auto(pp, &mut ShowPrf::new(seed.into()))
+    }
+
+    // Randomize credential for showing, erroring if it isn't signed
+    // Caller-chosen deltas must be fresh and non-zero: a zero delta_u gives an identity sigma1,
+    // a reused pair links presentations
+    #[deprecated(note = "use `show_auto`, which samples the deltas")]
     pub fn show(
         &self,
         pp: &PublicParams<E>,
         delta_r: &E::ScalarField,
         delta_u: &E::ScalarField,
         rng: &mut impl Rng,
-    ) -> ShowCredential<E> {
-        // Only allow randomization if credential is signed
-        if self.state != CredentialState::Signed || self.signature.is_none() {
-            panic!("Cannot randomize unsigned credential");
-        }
+    ) -> Result<ShowCredential<E>, Error> {
+        self.show_in_context(pp, delta_r, delta_u, None, pp.n, rng)
+    }
 
-        // Create randomized commitment
-        let new_r = self.r + delta_r;
+    // Show the credential with its presentation time bound into the proof challenge
+    // The verifier accepts it within a sliding window, see `ShowCredential::verify_fresh`
+    pub fn show_at(
+        &self,
+        pp: &PublicParams<E>,
+        presented_at: u64,
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        let delta_r = nonzero_scalar(|| E::ScalarField::rand(rng));
+        let delta_u = nonzero_scalar(|| E::ScalarField::rand(rng));
+        self.show_in_context(pp, &delta_r, &delta_u, Some(presented_at), pp.n, rng)
+    }
 
-        // Randomize signature
-        let randomized_signature = self.signature.as_ref().unwrap().randomize(delta_r, delta_u);
+    // Show bound to a verifier-supplied session nonce, folded into the proof challenge
+    // It only verifies with `ShowCredential::verify_with_nonce` under the same nonce, so a
+    // presentation captured in one session is rejected in the next
+    pub fn show_with_nonce(
+        &self,
+        pp: &PublicParams<E>,
+        nonce: &[u8],
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        let delta_r = nonzero_scalar(|| E::ScalarField::rand(rng));
+        let delta_u = nonzero_scalar(|| E::ScalarField::rand(rng));
+        let (randomized_signature, randomized_commitment, new_r) =
+            self.randomize(pp, &delta_r, &delta_u)?;
+        let proof = CommitmentProof::prove_with_context(
+            pp,
+            &randomized_commitment,
+            &self.messages,
+            &new_r,
+            self.get_tag(),
+            &nonce_context(nonce),
+            rng,
+        );
+        Ok(ShowCredential {
+            randomized_signature,
+            randomized_commitment,
+            proof,
+            r_new: new_r,
+            issuer_or_proof: None,
+            nullifier_proof: None,
+            presented_at: None,
+            disclosed: BTreeMap::new(),
+            range_proofs: BTreeMap::new(),
+        })
+    }
 
-        let randomized_commitment = self.commitment.randomize(pp, delta_r);
+    // Show with the proof padded to `pad_to` attributes, hiding this credential's attribute
+    // count among all credentials with at most `pad_to`; verifies like any other show
+    pub fn show_padded(
+        &self,
+        pp: &PublicParams<E>,
+        pad_to: usize,
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        if pad_to < self.messages.len() {
+            return Err(Error::Other(format!(
+                "Can't pad {} attributes down to {}",
+                self.messages.len(),
+                pad_to
+            )));
+        }
+        let delta_r = nonzero_scalar(|| E::ScalarField::rand(rng));
+        let delta_u = nonzero_scalar(|| E::ScalarField::rand(rng));
+        self.show_in_context(pp, &delta_r, &delta_u, None, pad_to, rng)
+    }
+
+    fn show_in_context(
+        &self,
+        pp: &PublicParams<E>,
+        delta_r: &E::ScalarField,
+        delta_u: &E::ScalarField,
+        presented_at: Option<u64>,
+        pad_to: usize,
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        // Only signed credentials can be randomized
+        let (randomized_signature, randomized_commitment, new_r) =
+            self.randomize(pp, delta_r, delta_u)?;
 
         // Create proof for randomized credential
-        let proof =
-            CommitmentProof::prove(&pp, &randomized_commitment, &self.messages, &new_r, rng);
+        let proof = CommitmentProof::prove_padded(
+            pp,
+            &randomized_commitment,
+            &self.messages,
+            &new_r,
+            self.get_tag(),
+            &presentation_context(presented_at),
+            pad_to,
+            rng,
+        );
 
         // Return presentation object
-        ShowCredential {
+        Ok(ShowCredential {
             randomized_signature,
             randomized_commitment,
             proof,
             r_new: new_r,
+            issuer_or_proof: None,
+            nullifier_proof: None,
+            presented_at,
+            disclosed: BTreeMap::new(),
+            range_proofs: BTreeMap::new(),
+        })
+    }
+
+    // Randomize signature and commitment, returning them with the new commitment randomness
+    pub(crate) fn randomize(
+        &self,
+        pp: &PublicParams<E>,
+        delta_r: &E::ScalarField,
+        delta_u: &E::ScalarField,
+    ) -> Result<Randomized<E>, Error> {
+        let signature = self.signature.as_ref().ok_or(Error::MissingSignature)?;
+        if self.state != CredentialState::Signed {
+            return Err(Error::InvalidCredentialState {
+                expected: CredentialState::Signed,
+                actual: self.state.clone(),
+            });
+        }
+        Ok((
+            signature.randomize(delta_r, delta_u),
+            self.commitment.randomize(pp, delta_r),
+            self.r + delta_r,
+        ))
+    }
+
+    // Show the credential proving it was signed by one of `candidate_vks` without revealing which
+    pub fn show_issuer_or(
+        &self,
+        pp: &PublicParams<E>,
+        candidate_vks: &[&VerificationKey<E>],
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        let signature = self.signature.as_ref().ok_or(Error::MissingSignature)?;
+
+        // The holder needs to know which candidate actually signed
+        let real_index = candidate_vks
+            .iter()
+            .position(|vk| vk.verify_with_pairing_checker(signature, &self.commitment, pp))
+            .ok_or_else(|| {
+                Error::Other("Credential issuer is not among the candidate keys".to_string())
+            })?;
+
+        let mut presentation = self.show_auto(pp, rng)?;
+
+        let (blinded_signature, issuer_proof) = IssuerOrProof::prove(
+            pp,
+            &presentation.randomized_signature,
+            &presentation.randomized_commitment,
+            candidate_vks,
+            real_index,
+            rng,
+        )?;
+        presentation.randomized_signature = blinded_signature;
+        presentation.issuer_or_proof = Some(issuer_proof);
+        Ok(presentation)
+    }
+
+    // Show the credential with a one-time nullifier for `context`
+    // Showing twice in the same context gives the same nullifier, so a verifier can spot reuse
+    pub fn show_with_nullifier(
+        &self,
+        pp: &PublicParams<E>,
+        context: &[u8],
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        let mut presentation = self.show_auto(pp, rng)?;
+        presentation.nullifier_proof = Some(NullifierProof::prove(
+            pp,
+            &presentation.randomized_commitment,
+            &self.messages,
+            &presentation.r_new,
+            self.id_index,
+            context,
+            rng,
+        )?);
+        Ok(presentation)
+    }
+
+    // Show with range proofs on hidden attributes, one per (index, min, max)
+    // Errors if an attribute is out of its range or doesn't decode to a u64
+    pub fn show_with_ranges(
+        &self,
+        pp: &PublicParams<E>,
+        ranges: &[(usize, u64, u64)],
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        let mut presentation = self.show_auto(pp, rng)?;
+        for &(index, min, max) in ranges {
+            let proof = AttributeRangeProof::prove(
+                pp,
+                &presentation.randomized_commitment,
+                &self.messages,
+                &presentation.r_new,
+                index,
+                min,
+                max,
+                rng,
+            )?;
+            if presentation.range_proofs.insert(index, proof).is_some() {
+                return Err(Error::Other(format!(
+                    "Attribute {} has more than one range",
+                    index
+                )));
+            }
+        }
+        Ok(presentation)
+    }
+
+    // Show proving the expiry timestamp at `expiry_index` hasn't passed at `now` without
+    // revealing it: expiry - now is proven to lie in [0, 2^max_bits), as a range proof over
+    // [now, now + 2^max_bits - 1]. Errors if the credential has expired or expires further
+    // out than `max_bits` covers. See `ShowCredential::verify_not_expired`
+    pub fn prove_not_expired(
+        &self,
+        pp: &PublicParams<E>,
+        expiry_index: usize,
+        now: u64,
+        max_bits: u32,
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        if max_bits == 0 || max_bits > 64 {
+            return Err(Error::Other(format!(
+                "max_bits must be between 1 and 64, got {}",
+                max_bits
+            )));
+        }
+        let span = u64::MAX >> (64 - max_bits);
+        self.show_with_ranges(pp, &[(expiry_index, now, now.saturating_add(span))], rng)
+    }
+
+    // Show revealing the plaintext attributes at `disclosed_indices`, proving knowledge of the
+    // rest. The values travel in the presentation's `disclosed` map and `verify` checks the
+    // proof opens them. Caller-chosen deltas must be fresh and non-zero, as for `show`
+    pub fn show_with_disclosure(
+        &self,
+        pp: &PublicParams<E>,
+        disclosed_indices: &[usize],
+        delta_r: &E::ScalarField,
+        delta_u: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        self.show_disclosing(pp, disclosed_indices, delta_r, delta_u, &[], rng)
+    }
+
+    // Show proving attribute `index` equals the public `expected`, the rest stays hidden
+    // Errors if it doesn't; check with `ShowCredential::verify_attribute_equals`
+    pub fn prove_attribute_equals(
+        &self,
+        pp: &PublicParams<E>,
+        index: usize,
+        expected: E::ScalarField,
+        delta_r: &E::ScalarField,
+        delta_u: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        match self.messages.get(index) {
+            Some(value) if *value == expected => {}
+            Some(_) => {
+                return Err(Error::Other(format!(
+                    "Attribute {} doesn't equal the expected value",
+                    index
+                )))
+            }
+            None => return Err(Error::Other(format!("Attribute {} is out of range", index))),
+        }
+        self.show_with_disclosure(pp, &[index], delta_r, delta_u, rng)
+    }
+
+    // Show revealing the attributes the verifier asked for, with its nonce bound into the proof
+    // Verify the response with `DisclosureRequest::verify`
+    pub fn respond_to_disclosure_request(
+        &self,
+        pp: &PublicParams<E>,
+        request: &DisclosureRequest,
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        let delta_r = nonzero_scalar(|| E::ScalarField::rand(rng));
+        let delta_u = nonzero_scalar(|| E::ScalarField::rand(rng));
+        self.show_disclosing(
+            pp,
+            &request.indices,
+            &delta_r,
+            &delta_u,
+            &request.context(),
+            rng,
+        )
+    }
+
+    fn show_disclosing(
+        &self,
+        pp: &PublicParams<E>,
+        indices: &[usize],
+        delta_r: &E::ScalarField,
+        delta_u: &E::ScalarField,
+        context: &[u8],
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        if let Some(&index) = indices.iter().find(|&&i| i >= self.messages.len()) {
+            return Err(Error::Other(format!(
+                "Requested attribute {} is out of range",
+                index
+            )));
         }
+        let (randomized_signature, randomized_commitment, r_new) =
+            self.randomize(pp, delta_r, delta_u)?;
+        let proof = CommitmentProof::prove_disclosing_with_context(
+            pp,
+            &randomized_commitment,
+            &self.messages,
+            &r_new,
+            self.get_tag(),
+            indices,
+            context,
+            rng,
+        );
+        Ok(ShowCredential {
+            randomized_signature,
+            randomized_commitment,
+            proof,
+            r_new,
+            issuer_or_proof: None,
+            nullifier_proof: None,
+            presented_at: None,
+            disclosed: indices.iter().map(|&i| (i, self.messages[i])).collect(),
+            range_proofs: BTreeMap::new(),
+        })
+    }
+
+    // Compressed size of an untagged signed credential with n attributes: version byte,
+    // commitment, flagged signature, the length-prefixed messages, r, state, tag flag and
+    // ID index. Depends only on the curve, not on a credential
+    pub fn size_for(n: usize) -> usize {
+        let (g1, g2, scalar) = element_sizes::<E>();
+        1 + (g1 + g2) + (1 + 2 * g1) + (8 + n * scalar) + scalar + 1 + 1 + 8
     }
 
     // Get user ID (useful for many applications)
     pub fn get_user_id(&self) -> &E::ScalarField {
-        &self.messages[0]
+        &self.messages[self.id_index]
     }
 
     // Verify signature directly on the credential
     pub fn verify(&self, pp: &PublicParams<E>, vk: &VerificationKey<E>) -> bool {
         if let Some(sig) = &self.signature {
             // vk.verify(sig, &self.commitment, &pp)
-            vk.verify_with_pairing_checker(sig, &self.commitment, &pp)
+            vk.verify_with_pairing_checker(sig, &self.commitment, pp)
         } else {
             false
         }
     }
+
+    // Index of the first issuer in the lists whose key verifies the credential, or None
+    // The lists pair up parameters and keys, entries past the shorter one are ignored
+    pub fn verify_any(
+        &self,
+        pp_list: &[&PublicParams<E>],
+        vk_list: &[&VerificationKey<E>],
+    ) -> Option<usize> {
+        pp_list
+            .iter()
+            .zip(vk_list)
+            .position(|(pp, vk)| self.verify(pp, vk))
+    }
+
+    // Verify a credential signed jointly by several issuers, see `VerificationKey::aggregate`
+    pub fn aggregate_verify(&self, pp: &PublicParams<E>, keys: &[&VerificationKey<E>]) -> bool {
+        self.verify(pp, &VerificationKey::aggregate(keys))
+    }
+
+    // Verify signature directly on the credential, saying which check failed:
+    // MissingSignature, InvalidCommitment (cm and cm_tilde open differently) or
+    // SignatureVerificationFailed (e.g. the vk belongs to another issuer)
+    pub fn verify_detailed(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+    ) -> Result<(), Error> {
+        let signature = self.signature.as_ref().ok_or(Error::MissingSignature)?;
+
+        // Commitment consistency e(cm, g_tilde) == e(g, cm_tilde)
+        let cm = &self.commitment;
+        if E::pairing(cm.cm, pp.g_tilde) != E::pairing(pp.g, cm.cm_tilde) {
+            return Err(Error::InvalidCommitment);
+        }
+
+        // Signature e(sigma2, g_tilde) == e(sigma1, vk + cm_tilde)
+        if signature.sigma1.is_zero()
+            || E::pairing(signature.sigma2, pp.g_tilde)
+                != E::pairing(signature.sigma1, vk.vk_tilde + cm.cm_tilde)
+        {
+            return Err(Error::SignatureVerificationFailed);
+        }
+        Ok(())
+    }
+
+    // Index of the first (pp, vk) candidate the credential verifies under, e.g. across a
+    // key rotation that changed the commitment key
+    pub fn verify_any_params(
+        &self,
+        candidates: &[(&PublicParams<E>, &VerificationKey<E>)],
+    ) -> Option<usize> {
+        candidates
+            .iter()
+            .position(|(pp, vk)| self.verify_detailed(pp, vk).is_ok())
+    }
+
+    // Re-commit to the stored opening and check it matches before verifying the signature
+    // Catches a corrupted (messages, r) as well as a signature over a different commitment
+    pub fn verify_opening_and_signature(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+    ) -> bool {
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let recomputed = ck.commit(pp, &self.messages, &self.r);
+        if recomputed.cm != self.commitment.cm || recomputed.cm_tilde != self.commitment.cm_tilde {
+            return false;
+        }
+        self.verify(pp, vk)
+    }
+}
+
+/// Builder for a `Credential`: the user ID, if set, takes the first slot ahead of the other
+/// attributes, and r is sampled at `build` unless given
+#[derive(Clone, Debug)]
+pub struct CredentialBuilder<E: Pairing> {
+    user_id: Option<E::ScalarField>,
+    attributes: Vec<E::ScalarField>,
+    r: Option<E::ScalarField>,
+}
+
+impl<E: Pairing> Default for CredentialBuilder<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Pairing> CredentialBuilder<E> {
+    pub fn new() -> Self {
+        Self {
+            user_id: None,
+            attributes: Vec::new(),
+            r: None,
+        }
+    }
+
+    pub fn user_id(mut self, id: E::ScalarField) -> Self {
+        self.user_id = Some(id);
+        self
+    }
+
+    pub fn attributes(mut self, attributes: impl IntoIterator<Item = E::ScalarField>) -> Self {
+        self.attributes.extend(attributes);
+        self
+    }
+
+    pub fn randomness(mut self, r: E::ScalarField) -> Self {
+        self.r = Some(r);
+        self
+    }
+
+    // Commit to the attributes, erroring unless there are exactly pp.n of them
+    pub fn build(
+        self,
+        pp: &PublicParams<E>,
+        ck: &CommitmentKey<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Credential<E>, Error> {
+        let messages: Vec<E::ScalarField> =
+            self.user_id.into_iter().chain(self.attributes).collect();
+        if messages.len() != pp.n {
+            return Err(Error::Other(format!(
+                "Attribute count mismatch: parameters take {} attributes, got {}",
+                pp.n,
+                messages.len()
+            )));
+        }
+        let r = self.r.unwrap_or_else(|| E::ScalarField::rand(rng));
+        Ok(Credential::new(ck, pp, &messages, r))
+    }
 }
 
 // Presentation object for shown credentials
@@ -112,21 +791,1603 @@ pub struct ShowCredential<E: Pairing> {
     pub randomized_commitment: Commitment<E>,
     pub proof: CommitmentProof<E>,
     pub r_new: E::ScalarField,
+    pub issuer_or_proof: Option<IssuerOrProof<E>>, // Set when the issuer is hidden among candidates
+    pub nullifier_proof: Option<NullifierProof<E>>, // Set for one-time shows
+    pub presented_at: Option<u64>, // Set for timed shows, bound into the proof challenge
+    pub disclosed: BTreeMap<usize, E::ScalarField>, // Revealed attributes, zero proof blindings
+    pub range_proofs: BTreeMap<usize, AttributeRangeProof<E>>, // Hidden attributes proven in a range
+}
+
+// SHA-256 in counter mode over a per-show seed, the randomness source of `show_counter`
+// Written out rather than a library RNG so presentations stay reproducible across versions
+struct ShowPrf {
+    seed: [u8; 32],
+    block: u64,
+    buffer: [u8; 32],
+    position: usize,
+}
+
+impl ShowPrf {
+    fn new(seed: [u8; 32]) -> Self {
+        Self {
+            seed,
+            block: 0,
+            buffer: [0; 32],
+            position: 32,
+        }
+    }
+}
+
+impl RngCore for ShowPrf {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest {
+            if self.position == self.buffer.len() {
+                self.buffer = Sha256::new()
+                    .chain_update(self.seed)
+                    .chain_update(self.block.to_le_bytes())
+                    .finalize()
+                    .into();
+                self.block += 1;
+                self.position = 0;
+            }
+            *byte = self.buffer[self.position];
+            self.position += 1;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ark_std::rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+// Format version of a serialized credential
+const CREDENTIAL_VERSION: u8 = 1;
+
+impl CanonicalSerialize for CredentialState {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let tag: u8 = match self {
+            CredentialState::Initialized => 0,
+            CredentialState::Committed => 1,
+            CredentialState::Signed => 2,
+            CredentialState::Randomized => 3,
+        };
+        tag.serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, _compress: Compress) -> usize {
+        1
+    }
+}
+
+impl Valid for CredentialState {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for CredentialState {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(reader, compress, validate)? {
+            0 => Ok(CredentialState::Initialized),
+            1 => Ok(CredentialState::Committed),
+            2 => Ok(CredentialState::Signed),
+            3 => Ok(CredentialState::Randomized),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+// The whole wallet record, messages and r included: store it as the secret it is
+impl<E: Pairing> CanonicalSerialize for Credential<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        write_version(&mut writer, CREDENTIAL_VERSION)?;
+        self.commitment.serialize_with_mode(&mut writer, compress)?;
+        self.signature.serialize_with_mode(&mut writer, compress)?;
+        self.messages.serialize_with_mode(&mut writer, compress)?;
+        self.r.serialize_with_mode(&mut writer, compress)?;
+        self.state.serialize_with_mode(&mut writer, compress)?;
+        self.tag.serialize_with_mode(&mut writer, compress)?;
+        (self.id_index as u64).serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + self.commitment.serialized_size(compress)
+            + self.signature.serialized_size(compress)
+            + self.messages.serialized_size(compress)
+            + self.r.serialized_size(compress)
+            + self.state.serialized_size(compress)
+            + self.tag.serialized_size(compress)
+            + 8
+    }
+}
+
+impl<E: Pairing> Valid for Credential<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.commitment.check()?;
+        self.signature.check()?;
+        // A signed credential has its signature, and the ID index points at an attribute
+        if (self.state == CredentialState::Signed && self.signature.is_none())
+            || self.id_index >= self.messages.len().max(1)
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(())
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for Credential<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        read_version(&mut reader, CREDENTIAL_VERSION)?;
+        let credential = Self {
+            commitment: Commitment::deserialize_with_mode(&mut reader, compress, validate)?,
+            signature: Option::deserialize_with_mode(&mut reader, compress, validate)?,
+            messages: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            r: E::ScalarField::deserialize_with_mode(&mut reader, compress, validate)?,
+            state: CredentialState::deserialize_with_mode(&mut reader, compress, validate)?,
+            tag: Option::deserialize_with_mode(&mut reader, compress, validate)?,
+            id_index: usize::try_from(u64::deserialize_with_mode(&mut reader, compress, validate)?)
+                .map_err(|_| SerializationError::InvalidData)?,
+        };
+        if validate == Validate::Yes {
+            credential.check()?;
+        }
+        Ok(credential)
+    }
+}
+
+// Format version of a serialized presentation
+const SHOW_CREDENTIAL_VERSION: u8 = 3;
+
+// r_new is the holder's secret and isn't encoded; a decoded presentation has it set to zero
+impl<E: Pairing> CanonicalSerialize for ShowCredential<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        write_version(&mut writer, SHOW_CREDENTIAL_VERSION)?;
+        self.randomized_signature
+            .serialize_with_mode(&mut writer, compress)?;
+        self.randomized_commitment
+            .serialize_with_mode(&mut writer, compress)?;
+        self.proof.serialize_with_mode(&mut writer, compress)?;
+        self.issuer_or_proof
+            .serialize_with_mode(&mut writer, compress)?;
+        self.nullifier_proof
+            .serialize_with_mode(&mut writer, compress)?;
+        self.presented_at
+            .serialize_with_mode(&mut writer, compress)?;
+        self.disclosed.serialize_with_mode(&mut writer, compress)?;
+        self.range_proofs.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + self.randomized_signature.serialized_size(compress)
+            + self.randomized_commitment.serialized_size(compress)
+            + self.proof.serialized_size(compress)
+            + self.issuer_or_proof.serialized_size(compress)
+            + self.nullifier_proof.serialized_size(compress)
+            + self.presented_at.serialized_size(compress)
+            + self.disclosed.serialized_size(compress)
+            + self.range_proofs.serialized_size(compress)
+    }
+}
+
+impl<E: Pairing> Valid for ShowCredential<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.randomized_signature.check()?;
+        self.randomized_commitment.check()?;
+        self.proof.check()?;
+        self.issuer_or_proof.check()?;
+        self.nullifier_proof.check()?;
+        self.disclosed.check()?;
+        self.range_proofs.check()
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for ShowCredential<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        read_version(&mut reader, SHOW_CREDENTIAL_VERSION)?;
+        Ok(Self {
+            randomized_signature: Signature::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            randomized_commitment: Commitment::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            proof: CommitmentProof::deserialize_with_mode(&mut reader, compress, validate)?,
+            r_new: E::ScalarField::zero(),
+            issuer_or_proof: Option::deserialize_with_mode(&mut reader, compress, validate)?,
+            nullifier_proof: Option::deserialize_with_mode(&mut reader, compress, validate)?,
+            presented_at: Option::deserialize_with_mode(&mut reader, compress, validate)?,
+            disclosed: BTreeMap::deserialize_with_mode(&mut reader, compress, validate)?,
+            range_proofs: BTreeMap::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+// Compressed sizes of a G1 point, a G2 point and a scalar on this curve
+fn element_sizes<E: Pairing>() -> (usize, usize, usize) {
+    (
+        E::G1Affine::generator().compressed_size(),
+        E::G2Affine::generator().compressed_size(),
+        E::ScalarField::zero().compressed_size(),
+    )
+}
+
+// Proof context for a presentation time; untimed shows use none
+fn presentation_context(presented_at: Option<u64>) -> Vec<u8> {
+    presented_at
+        .map(|t| t.to_le_bytes().to_vec())
+        .unwrap_or_default()
+}
+
+// Proof context for a session nonce, domain separated from presentation times
+fn nonce_context(nonce: &[u8]) -> Vec<u8> {
+    let mut context = b"mimc-abc-show-nonce".to_vec();
+    context.extend_from_slice(&(nonce.len() as u64).to_le_bytes());
+    context.extend_from_slice(nonce);
+    context
 }
 
 impl<E: Pairing> ShowCredential<E> {
+    // Compressed size of an untagged, untimed presentation of n attributes: version byte,
+    // randomized signature and commitment, the commitment proof (version, commitment,
+    // announcement, n + 1 bases, n + 1 responses, tag flag), three option flags and the
+    // lengths of the empty disclosure and range proof maps
+    pub fn size_for(n: usize) -> usize {
+        let (g1, g2, scalar) = element_sizes::<E>();
+        let proof = 1 + (g1 + g2) + g1 + (8 + (n + 1) * g1) + (8 + (n + 1) * scalar) + 1;
+        1 + 2 * g1 + (g1 + g2) + proof + 3 + 8 + 8
+    }
+
+    // The cleartext tag travelling with the presentation
+    pub fn tag(&self) -> Option<&[u8]> {
+        self.proof.tag.as_deref()
+    }
+
+    // Verify the presentation and that it was made under the verifier's `expected` tag
+    // A presentation with any other tag, or none, is rejected
+    pub fn verify_tagged(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        expected: &[u8],
+    ) -> bool {
+        self.tag() == Some(expected) && self.verify(pp, vk)
+    }
+
+    // Verify the opening proof under this presentation's own context, against pp's bases
+    pub(crate) fn verify_proof(&self, pp: &PublicParams<E>) -> bool {
+        self.verify_proof_with_context(pp, &presentation_context(self.presented_at))
+    }
+
+    // `verify_proof`, and that the proof is about the randomized commitment the signature is
+    // checked on rather than a commitment of the prover's choosing
+    pub(crate) fn verify_bound_proof(&self, pp: &PublicParams<E>) -> bool {
+        self.verify_bound_proof_with_context(pp, &presentation_context(self.presented_at))
+    }
+
+    // `verify_proof_with_context` with the binding of `verify_bound_proof`
+    pub(crate) fn verify_bound_proof_with_context(
+        &self,
+        pp: &PublicParams<E>,
+        context: &[u8],
+    ) -> bool {
+        self.proof.commitment.cm == self.randomized_commitment.cm
+            && self.verify_proof_with_context(pp, context)
+    }
+
+    // Verify the opening proof under `context`, that it opens the disclosed attributes
+    // to their claimed values, and every carried range proof
+    pub(crate) fn verify_proof_with_context(&self, pp: &PublicParams<E>, context: &[u8]) -> bool {
+        self.proof
+            .verify_disclosed_against_with_context(pp, &self.disclosed, context)
+            && self.range_proofs.iter().all(|(&index, proof)| {
+                proof.range.index == index && proof.verify(pp, &self.randomized_commitment)
+            })
+    }
+
+    // Verify the presentation and that it opens attribute `index` to `expected`: the opening
+    // proof's response for a disclosed attribute is c * expected, the expected value's share
+    // of the commitment, so any other value fails the Schnorr relation
+    pub fn verify_attribute_equals(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        index: usize,
+        expected: &E::ScalarField,
+    ) -> bool {
+        self.disclosed.get(&index) == Some(expected) && self.verify(pp, vk)
+    }
+
+    // The range attribute `index` is proven to lie in, if the presentation carries one
+    pub fn range(&self, index: usize) -> Option<(u64, u64)> {
+        self.range_proofs
+            .get(&index)
+            .map(|proof| (proof.range.min, proof.range.max))
+    }
+
+    // Verify the presentation carries each required (index, min, max) range and verifies
+    pub fn verify_with_ranges(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        required: &[(usize, u64, u64)],
+    ) -> bool {
+        required
+            .iter()
+            .all(|&(index, min, max)| self.range(index) == Some((min, max)))
+            && self.verify(pp, vk)
+    }
+
+    // Verify the presentation proves the expiry at `expiry_index` is at least the verifier's
+    // own `now`: the proven lower bound must not be earlier, so a prover's clock running
+    // behind is rejected and one running ahead only proves more
+    pub fn verify_not_expired(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        expiry_index: usize,
+        now: u64,
+    ) -> bool {
+        matches!(self.range(expiry_index), Some((min, _)) if min >= now) && self.verify(pp, vk)
+    }
+
+    // The commitment the opening proof opens, with `disclosed` substituted for the
+    // disclosed attributes, see `CommitmentProof::recompute_commitment`
+    pub fn recompute_commitment(
+        &self,
+        pp: &PublicParams<E>,
+        disclosed: &BTreeMap<usize, E::ScalarField>,
+    ) -> E::G1Affine {
+        self.proof
+            .recompute_commitment(pp, disclosed, &presentation_context(self.presented_at))
+    }
+
     pub fn verify(&self, pp: &PublicParams<E>, vk: &VerificationKey<E>) -> bool {
-        // First verify the proof
-        if !self.proof.verify() {
-            println!("Show Cred Proof failed");
+        // First verify the proof and that it opens the carried commitment, then the signature
+        self.verify_proof(pp)
+            && self.recompute_commitment(pp, &self.disclosed) == self.randomized_commitment.cm
+            && vk.verify(&self.randomized_signature, &self.randomized_commitment, pp)
+    }
+
+    // Verify a timed presentation made at `presented_at` is at most `max_age` old at `now`
+    // `presented_at` is part of the challenge, so a claimed time other than the prover's fails
+    pub fn verify_fresh(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        presented_at: u64,
+        now: u64,
+        max_age: u64,
+    ) -> bool {
+        // Reject presentations from the future as well as stale ones
+        match now.checked_sub(presented_at) {
+            Some(age) if age <= max_age => {}
+            _ => return false,
+        }
+        self.verify_bound_proof_with_context(pp, &presentation_context(Some(presented_at)))
+            && vk.verify(&self.randomized_signature, &self.randomized_commitment, pp)
+    }
+
+    // Verify a presentation from `Credential::show_with_nonce` made under `nonce`
+    pub fn verify_with_nonce(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        nonce: &[u8],
+    ) -> bool {
+        self.verify_bound_proof_with_context(pp, &nonce_context(nonce))
+            && vk.verify(&self.randomized_signature, &self.randomized_commitment, pp)
+    }
+
+    // The one-time nullifier, if this presentation carries one
+    pub fn nullifier(&self) -> Option<&E::G1Affine> {
+        self.nullifier_proof.as_ref().map(|proof| &proof.nullifier)
+    }
+
+    // Verify the presentation and that its nullifier belongs to `context` and the user ID at
+    // attribute `id_index` of the schema
+    // The caller then records the nullifier in a NullifierSet to reject reuse
+    pub fn verify_with_nullifier(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        id_index: usize,
+        context: &[u8],
+    ) -> bool {
+        match &self.nullifier_proof {
+            Some(proof) => {
+                proof.verify(pp, &self.randomized_commitment, id_index, context)
+                    && self.verify(pp, vk)
+            }
+            None => false,
+        }
+    }
+
+    // Verify a presentation whose issuer is hidden among `candidate_vks`
+    pub fn verify_issuer_or(
+        &self,
+        pp: &PublicParams<E>,
+        candidate_vks: &[&VerificationKey<E>],
+    ) -> bool {
+        let issuer_proof = match &self.issuer_or_proof {
+            Some(proof) => proof,
+            None => return false,
+        };
+
+        if !self.verify_bound_proof(pp) {
             return false;
         }
 
-        // Then verify the signature
-        if !vk.verify(&self.randomized_signature, &self.randomized_commitment, &pp) {
-            println!("Signature failed");
+        // Commitment consistency e(cm, g_tilde) == e(g, cm_tilde)
+        let cm = &self.randomized_commitment;
+        if E::pairing(cm.cm, pp.g_tilde) != E::pairing(pp.g, cm.cm_tilde) {
             return false;
         }
-        true
+
+        issuer_proof.verify(pp, &self.randomized_signature, cm, candidate_vks)
+    }
+
+    /// Re-randomize a received presentation's signature for forwarding, so the two verifiers can't link it
+    /// A relay doesn't know the attributes, so it can't prove an opening of the new commitment:
+    /// the challenge hashes the commitment and announcement, and rescaling the responses
+    /// to a new challenge needs the witness. The result is therefore not a `ShowCredential`,
+    /// only the signature and commitment, which show some credential was signed but not who holds it.
+    /// The relay checks the proof is bound to this commitment before forwarding.
+    pub fn reblind_signature(
+        &self,
+        pp: &PublicParams<E>,
+        rng: &mut impl Rng,
+    ) -> Result<UnprovenSignedCommitment<E>, Error> {
+        if !self.verify_bound_proof(pp) {
+            return Err(Error::ProofVerificationFailed);
+        }
+        Ok(UnprovenSignedCommitment {
+            randomized_signature: self.randomized_signature.clone(),
+            randomized_commitment: self.randomized_commitment.clone(),
+        }
+        .reblind(pp, rng))
+    }
+}
+
+/// A signature and commitment re-randomized by a relay, with no proof of possession
+/// Anyone who has seen a presentation can make unlimited unlinkable copies of this,
+/// so it must never be accepted where a `ShowCredential` is expected
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct UnprovenSignedCommitment<E: Pairing> {
+    pub randomized_signature: Signature<E>,
+    pub randomized_commitment: Commitment<E>,
+}
+
+impl<E: Pairing> UnprovenSignedCommitment<E> {
+    /// Apply fresh delta_r, delta_u again, for the next hop
+    pub fn reblind(&self, pp: &PublicParams<E>, rng: &mut impl Rng) -> Self {
+        let delta_r = E::ScalarField::rand(rng);
+        let delta_u = nonzero_scalar(|| E::ScalarField::rand(rng));
+        Self {
+            randomized_signature: self.randomized_signature.randomize(&delta_r, &delta_u),
+            randomized_commitment: self.randomized_commitment.randomize(pp, &delta_r),
+        }
+    }
+
+    /// The signature verifies on the commitment under `vk`; says nothing about who holds it
+    pub fn verify_signature(&self, pp: &PublicParams<E>, vk: &VerificationKey<E>) -> bool {
+        !self.randomized_signature.sigma1.is_zero()
+            && vk.verify_with_pairing_checker(
+                &self.randomized_signature,
+                &self.randomized_commitment,
+                pp,
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::MimcAbc;
+    use crate::serialization::{from_bytes, to_bytes};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_tagged_credential() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, r).with_tag(b"passport/v1");
+
+        // Issuer sees the tag in the issuance proof
+        let proof = protocol.obtain(&credential, &mut rng);
+        assert_eq!(proof.tag.as_deref(), Some(&b"passport/v1"[..]));
+        let signature = protocol.issue(&proof, &sk, &mut rng).unwrap();
+        credential.add_signature(signature).unwrap();
+
+        let presentation = protocol.show(&credential, &mut rng).unwrap();
+        assert_eq!(presentation.tag(), Some(&b"passport/v1"[..]));
+        assert!(
+            presentation.verify_tagged(&protocol.pp, &vk, b"passport/v1"),
+            "Tagged presentation should verify"
+        );
+
+        // Changing the tag invalidates the presentation
+        let mut tampered = presentation.clone();
+        tampered.proof.tag = Some(b"passport/v2".to_vec());
+        assert!(
+            !tampered.verify_tagged(&protocol.pp, &vk, b"passport/v2"),
+            "Presentation with a modified tag should fail"
+        );
+
+        // So does stripping it
+        let mut stripped = presentation;
+        stripped.proof.tag = None;
+        assert!(
+            !stripped.verify(&protocol.pp, &vk),
+            "Presentation with a removed tag should fail"
+        );
+
+        // A presentation under another tag, or none, isn't accepted for this one
+        let retagged = credential.clone().with_tag(b"passport/v2");
+        let presentation = protocol.show(&retagged, &mut rng).unwrap();
+        assert!(!presentation.verify_tagged(&protocol.pp, &vk, b"passport/v1"));
+        let mut untagged = credential;
+        untagged.tag = None;
+        let presentation = protocol.show(&untagged, &mut rng).unwrap();
+        assert!(presentation.verify(&protocol.pp, &vk));
+        assert!(!presentation.verify_tagged(&protocol.pp, &vk, b"passport/v1"));
+    }
+
+    #[test]
+    fn test_project_credential() {
+        let mut rng = test_rng();
+        let n = 10;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+
+        let (projected, link) = credential
+            .project(&[2, 5, 7], &protocol.pp, &mut rng)
+            .expect("Projection should succeed");
+        assert_eq!(
+            projected.get_messages(),
+            &vec![messages[0], messages[2], messages[5], messages[7]]
+        );
+        assert!(
+            link.verify(&protocol.pp, &vk, &projected.commitment, 0),
+            "Link proof should verify"
+        );
+
+        // A projection of different attributes doesn't match this link
+        let (other, _) = credential
+            .project(&[2, 5, 8], &protocol.pp, &mut rng)
+            .unwrap();
+        assert!(!link.verify(&protocol.pp, &vk, &other.commitment, 0));
+
+        // Claiming a different source index fails
+        let mut tampered = link.clone();
+        tampered.indices[2] = 6;
+        assert!(!tampered.verify(&protocol.pp, &vk, &projected.commitment, 0));
+
+        // Wrong issuer
+        let (_, other_vk) = crate::signature::generate_keys(&protocol.pp, &mut rng);
+        assert!(!link.verify(&protocol.pp, &other_vk, &projected.commitment, 0));
+
+        // With the user ID in slot 3 the projection leads with it, and the verifier pins it
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r)
+            .with_id_index(3)
+            .unwrap();
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+        let (projected, link) = credential
+            .project(&[2, 3, 5], &protocol.pp, &mut rng)
+            .unwrap();
+        assert_eq!(
+            projected.get_messages(),
+            &vec![messages[3], messages[2], messages[5]]
+        );
+        assert!(link.verify(&protocol.pp, &vk, &projected.commitment, 3));
+        assert!(!link.verify(&protocol.pp, &vk, &projected.commitment, 0));
+    }
+
+    #[test]
+    fn test_nullifier_double_spend() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+
+        let mut spent = crate::nullifier::NullifierSet::new();
+        let first = credential
+            .show_with_nullifier(&protocol.pp, b"vote-2026", &mut rng)
+            .unwrap();
+        assert!(first.verify_with_nullifier(&protocol.pp, &vk, 0, b"vote-2026"));
+        assert!(spent.check_and_insert(first.nullifier().unwrap()));
+
+        // A fresh presentation in the same context is a double-spend
+        let second = credential
+            .show_with_nullifier(&protocol.pp, b"vote-2026", &mut rng)
+            .unwrap();
+        assert!(second.verify_with_nullifier(&protocol.pp, &vk, 0, b"vote-2026"));
+        assert!(
+            !spent.check_and_insert(second.nullifier().unwrap()),
+            "Second presentation in the same context should be rejected"
+        );
+
+        // Another context is independent
+        let other = credential
+            .show_with_nullifier(&protocol.pp, b"vote-2027", &mut rng)
+            .unwrap();
+        assert!(other.verify_with_nullifier(&protocol.pp, &vk, 0, b"vote-2027"));
+        assert!(spent.check_and_insert(other.nullifier().unwrap()));
+        assert!(!other.verify_with_nullifier(&protocol.pp, &vk, 0, b"vote-2026"));
+
+        // Swapping in a different nullifier breaks the proof
+        let mut forged = second;
+        forged.nullifier_proof.as_mut().unwrap().nullifier = *other.nullifier().unwrap();
+        assert!(!forged.verify_with_nullifier(&protocol.pp, &vk, 0, b"vote-2026"));
+
+        // With the user ID in slot 2 the nullifier comes from it, and only verifies there
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r)
+            .with_id_index(2)
+            .unwrap();
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+        let presentation = credential
+            .show_with_nullifier(&protocol.pp, b"vote-2026", &mut rng)
+            .unwrap();
+        assert_eq!(
+            presentation.nullifier().unwrap(),
+            &crate::nullifier::nullifier(&protocol.pp, &messages[2], b"vote-2026").unwrap()
+        );
+        assert!(presentation.verify_with_nullifier(&protocol.pp, &vk, 2, b"vote-2026"));
+        assert!(!presentation.verify_with_nullifier(&protocol.pp, &vk, 0, b"vote-2026"));
+    }
+
+    #[test]
+    fn test_verify_opening_and_signature() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+        assert!(credential.verify_opening_and_signature(&protocol.pp, &vk));
+
+        // Corrupted stored randomness: the signature is still fine but the opening isn't
+        credential.r += Fr::from(1u64);
+        assert!(credential.verify(&protocol.pp, &vk));
+        assert!(
+            !credential.verify_opening_and_signature(&protocol.pp, &vk),
+            "Altered r should fail the combined check"
+        );
+    }
+
+    #[test]
+    fn test_show_with_nonce() {
+        let mut rng = test_rng();
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(4, &mut rng);
+        let messages: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+
+        let shown = credential
+            .show_with_nonce(&protocol.pp, b"session A", &mut rng)
+            .unwrap();
+        assert!(shown.verify_with_nonce(&protocol.pp, &vk, b"session A"));
+
+        // Replayed in another session, or to a verifier not asking for a nonce
+        assert!(!shown.verify_with_nonce(&protocol.pp, &vk, b"session B"));
+        assert!(!shown.verify(&protocol.pp, &vk));
+
+        // The captured signature and commitment next to a fresh session-B proof of a
+        // commitment the replayer can open
+        let own: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let own_r = Fr::rand(&mut rng);
+        let own_commitment = protocol.ck.commit(&protocol.pp, &own, &own_r);
+        let mut replayed = shown.clone();
+        replayed.proof = CommitmentProof::prove_with_context(
+            &protocol.pp,
+            &own_commitment,
+            &own,
+            &own_r,
+            None,
+            &nonce_context(b"session B"),
+            &mut rng,
+        );
+        assert!(replayed
+            .proof
+            .verify_against_with_context(&protocol.pp, &nonce_context(b"session B")));
+        assert!(!replayed.verify_with_nonce(&protocol.pp, &vk, b"session B"));
+    }
+
+    #[test]
+    fn test_verify_fresh_window() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+
+        let presented_at = 1_700_000_000;
+        let max_age = 300;
+        let presentation = credential
+            .show_at(&protocol.pp, presented_at, &mut rng)
+            .unwrap();
+        assert!(presentation.verify(&protocol.pp, &vk));
+        assert!(
+            presentation.verify_fresh(&protocol.pp, &vk, presented_at, presented_at + 60, max_age),
+            "Presentation inside the window should verify"
+        );
+        assert!(
+            !presentation.verify_fresh(
+                &protocol.pp,
+                &vk,
+                presented_at,
+                presented_at + max_age + 1,
+                max_age
+            ),
+            "Presentation older than max_age should fail"
+        );
+
+        // Claiming a later presentation time changes the challenge
+        let now = presented_at + max_age + 100;
+        assert!(
+            !presentation.verify_fresh(&protocol.pp, &vk, now - 10, now, max_age),
+            "Tampered presentation time should fail"
+        );
+        let mut tampered = presentation.clone();
+        tampered.presented_at = Some(now - 10);
+        assert!(!tampered.verify(&protocol.pp, &vk));
+
+        // The captured signature and commitment next to a fresh proof, timed now, of a
+        // commitment the replayer can open
+        let own: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let own_r = Fr::rand(&mut rng);
+        let own_commitment = protocol.ck.commit(&protocol.pp, &own, &own_r);
+        let mut replayed = presentation;
+        replayed.presented_at = Some(now);
+        replayed.proof = CommitmentProof::prove_with_context(
+            &protocol.pp,
+            &own_commitment,
+            &own,
+            &own_r,
+            None,
+            &presentation_context(Some(now)),
+            &mut rng,
+        );
+        assert!(!replayed.verify_fresh(&protocol.pp, &vk, now, now, max_age));
+    }
+
+    #[test]
+    fn test_size_for_matches_serialized() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, _) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+
+        let bytes = to_bytes(&credential);
+        assert_eq!(Credential::<Bls12_381>::size_for(n), bytes.len());
+
+        let presentation = protocol.show(&credential, &mut rng).unwrap();
+        let bytes = to_bytes(&presentation);
+        assert_eq!(ShowCredential::<Bls12_381>::size_for(n), bytes.len());
+    }
+
+    #[test]
+    fn test_verify_detailed_failure_causes() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let (_, _, other_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let pp = &protocol.pp;
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, pp, &messages, r);
+        assert!(matches!(
+            credential.verify_detailed(pp, &vk),
+            Err(Error::MissingSignature)
+        ));
+
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+        assert!(credential.verify_detailed(pp, &vk).is_ok());
+
+        // Right credential, wrong issuer's key
+        assert!(matches!(
+            credential.verify_detailed(pp, &other_vk),
+            Err(Error::SignatureVerificationFailed)
+        ));
+
+        // G2 side no longer matches G1 side
+        let other = protocol.ck.commit(pp, &messages, &Fr::rand(&mut rng));
+        credential.commitment.cm_tilde = other.cm_tilde;
+        assert!(matches!(
+            credential.verify_detailed(pp, &vk),
+            Err(Error::InvalidCommitment)
+        ));
+    }
+
+    #[test]
+    fn test_show_auto_rerandomizes() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+        let original = credential.signature.clone().unwrap();
+
+        let first = credential.show_auto(&protocol.pp, &mut rng).unwrap();
+        let second = credential.show_auto(&protocol.pp, &mut rng).unwrap();
+        assert!(first.verify(&protocol.pp, &vk));
+        assert!(second.verify(&protocol.pp, &vk));
+
+        // Neither delta was zero: nothing of the original signature or commitment survives
+        for shown in [&first, &second] {
+            assert!(!shown.randomized_signature.sigma1.is_zero());
+            assert_ne!(shown.randomized_signature.sigma1, original.sigma1);
+            assert_ne!(shown.randomized_signature.sigma2, original.sigma2);
+            assert_ne!(shown.randomized_commitment.cm, credential.commitment.cm);
+        }
+
+        // Two shows share no group element
+        assert_ne!(
+            first.randomized_signature.sigma1,
+            second.randomized_signature.sigma1
+        );
+        assert_ne!(
+            first.randomized_signature.sigma2,
+            second.randomized_signature.sigma2
+        );
+        assert_ne!(
+            first.randomized_commitment.cm,
+            second.randomized_commitment.cm
+        );
+        assert_ne!(
+            first.proof.schnorr_commitment,
+            second.proof.schnorr_commitment
+        );
+    }
+
+    #[test]
+    fn test_versioned_serialization() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, r).with_tag(b"id/v1");
+        let proof = protocol.obtain(&credential, &mut rng);
+
+        // Issuance proofs round-trip
+        let bytes = to_bytes(&proof);
+        let decoded: CommitmentProof<Bls12_381> = from_bytes(&bytes).unwrap();
+        assert!(decoded.verify());
+        assert_eq!(to_bytes(&decoded), bytes);
+
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+        let presentation = credential
+            .show_with_nullifier(&protocol.pp, b"poll-7", &mut rng)
+            .unwrap();
+        let bytes = to_bytes(&presentation);
+        let decoded: ShowCredential<Bls12_381> = from_bytes(&bytes).unwrap();
+        assert!(decoded.verify_with_nullifier(&protocol.pp, &vk, 0, b"poll-7"));
+        assert!(
+            decoded.r_new.is_zero(),
+            "The holder's randomness isn't encoded"
+        );
+
+        // A buffer from a future format version is rejected, at either layer
+        let mut future = bytes.clone();
+        future[0] = SHOW_CREDENTIAL_VERSION + 1;
+        assert!(matches!(
+            from_bytes::<ShowCredential<Bls12_381>>(&future),
+            Err(Error::SerializationError(_))
+        ));
+        let mut future_proof = to_bytes(&proof);
+        future_proof[0] = 0xff;
+        assert!(matches!(
+            from_bytes::<CommitmentProof<Bls12_381>>(&future_proof),
+            Err(Error::SerializationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_aggregate_verify() {
+        use crate::signature::{generate_keys, SecretKey};
+        let mut rng = test_rng();
+        let protocol = MimcAbc::<Bls12_381>::setup(3, &mut rng).0;
+        let (sk_a, vk_a) = generate_keys(&protocol.pp, &mut rng);
+        let (sk_b, vk_b) = generate_keys(&protocol.pp, &mut rng);
+        let messages: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        let joint = SecretKey::combine(&[&sk_a, &sk_b]);
+        protocol
+            .obtain_and_issue(&mut credential, &joint, &mut rng)
+            .unwrap();
+
+        assert!(credential.aggregate_verify(&protocol.pp, &[&vk_a, &vk_b]));
+        assert!(!credential.aggregate_verify(&protocol.pp, &[&vk_a]));
+    }
+
+    #[test]
+    fn test_update_attribute() {
+        let mut rng = test_rng();
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(4, &mut rng);
+        let messages: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+
+        // Bump the tier at index 2
+        let delta_r = Fr::rand(&mut rng);
+        credential
+            .update_attribute(&protocol.pp, 2, Fr::from(3u64), &delta_r)
+            .unwrap();
+        let mut updated = messages.clone();
+        updated[2] = Fr::from(3u64);
+        let fresh = protocol.ck.commit(&protocol.pp, &updated, &(r + delta_r));
+        assert_eq!(credential.commitment.cm, fresh.cm);
+        assert_eq!(credential.commitment.cm_tilde, fresh.cm_tilde);
+        assert_eq!(credential.get_messages(), &updated);
+
+        // Re-signing is required before it can be shown again
+        assert_eq!(credential.state, CredentialState::Committed);
+        assert!(credential.show_auto(&protocol.pp, &mut rng).is_err());
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+        assert!(credential.verify(&protocol.pp, &vk));
+
+        assert!(credential
+            .update_attribute(&protocol.pp, 4, Fr::from(1u64), &delta_r)
+            .is_err());
+    }
+
+    #[test]
+    fn test_recompute_commitment() {
+        use ark_ec::CurveGroup;
+        let mut rng = test_rng();
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(4, &mut rng);
+        let messages: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+
+        let shown = credential
+            .show_with_disclosure(
+                &protocol.pp,
+                &[1],
+                &Fr::rand(&mut rng),
+                &Fr::rand(&mut rng),
+                &mut rng,
+            )
+            .unwrap();
+        let disclosed = BTreeMap::from([(1, messages[1])]);
+        assert_eq!(
+            shown.recompute_commitment(&protocol.pp, &disclosed),
+            shown.randomized_commitment.cm
+        );
+        assert!(shown.verify(&protocol.pp, &vk));
+
+        // Another disclosed value recomputes to another commitment
+        let wrong = BTreeMap::from([(1, messages[2])]);
+        assert_ne!(
+            shown.recompute_commitment(&protocol.pp, &wrong),
+            shown.randomized_commitment.cm
+        );
+
+        let mut tampered = shown;
+        tampered.randomized_commitment.cm =
+            (tampered.randomized_commitment.cm + protocol.pp.g).into_affine();
+        assert!(!tampered.verify(&protocol.pp, &vk));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_round_trip() {
+        let mut rng = test_rng();
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(4, &mut rng);
+        let messages: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+        let shown = credential.show_auto(&protocol.pp, &mut rng).unwrap();
+
+        let json = serde_json::to_string(&shown).unwrap();
+        let decoded: ShowCredential<Bls12_381> = serde_json::from_str(&json).unwrap();
+        assert_eq!(to_bytes(&decoded), to_bytes(&shown));
+        assert!(decoded.verify(&protocol.pp, &vk));
+
+        // A flipped byte is caught by the canonical decoding
+        let mut bytes: Vec<u8> = serde_json::from_str(&json).unwrap();
+        bytes[5] ^= 1;
+        let json = serde_json::to_string(&bytes).unwrap();
+        assert!(serde_json::from_str::<ShowCredential<Bls12_381>>(&json).is_err());
+    }
+
+    #[test]
+    fn test_verify_any() {
+        let mut rng = test_rng();
+        let issuers: Vec<_> = (0..3)
+            .map(|_| MimcAbc::<Bls12_381>::setup(4, &mut rng))
+            .collect();
+        let (protocol, sk, _) = &issuers[1];
+        let messages: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        protocol
+            .obtain_and_issue(&mut credential, sk, &mut rng)
+            .unwrap();
+
+        let pp_list: Vec<_> = issuers
+            .iter()
+            .map(|(protocol, _, _)| &protocol.pp)
+            .collect();
+        let vk_list: Vec<_> = issuers.iter().map(|(_, _, vk)| vk).collect();
+        assert_eq!(credential.verify_any(&pp_list, &vk_list), Some(1));
+        assert_eq!(credential.verify_any(&pp_list[2..], &vk_list[2..]), None);
+
+        let unsigned = Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        assert_eq!(unsigned.verify_any(&pp_list, &vk_list), None);
+    }
+
+    #[test]
+    fn test_prove_not_expired() {
+        let mut rng = test_rng();
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(3, &mut rng);
+        let now = 1_700_000_000u64;
+        let expiry = now + 30 * 24 * 3600;
+        let messages = vec![Fr::rand(&mut rng), Fr::from(expiry), Fr::rand(&mut rng)];
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+
+        let shown = credential
+            .prove_not_expired(&protocol.pp, 1, now, 32, &mut rng)
+            .unwrap();
+        assert!(shown.verify_not_expired(&protocol.pp, &vk, 1, now));
+        assert!(shown.verify_not_expired(&protocol.pp, &vk, 1, now - 60));
+        // A verifier whose clock is ahead of the proven bound rejects it
+        assert!(!shown.verify_not_expired(&protocol.pp, &vk, 1, now + 60));
+        assert!(!shown.verify_not_expired(&protocol.pp, &vk, 2, now));
+
+        // Expired, or further out than max_bits covers
+        assert!(credential
+            .prove_not_expired(&protocol.pp, 1, expiry + 1, 32, &mut rng)
+            .is_err());
+        assert!(credential
+            .prove_not_expired(&protocol.pp, 1, now, 16, &mut rng)
+            .is_err());
+    }
+
+    #[test]
+    fn test_credential_builder() {
+        let mut rng = test_rng();
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(4, &mut rng);
+        let user_id = Fr::rand(&mut rng);
+        let attributes: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut credential = CredentialBuilder::new()
+            .user_id(user_id)
+            .attributes(attributes.clone())
+            .build(&protocol.pp, &protocol.ck, &mut rng)
+            .unwrap();
+        assert_eq!(credential.get_messages()[0], user_id);
+        assert_eq!(credential.get_messages()[1..], attributes[..]);
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+        assert!(credential.verify(&protocol.pp, &vk));
+
+        // Given randomness is used as is
+        let r = Fr::rand(&mut rng);
+        let built = CredentialBuilder::new()
+            .user_id(user_id)
+            .attributes(attributes.clone())
+            .randomness(r)
+            .build(&protocol.pp, &protocol.ck, &mut rng)
+            .unwrap();
+        let mut messages = vec![user_id];
+        messages.extend(&attributes);
+        assert_eq!(
+            built.commitment.cm,
+            protocol.ck.commit(&protocol.pp, &messages, &r).cm
+        );
+
+        // Without the user ID one attribute is missing
+        assert!(CredentialBuilder::<Bls12_381>::new()
+            .attributes(attributes)
+            .build(&protocol.pp, &protocol.ck, &mut rng)
+            .is_err());
+    }
+
+    #[test]
+    fn test_add_signature_transitions() {
+        let mut rng = test_rng();
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(3, &mut rng);
+        let messages: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        assert_eq!(credential.state(), &CredentialState::Committed);
+
+        let proof = protocol.obtain(&credential, &mut rng);
+        let first = protocol.issue(&proof, &sk, &mut rng).unwrap();
+        let second = protocol.issue(&proof, &sk, &mut rng).unwrap();
+        credential.add_signature(first.clone()).unwrap();
+        assert_eq!(credential.state(), &CredentialState::Signed);
+
+        // Signing twice is refused and the first signature stays
+        let Err(Error::InvalidCredentialState { expected, actual }) =
+            credential.add_signature(second.clone())
+        else {
+            panic!("Second signature should be refused");
+        };
+        assert_eq!(
+            (expected, actual),
+            (CredentialState::Committed, CredentialState::Signed)
+        );
+        assert_eq!(credential.signature.as_ref().unwrap().sigma2, first.sigma2);
+        assert!(credential.verify(&protocol.pp, &vk));
+
+        // As is signing a randomized or uninitialized credential
+        for state in [CredentialState::Randomized, CredentialState::Initialized] {
+            let mut other =
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+            other.state = state.clone();
+            assert!(matches!(
+                other.add_signature(second.clone()),
+                Err(Error::InvalidCredentialState { actual, .. }) if actual == state
+            ));
+            assert!(other.signature.is_none());
+        }
+    }
+
+    #[test]
+    fn test_show_unsigned_errors() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+
+        // An unsigned credential is an error, not a panic, on every show path
+        assert!(matches!(
+            protocol.show(&credential, &mut rng),
+            Err(Error::MissingSignature)
+        ));
+        assert!(matches!(
+            credential.show_counter(&protocol.pp, 0),
+            Err(Error::MissingSignature)
+        ));
+        assert!(credential.show_padded(&protocol.pp, n, &mut rng).is_err());
+
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+        assert!(protocol
+            .show(&credential, &mut rng)
+            .unwrap()
+            .verify(&protocol.pp, &vk));
+    }
+
+    #[test]
+    fn test_show_with_ranges() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        // Age 34 at attribute 2
+        let mut messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        messages[2] = Fr::from(34u64);
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+
+        let presentation = credential
+            .show_with_ranges(&protocol.pp, &[(2, 18, 120)], &mut rng)
+            .unwrap();
+        assert!(presentation.verify(&protocol.pp, &vk));
+        assert!(presentation.verify_with_ranges(&protocol.pp, &vk, &[(2, 18, 120)]));
+        let decoded: ShowCredential<Bls12_381> = from_bytes(&to_bytes(&presentation)).unwrap();
+        assert!(decoded.verify_with_ranges(&protocol.pp, &vk, &[(2, 18, 120)]));
+
+        // A verifier asking for another range, or a claimed tighter one, rejects it
+        assert!(!presentation.verify_with_ranges(&protocol.pp, &vk, &[(2, 21, 120)]));
+        let mut tightened = presentation.clone();
+        tightened.range_proofs.get_mut(&2).unwrap().range.min = 40;
+        assert!(!tightened.verify(&protocol.pp, &vk));
+
+        // A range proof moved onto another presentation fails
+        let mut moved = credential.show_auto(&protocol.pp, &mut rng).unwrap();
+        moved.range_proofs = presentation.range_proofs.clone();
+        assert!(!moved.verify(&protocol.pp, &vk));
+
+        // Out of range, the holder can't prove it
+        assert!(credential
+            .show_with_ranges(&protocol.pp, &[(2, 40, 120)], &mut rng)
+            .is_err());
+    }
+
+    #[test]
+    fn test_prove_attribute_equals() {
+        let mut rng = test_rng();
+        let n = 5;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        // Nationality code 250 at attribute 3
+        let mut messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        messages[3] = Fr::from(250u64);
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+
+        let (delta_r, delta_u) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let expected = Fr::from(250u64);
+        let presentation = credential
+            .prove_attribute_equals(&protocol.pp, 3, expected, &delta_r, &delta_u, &mut rng)
+            .unwrap();
+        assert!(presentation.verify_attribute_equals(&protocol.pp, &vk, 3, &expected));
+        assert_eq!(presentation.disclosed.len(), 1);
+
+        // Another expected value, or another index, fails
+        let other = Fr::from(276u64);
+        assert!(!presentation.verify_attribute_equals(&protocol.pp, &vk, 3, &other));
+        assert!(!presentation.verify_attribute_equals(&protocol.pp, &vk, 2, &expected));
+        let mut forged = presentation;
+        forged.disclosed.insert(3, other);
+        assert!(!forged.verify_attribute_equals(&protocol.pp, &vk, 3, &other));
+
+        // The holder can't prove a value the attribute doesn't have
+        assert!(credential
+            .prove_attribute_equals(&protocol.pp, 3, other, &delta_r, &delta_u, &mut rng)
+            .is_err());
+    }
+
+    #[test]
+    fn test_show_with_disclosure() {
+        let mut rng = test_rng();
+        let n = 5;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+
+        let delta_r = Fr::rand(&mut rng);
+        let delta_u = Fr::rand(&mut rng);
+        let presentation = credential
+            .show_with_disclosure(&protocol.pp, &[2], &delta_r, &delta_u, &mut rng)
+            .unwrap();
+        assert!(presentation.verify(&protocol.pp, &vk));
+
+        // The verifier reads attribute 2 and nothing else, even after a round trip
+        let decoded: ShowCredential<Bls12_381> = from_bytes(&to_bytes(&presentation)).unwrap();
+        assert!(decoded.verify(&protocol.pp, &vk));
+        assert_eq!(decoded.disclosed.len(), 1);
+        assert_eq!(decoded.disclosed.get(&2), Some(&messages[2]));
+
+        // A swapped value, or claiming a hidden attribute as disclosed, fails
+        let mut forged = presentation.clone();
+        forged.disclosed.insert(2, Fr::rand(&mut rng));
+        assert!(!forged.verify(&protocol.pp, &vk));
+        let mut forged = presentation;
+        forged.disclosed.insert(3, messages[3]);
+        assert!(!forged.verify(&protocol.pp, &vk));
+
+        assert!(credential
+            .show_with_disclosure(&protocol.pp, &[n], &delta_r, &delta_u, &mut rng)
+            .is_err());
+    }
+
+    #[test]
+    fn test_credential_round_trip() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r)
+            .with_tag(b"id/v1")
+            .with_id_index(2)
+            .unwrap();
+        // An index past the attributes is an error, not a panic
+        assert!(Credential::new(&protocol.ck, &protocol.pp, &messages, r)
+            .with_id_index(n)
+            .is_err());
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+
+        // The wallet record comes back whole and still verifies and shows
+        let bytes = to_bytes(&credential);
+        let decoded: Credential<Bls12_381> = from_bytes(&bytes).unwrap();
+        assert!(decoded.verify(&protocol.pp, &vk));
+        assert_eq!(decoded.get_messages(), credential.get_messages());
+        assert_eq!(decoded.get_r(), credential.get_r());
+        assert_eq!(decoded.state, CredentialState::Signed);
+        assert_eq!(decoded.get_tag(), Some(&b"id/v1"[..]));
+        assert_eq!(decoded.id_index(), 2);
+        assert!(decoded
+            .show_auto(&protocol.pp, &mut rng)
+            .unwrap()
+            .verify(&protocol.pp, &vk));
+        assert_eq!(to_bytes(&decoded), bytes);
+
+        // Truncated, future-version or unknown-state buffers are rejected
+        assert!(from_bytes::<Credential<Bls12_381>>(&bytes[..bytes.len() - 1]).is_err());
+        let mut future = bytes.clone();
+        future[0] = CREDENTIAL_VERSION + 1;
+        assert!(from_bytes::<Credential<Bls12_381>>(&future).is_err());
+        let mut unsigned = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        unsigned.state = CredentialState::Signed;
+        assert!(from_bytes::<Credential<Bls12_381>>(&to_bytes(&unsigned)).is_err());
+    }
+
+    #[test]
+    fn test_verify_any_params() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (old, _, old_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let (new, sk, new_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&new.ck, &new.pp, &messages, r);
+        let proof = new.obtain(&credential, &mut rng);
+        credential
+            .add_signature(new.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            credential.verify_any_params(&[(&old.pp, &old_vk), (&new.pp, &new_vk)]),
+            Some(1)
+        );
+        // Right key under the wrong parameters still fails
+        assert_eq!(
+            credential.verify_any_params(&[(&old.pp, &old_vk), (&old.pp, &new_vk)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_show_padded_hides_attribute_count() {
+        let mut rng = test_rng();
+        let pad_to = 8;
+
+        let mut presentations = Vec::new();
+        for n in [3, 6] {
+            let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+            let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+            let mut credential =
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+            let proof = protocol.obtain(&credential, &mut rng);
+            credential
+                .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+                .unwrap();
+
+            let presentation = credential
+                .show_padded(&protocol.pp, pad_to, &mut rng)
+                .unwrap();
+            assert!(presentation.verify(&protocol.pp, &vk));
+            assert!(credential
+                .show_padded(&protocol.pp, n - 1, &mut rng)
+                .is_err());
+            presentations.push(presentation);
+        }
+
+        assert_eq!(presentations[0].proof.responses.len(), pad_to + 1);
+        assert_eq!(
+            presentations[0].proof.responses.len(),
+            presentations[1].proof.responses.len()
+        );
+        assert_eq!(
+            to_bytes(&presentations[0]).len(),
+            to_bytes(&presentations[1]).len()
+        );
+    }
+
+    #[test]
+    fn test_reblind_presentation() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+
+        // The relay only sees the encoded presentation
+        let shown = credential.show_auto(&protocol.pp, &mut rng).unwrap();
+        let received: ShowCredential<Bls12_381> = from_bytes(&to_bytes(&shown)).unwrap();
+        let forwarded = received.reblind_signature(&protocol.pp, &mut rng).unwrap();
+        assert!(forwarded.verify_signature(&protocol.pp, &vk));
+
+        // Nothing the two verifiers see is shared
+        let original = &received.randomized_signature;
+        assert_ne!(forwarded.randomized_signature.sigma1, original.sigma1);
+        assert_ne!(forwarded.randomized_signature.sigma2, original.sigma2);
+        assert_ne!(
+            forwarded.randomized_commitment.cm,
+            received.randomized_commitment.cm
+        );
+        assert_ne!(
+            forwarded.randomized_commitment.cm_tilde,
+            received.randomized_commitment.cm_tilde
+        );
+
+        // Further hops keep it valid, other keys don't accept it
+        assert!(forwarded
+            .reblind(&protocol.pp, &mut rng)
+            .verify_signature(&protocol.pp, &vk));
+        let (_, _, other_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        assert!(!forwarded.verify_signature(&protocol.pp, &other_vk));
+
+        // A presentation with a broken proof isn't forwarded
+        let mut broken = received.clone();
+        broken.proof.responses[0] += Fr::from(1u64);
+        assert!(broken.reblind_signature(&protocol.pp, &mut rng).is_err());
+
+        // Nor is one carrying a valid proof for a different commitment
+        let other = credential.show_auto(&protocol.pp, &mut rng).unwrap();
+        let mut unbound = received;
+        unbound.proof = other.proof;
+        assert!(unbound.verify_proof(&protocol.pp));
+        assert!(unbound.reblind_signature(&protocol.pp, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_show_counter() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+
+        // A retry with the same counter resends the same presentation
+        let first = credential.show_counter(&protocol.pp, 7).unwrap();
+        let retry = credential.show_counter(&protocol.pp, 7).unwrap();
+        assert!(first.verify(&protocol.pp, &vk));
+        assert_eq!(to_bytes(&first), to_bytes(&retry));
+
+        // The next counter shares nothing with it
+        let next = credential.show_counter(&protocol.pp, 8).unwrap();
+        assert!(next.verify(&protocol.pp, &vk));
+        assert_ne!(
+            next.randomized_signature.sigma1,
+            first.randomized_signature.sigma1
+        );
+        assert_ne!(
+            next.randomized_signature.sigma2,
+            first.randomized_signature.sigma2
+        );
+        assert_ne!(
+            next.randomized_commitment.cm,
+            first.randomized_commitment.cm
+        );
+        assert_ne!(
+            next.proof.schnorr_commitment,
+            first.proof.schnorr_commitment
+        );
     }
 }
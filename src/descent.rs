@@ -0,0 +1,179 @@
+// mimc_abc/src/descent.rs
+use crate::credential::{Credential, ShowCredential};
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::schnorr::SchnorrProtocol;
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ff::UniformRand;
+use ark_std::rand::Rng;
+
+/// Public tweak H(path) added to a root identifier to derive a child
+/// The derivation is linear (child = root + H(path)) so descent can be shown with Schnorr proofs
+pub fn path_tweak<E: Pairing>(path: &[u8]) -> E::ScalarField {
+    let mut transcript = Transcript::new(b"mimc-abc-derive-child");
+    transcript.append_message(b"path", path);
+    transcript.challenge_scalar(b"tweak")
+}
+
+/// Proof that the presentations' user IDs all derive from one hidden root
+///
+/// Each presentation's user ID is root + H(path_k), kept at its credential's `id_index`. Every
+/// opening proof uses the same blinding for the user ID, so z_k - c * H(path_k) = rho + c * root
+/// is identical across presentations.
+#[derive(Clone, Debug)]
+pub struct DescentProof<E: Pairing> {
+    pub paths: Vec<Vec<u8>>,
+    pub announcements: Vec<E::G1Affine>,
+    pub responses: Vec<Vec<E::ScalarField>>,
+}
+
+impl<E: Pairing> DescentProof<E> {
+    pub fn prove(
+        presentations: &[&ShowCredential<E>],
+        credentials: &[&Credential<E>],
+        paths: &[&[u8]],
+        public_params: &[&PublicParams<E>],
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let count = presentations.len();
+        if count == 0
+            || credentials.len() != count
+            || paths.len() != count
+            || public_params.len() != count
+        {
+            return Err(Error::Other("Mismatched input lengths".to_string()));
+        }
+
+        // Every child must come from the same root
+        let roots: Vec<E::ScalarField> = credentials
+            .iter()
+            .zip(paths.iter())
+            .map(|(credential, path)| *credential.get_user_id() - path_tweak::<E>(path))
+            .collect();
+        if roots.iter().any(|root| *root != roots[0]) {
+            return Err(Error::Other(
+                "Credentials don't descend from the same root".to_string(),
+            ));
+        }
+
+        let id_indices: Vec<usize> = credentials
+            .iter()
+            .map(|credential| credential.id_index())
+            .collect();
+        if id_indices
+            .iter()
+            .zip(public_params)
+            .any(|(&i, pp)| i >= pp.n)
+        {
+            return Err(Error::Other(
+                "User ID index is outside the attributes".to_string(),
+            ));
+        }
+        let common_blinding = E::ScalarField::rand(rng);
+        let commitments: Vec<_> = public_params
+            .iter()
+            .zip(&id_indices)
+            .map(|(pp, &id_index)| {
+                let bases = pp.get_g1_bases();
+                let mut blindings: Vec<E::ScalarField> = (0..bases.len())
+                    .map(|_| E::ScalarField::rand(rng))
+                    .collect();
+                blindings[id_index] = common_blinding;
+                SchnorrProtocol::commit_with_prepared_blindings(bases, &blindings)
+            })
+            .collect();
+        let announcements: Vec<E::G1Affine> = commitments
+            .iter()
+            .map(|commitment| commitment.commited_blindings)
+            .collect();
+
+        let owned_paths: Vec<Vec<u8>> = paths.iter().map(|path| path.to_vec()).collect();
+        let challenge = Self::challenge(presentations, &owned_paths, &id_indices, &announcements);
+
+        let responses = commitments
+            .iter()
+            .zip(credentials.iter().zip(presentations.iter()))
+            .map(|(commitment, (credential, presentation))| {
+                let mut exponents = credential.get_messages().clone();
+                exponents.push(presentation.r_new);
+                SchnorrProtocol::prove(commitment, &exponents, &challenge).0
+            })
+            .collect();
+
+        Ok(Self {
+            paths: owned_paths,
+            announcements,
+            responses,
+        })
+    }
+
+    /// Verify with the user ID of presentation k at attribute `id_indices[k]`, taken from
+    /// each issuer's schema
+    pub fn verify(
+        &self,
+        presentations: &[&ShowCredential<E>],
+        public_params: &[&PublicParams<E>],
+        id_indices: &[usize],
+    ) -> bool {
+        let count = presentations.len();
+        if count == 0
+            || self.paths.len() != count
+            || id_indices.len() != count
+            || self.announcements.len() != count
+            || self.responses.len() != count
+            || public_params.len() != count
+        {
+            return false;
+        }
+
+        let challenge =
+            Self::challenge(presentations, &self.paths, id_indices, &self.announcements);
+        let mut root_response = None;
+        for k in 0..count {
+            let (responses, id_index) = (&self.responses[k], id_indices[k]);
+            // The user ID is an attribute, never the randomness r in the last position
+            if id_index + 1 >= responses.len()
+                || !SchnorrProtocol::verify_schnorr(
+                    public_params[k].get_g1_bases(),
+                    &presentations[k].randomized_commitment.cm,
+                    &self.announcements[k],
+                    responses,
+                    &challenge,
+                )
+            {
+                return false;
+            }
+
+            // Strip the path tweak: what's left only depends on the root
+            let stripped = responses[id_index] - challenge * path_tweak::<E>(&self.paths[k]);
+            match root_response {
+                None => root_response = Some(stripped),
+                Some(expected) if expected != stripped => return false,
+                Some(_) => {}
+            }
+        }
+        true
+    }
+
+    fn challenge(
+        presentations: &[&ShowCredential<E>],
+        paths: &[Vec<u8>],
+        id_indices: &[usize],
+        announcements: &[E::G1Affine],
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-descent");
+        for (((presentation, path), id_index), announcement) in presentations
+            .iter()
+            .zip(paths)
+            .zip(id_indices)
+            .zip(announcements)
+        {
+            transcript.append_serializable(b"cm", &presentation.randomized_commitment.cm);
+            transcript.append_message(b"path", path);
+            transcript.append_message(b"id_index", &(*id_index as u64).to_le_bytes());
+            transcript.append_serializable(b"announcement", announcement);
+        }
+        transcript.challenge_scalar(b"c")
+    }
+}
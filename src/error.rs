@@ -20,6 +20,8 @@ pub enum Error {
     InvalidSignature,
     #[error("Signature verification failed")]
     SignatureVerificationFailed,
+    #[error("Invalid partial signature from issuer {0}")]
+    InvalidPartialSignature(usize),
 
     // Credential errors
     #[error("Invalid credential state: expected {expected:?}, got {actual:?}")]
@@ -30,6 +32,14 @@ pub enum Error {
     #[error("Missing signature on credential")]
     MissingSignature,
 
+    // Parameter errors
+    #[error("Invalid public parameters: {0}")]
+    InvalidPublicParams(String),
+
+    // Attribute encoding errors
+    #[error("Attribute value {0} can't be encoded injectively in the scalar field")]
+    AttributeOutOfRange(u128),
+
     // Protocol errors
     #[error("Protocol aborted")]
     ProtocolAborted,
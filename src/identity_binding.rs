@@ -1,16 +1,25 @@
 // mimc_abc/src/identity_binding.rs
 use crate::commitment::Commitment;
+use crate::credential::{Credential, ShowCredential};
 use crate::error::Error;
 use crate::public_params::PublicParams;
 use crate::schnorr::{SchnorrCommitment, SchnorrProtocol};
+use crate::serialization::{read_version, write_version};
+use crate::transcript::Transcript;
 use ark_ec::pairing::Pairing;
 use ark_ff::UniformRand;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
 use ark_std::rand::Rng;
+use std::collections::BTreeMap;
 
-/// Proof that multiple commitments share the same value at index 0 (the user identifier)
+/// Proof that multiple commitments share the same value at `id_index` (the user identifier)
 pub struct IdentityBindingProof<E: Pairing> {
+    pub id_index: usize, // Attribute holding the user ID, 0 unless the schema puts it elsewhere
     pub commitments: Vec<Commitment<E>>, // The randomized commitments that are being proven over
-    pub schnorr_commitments: Vec<SchnorrCommitment<E::G1Affine>>, // Schnorr commitments for each credential (with shared blinding at position 0)
+    pub schnorr_commitments: Vec<SchnorrCommitment<E::G1Affine>>, // Schnorr commitments for each credential (with shared blinding at the user ID)
     pub challenge: E::ScalarField,
     pub responses: Vec<Vec<E::ScalarField>>,
 }
@@ -23,69 +32,129 @@ impl<E: Pairing> IdentityBindingProof<E> {
         randomness: &[E::ScalarField],
         public_params: &[&PublicParams<E>],
         rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let disclosed = vec![Vec::new(); commitments.len()];
+        Self::prove_with_disclosure(
+            commitments,
+            messages,
+            randomness,
+            &disclosed,
+            public_params,
+            rng,
+        )
+    }
+
+    /// Same as `prove`, revealing the attributes at `disclosed[i]` of commitment i
+    /// Each Schnorr proof covers cm_i minus its disclosed attributes, over the hidden bases only;
+    /// the user ID at position 0 can't be disclosed since it carries the binding
+    pub fn prove_with_disclosure(
+        commitments: &[Commitment<E>],
+        messages: &[Vec<E::ScalarField>],
+        randomness: &[E::ScalarField],
+        disclosed: &[Vec<usize>],
+        public_params: &[&PublicParams<E>],
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        Self::prove_with_id_index(
+            commitments,
+            messages,
+            randomness,
+            disclosed,
+            0,
+            public_params,
+            rng,
+        )
+    }
+
+    /// Same as `prove_with_disclosure` for schemas keeping the user ID at `id_index`
+    pub fn prove_with_id_index(
+        commitments: &[Commitment<E>],
+        messages: &[Vec<E::ScalarField>],
+        randomness: &[E::ScalarField],
+        disclosed: &[Vec<usize>],
+        id_index: usize,
+        public_params: &[&PublicParams<E>],
+        rng: &mut impl Rng,
     ) -> Result<Self, Error> {
         // Check inputs
         if commitments.is_empty()
             || commitments.len() != messages.len()
             || commitments.len() != randomness.len()
+            || commitments.len() != disclosed.len()
             || commitments.len() != public_params.len()
         {
             return Err(Error::Other("Mismatched input lengths".to_string()));
         }
+        for (indices, msgs) in disclosed.iter().zip(messages) {
+            if indices.iter().any(|&j| j == id_index || j >= msgs.len()) {
+                return Err(Error::Other(
+                    "Disclosed indices must be in 0..n and not the user ID".to_string(),
+                ));
+            }
+        }
 
-        // Ensure all messages have at least one element (the user ID)
+        // Ensure all messages have the user ID
         for msg in messages.iter() {
-            if msg.is_empty() {
+            if msg.len() <= id_index {
                 return Err(Error::Other(
-                    "Messages must have at least one element".to_string(),
+                    "Messages must have an element at the ID index".to_string(),
                 ));
             }
         }
 
-        // Check that all first elements (user IDs) are identical
-        let user_id = &messages[0][0];
+        // Check that all user IDs are identical
+        let user_id = &messages[0][id_index];
         for msg in messages.iter().skip(1) {
-            if &msg[0] != user_id {
+            if &msg[id_index] != user_id {
                 return Err(Error::Other(
                     "User identifiers must be identical".to_string(),
                 ));
             }
         }
 
-        // Generate a common blinding factor for position 0 (the user ID)
+        // Generate a common blinding factor for the user ID
         let common_blinding = E::ScalarField::rand(rng);
 
         // Store schnorr commitments and prepare for responses
         let mut schnorr_commitments = Vec::with_capacity(commitments.len());
+        let mut all_bases = Vec::with_capacity(commitments.len());
 
         // Generate Schnorr commitments for each credential
         for (i, _) in commitments.iter().enumerate() {
-            let pp = public_params[i];
-            let bases = pp.get_g1_bases();
+            let bases = hidden(public_params[i].get_g1_bases(), &disclosed[i]);
 
-            // Create blindings with common blinding at position 0
+            // Create blindings with the common blinding at the ID's position among the hidden bases
             let mut blindings: Vec<E::ScalarField> = (1..bases.len())
                 .map(|_| E::ScalarField::rand(rng))
                 .collect();
-
-            // Insert the common blinding at the first position
-            blindings.insert(0, common_blinding);
+            blindings.insert(hidden_position(id_index, &disclosed[i]), common_blinding);
 
             // Create Schnorr commitment
             let schnorr_commitment =
                 SchnorrProtocol::commit_with_prepared_blindings(&bases, &blindings);
 
             schnorr_commitments.push(schnorr_commitment);
+            all_bases.push(bases);
         }
 
-        // Generate a single challenge for all proofs
-        let challenge = E::ScalarField::rand(rng);
+        // A single challenge for all proofs, hashed from every statement and announcement
+        let revealed: Vec<BTreeMap<usize, E::ScalarField>> = disclosed
+            .iter()
+            .zip(messages)
+            .map(|(indices, msgs)| indices.iter().map(|&j| (j, msgs[j])).collect())
+            .collect();
+        let announcements: Vec<E::G1Affine> = schnorr_commitments
+            .iter()
+            .map(|commitment| commitment.commited_blindings)
+            .collect();
+        let challenge =
+            Self::challenge(id_index, commitments, &all_bases, &revealed, &announcements);
 
         // Generate responses for each commitment
         let mut all_responses = Vec::with_capacity(commitments.len());
         for (i, _) in commitments.iter().enumerate() {
-            // Create a vector with messages and randomness
-            let mut exponents = messages[i].clone();
+            // Create a vector with hidden messages and randomness
+            let mut exponents = hidden(&messages[i], &disclosed[i]);
             exponents.push(randomness[i]);
 
             // Generate responses
@@ -94,6 +163,7 @@ impl<E: Pairing> IdentityBindingProof<E> {
         }
 
         Ok(IdentityBindingProof {
+            id_index,
             commitments: commitments.to_vec(),
             schnorr_commitments,
             challenge,
@@ -101,29 +171,85 @@ impl<E: Pairing> IdentityBindingProof<E> {
         })
     }
 
-    /// Verify that multiple commitments share the same value at position 0
-    pub fn verify(&self, public_params: &[&PublicParams<E>]) -> Result<bool, Error> {
+    /// Verify that multiple commitments share the same value at `id_index`, the ID slot of
+    /// the verifier's schema
+    pub fn verify(
+        &self,
+        public_params: &[&PublicParams<E>],
+        id_index: usize,
+    ) -> Result<bool, Error> {
+        let disclosed = vec![BTreeMap::new(); self.commitments.len()];
+        self.verify_with_disclosure(public_params, &disclosed, id_index)
+    }
+
+    /// Verify a proof from `prove_with_disclosure` against the revealed attribute values
+    /// The proof's own `id_index` must be the verifier's: a prover picking the slot could
+    /// link credentials of different users through any attribute they happen to share
+    pub fn verify_with_disclosure(
+        &self,
+        public_params: &[&PublicParams<E>],
+        disclosed: &[BTreeMap<usize, E::ScalarField>],
+        id_index: usize,
+    ) -> Result<bool, Error> {
         if self.commitments.is_empty()
             || self.commitments.len() != self.schnorr_commitments.len()
             || self.commitments.len() != self.responses.len()
+            || self.commitments.len() != disclosed.len()
             || self.commitments.len() != public_params.len()
         {
             return Err(Error::Other(
                 "Mismatched proof component lengths".to_string(),
             ));
         }
+        if self.id_index != id_index {
+            return Ok(false);
+        }
+
+        // Credentials from the same issuer share bases, compute them once
+        let (unique_bases, bases_index) = shared_bases(public_params);
+
+        let mut all_bases = Vec::with_capacity(self.commitments.len());
+        for (i, &b) in bases_index.iter().enumerate() {
+            let pp = public_params[i];
+            if self.id_index >= pp.n
+                || disclosed[i]
+                    .keys()
+                    .any(|&j| j == self.id_index || j >= pp.n)
+            {
+                return Ok(false);
+            }
+            let indices: Vec<usize> = disclosed[i].keys().copied().collect();
+            let bases = hidden(&unique_bases[b], &indices);
+            if self.responses[i].len() != bases.len() {
+                return Ok(false);
+            }
+            all_bases.push(bases);
+        }
+
+        // The challenge must be the hash of this statement, not one the prover picked
+        let challenge = Self::challenge(
+            self.id_index,
+            &self.commitments,
+            &all_bases,
+            disclosed,
+            &self.announcements(),
+        );
+        if challenge != self.challenge {
+            return Ok(false);
+        }
 
         // Verify each individual Schnorr proof
-        for i in 0..self.commitments.len() {
-            let bases = public_params[i].get_g1_bases();
+        for (i, bases) in all_bases.iter().enumerate() {
+            // Subtract the disclosed attributes, leaving a commitment to the hidden ones
+            let statement = self.commitments[i].subtract_disclosed(public_params[i], &disclosed[i]);
 
             // Verify the Schnorr proof
             let is_valid = SchnorrProtocol::verify_schnorr(
-                &bases,
-                &self.commitments[i].cm,
+                bases,
+                &statement,
                 &self.schnorr_commitments[i].commited_blindings,
                 &self.responses[i],
-                &self.challenge,
+                &challenge,
             );
 
             if !is_valid {
@@ -131,19 +257,375 @@ impl<E: Pairing> IdentityBindingProof<E> {
             }
         }
 
-        // Verify that all responses at position 0 are identical
-        // This proves that the first attribute is the same in all commitments
-        let first_response = &self.responses[0][0];
-        for responses in self.responses.iter().skip(1) {
-            if &responses[0] != first_response {
-                return Ok(false);
-            }
+        // Verify that all responses for the user ID are identical
+        // This proves that the ID attribute is the same in all commitments
+        let id_responses: Vec<&E::ScalarField> = self
+            .responses
+            .iter()
+            .zip(disclosed)
+            .map(|(responses, revealed)| {
+                let indices: Vec<usize> = revealed.keys().copied().collect();
+                &responses[hidden_position(self.id_index, &indices)]
+            })
+            .collect();
+        if id_responses
+            .iter()
+            .any(|response| *response != id_responses[0])
+        {
+            return Ok(false);
         }
 
         Ok(true)
     }
 }
 
+// Drop the entries at the disclosed attribute indices; later entries (e.g. g, r) are kept
+fn hidden<T: Clone>(items: &[T], disclosed: &[usize]) -> Vec<T> {
+    items
+        .iter()
+        .enumerate()
+        .filter(|(j, _)| !disclosed.contains(j))
+        .map(|(_, item)| item.clone())
+        .collect()
+}
+
+// Position of attribute `index` once the disclosed attributes are dropped
+fn hidden_position(index: usize, disclosed: &[usize]) -> usize {
+    index - disclosed.iter().filter(|&&j| j < index).count()
+}
+
+/// Compute G1 bases once per distinct public params
+/// Returns the distinct bases and, for each input, the index of its bases
+pub(crate) fn shared_bases<E: Pairing>(
+    public_params: &[&PublicParams<E>],
+) -> (Vec<Vec<E::G1Affine>>, Vec<usize>) {
+    let mut distinct: Vec<&PublicParams<E>> = Vec::new();
+    let mut unique_bases = Vec::new();
+    let mut bases_index = Vec::with_capacity(public_params.len());
+
+    for pp in public_params {
+        // Same reference, or a copy of the same parameters
+        let existing = distinct
+            .iter()
+            .position(|seen| std::ptr::eq(*seen, *pp) || (seen.g == pp.g && seen.ck == pp.ck));
+        match existing {
+            Some(index) => bases_index.push(index),
+            None => {
+                distinct.push(pp);
+                unique_bases.push(pp.get_g1_bases().to_vec());
+                bases_index.push(unique_bases.len() - 1);
+            }
+        }
+    }
+
+    (unique_bases, bases_index)
+}
+
+// Format version of the serialized proof
+const IDENTITY_BINDING_VERSION: u8 = 3;
+
+// Only the announcements of the Schnorr commitments are encoded, never the prover's blindings
+impl<E: Pairing> CanonicalSerialize for IdentityBindingProof<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        write_version(&mut writer, IDENTITY_BINDING_VERSION)?;
+        (self.id_index as u64).serialize_with_mode(&mut writer, compress)?;
+        self.commitments
+            .serialize_with_mode(&mut writer, compress)?;
+        self.announcements()
+            .serialize_with_mode(&mut writer, compress)?;
+        self.challenge.serialize_with_mode(&mut writer, compress)?;
+        self.responses.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + 8
+            + self.commitments.serialized_size(compress)
+            + self.announcements().serialized_size(compress)
+            + self.challenge.serialized_size(compress)
+            + self.responses.serialized_size(compress)
+    }
+}
+
+impl<E: Pairing> Valid for IdentityBindingProof<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.commitments.check()?;
+        self.announcements().check()
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for IdentityBindingProof<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        read_version(&mut reader, IDENTITY_BINDING_VERSION)?;
+        let id_index = u64::deserialize_with_mode(&mut reader, compress, validate)?;
+        let id_index = usize::try_from(id_index).map_err(|_| SerializationError::InvalidData)?;
+        let commitments = Vec::deserialize_with_mode(&mut reader, compress, validate)?;
+        let announcements: Vec<E::G1Affine> =
+            Vec::deserialize_with_mode(&mut reader, compress, validate)?;
+        Ok(Self {
+            id_index,
+            commitments,
+            schnorr_commitments: announcements
+                .into_iter()
+                .map(|commited_blindings| SchnorrCommitment {
+                    random_blindings: Vec::new(),
+                    commited_blindings,
+                })
+                .collect(),
+            challenge: E::ScalarField::deserialize_with_mode(&mut reader, compress, validate)?,
+            responses: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+impl<E: Pairing> IdentityBindingProof<E> {
+    fn announcements(&self) -> Vec<E::G1Affine> {
+        self.schnorr_commitments
+            .iter()
+            .map(|commitment| commitment.commited_blindings)
+            .collect()
+    }
+
+    // Fiat-Shamir challenge over the ID position, and per credential its commitment, the
+    // bases its proof runs over, the disclosed values and the announcement
+    fn challenge(
+        id_index: usize,
+        commitments: &[Commitment<E>],
+        bases: &[Vec<E::G1Affine>],
+        disclosed: &[BTreeMap<usize, E::ScalarField>],
+        announcements: &[E::G1Affine],
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-identity-binding");
+        transcript.append_message(b"id_index", &(id_index as u64).to_le_bytes());
+        for (((commitment, bases), revealed), announcement) in commitments
+            .iter()
+            .zip(bases)
+            .zip(disclosed)
+            .zip(announcements)
+        {
+            transcript.append_serializable(b"cm", &commitment.cm);
+            transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+            transcript.append_serializable(b"bases", bases);
+            for (index, value) in revealed {
+                transcript.append_message(b"disclosed_index", &(*index as u64).to_le_bytes());
+                transcript.append_serializable(b"disclosed_value", value);
+            }
+            transcript.append_serializable(b"announcement", announcement);
+        }
+        transcript.challenge_scalar(b"c")
+    }
+}
+
+/// Proof that attribute `index_a` of one commitment equals attribute `index_b` of another,
+/// e.g. a date of birth kept at different positions by two issuers' schemas
+/// The position-0 technique of `IdentityBindingProof` at arbitrary positions: both Schnorr
+/// commitments use one blinding at the chosen attributes, so their responses are equal.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AttributeEqualityProof<E: Pairing> {
+    pub index_a: usize,
+    pub index_b: usize,
+    pub commitment_a: Commitment<E>,
+    pub commitment_b: Commitment<E>,
+    pub announcement_a: E::G1Affine,
+    pub announcement_b: E::G1Affine,
+    pub responses_a: Vec<E::ScalarField>, // attributes, then r
+    pub responses_b: Vec<E::ScalarField>,
+}
+
+impl<E: Pairing> AttributeEqualityProof<E> {
+    /// Prove over the credentials' own commitments
+    /// These are the issued ones, so the verifier can link the proof to issuance; use
+    /// `prove_shown` to prove over presentations instead
+    pub fn prove(
+        cred_a: &Credential<E>,
+        idx_a: usize,
+        cred_b: &Credential<E>,
+        idx_b: usize,
+        pp_a: &PublicParams<E>,
+        pp_b: &PublicParams<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        Self::prove_openings(
+            (
+                &cred_a.commitment,
+                cred_a.get_messages(),
+                cred_a.get_r(),
+                idx_a,
+            ),
+            (
+                &cred_b.commitment,
+                cred_b.get_messages(),
+                cred_b.get_r(),
+                idx_b,
+            ),
+            pp_a,
+            pp_b,
+            rng,
+        )
+    }
+
+    /// Prove over the randomized commitments of `show_a` and `show_b`, presentations of
+    /// `cred_a` and `cred_b`, so the proof is as unlinkable as the presentations
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_shown(
+        cred_a: &Credential<E>,
+        show_a: &ShowCredential<E>,
+        idx_a: usize,
+        cred_b: &Credential<E>,
+        show_b: &ShowCredential<E>,
+        idx_b: usize,
+        pp_a: &PublicParams<E>,
+        pp_b: &PublicParams<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        Self::prove_openings(
+            (
+                &show_a.randomized_commitment,
+                cred_a.get_messages(),
+                &show_a.r_new,
+                idx_a,
+            ),
+            (
+                &show_b.randomized_commitment,
+                cred_b.get_messages(),
+                &show_b.r_new,
+                idx_b,
+            ),
+            pp_a,
+            pp_b,
+            rng,
+        )
+    }
+
+    // Each side is (commitment, messages, r, index)
+    #[allow(clippy::type_complexity)]
+    fn prove_openings(
+        a: (&Commitment<E>, &[E::ScalarField], &E::ScalarField, usize),
+        b: (&Commitment<E>, &[E::ScalarField], &E::ScalarField, usize),
+        pp_a: &PublicParams<E>,
+        pp_b: &PublicParams<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let (commitment_a, messages_a, r_a, index_a) = a;
+        let (commitment_b, messages_b, r_b, index_b) = b;
+        if messages_a.len() != pp_a.n
+            || messages_b.len() != pp_b.n
+            || index_a >= pp_a.n
+            || index_b >= pp_b.n
+        {
+            return Err(Error::Other("Attribute index out of range".to_string()));
+        }
+        if messages_a[index_a] != messages_b[index_b] {
+            return Err(Error::Other("Attributes are not equal".to_string()));
+        }
+
+        // One blinding at both chosen positions
+        let common_blinding = E::ScalarField::rand(rng);
+        let blindings = |n: usize, index: usize, rng: &mut _| -> Vec<E::ScalarField> {
+            (0..=n)
+                .map(|j| {
+                    if j == index {
+                        common_blinding
+                    } else {
+                        E::ScalarField::rand(rng)
+                    }
+                })
+                .collect()
+        };
+        let schnorr_a = SchnorrProtocol::commit_with_prepared_blindings(
+            pp_a.get_g1_bases(),
+            &blindings(pp_a.n, index_a, rng),
+        );
+        let schnorr_b = SchnorrProtocol::commit_with_prepared_blindings(
+            pp_b.get_g1_bases(),
+            &blindings(pp_b.n, index_b, rng),
+        );
+
+        let challenge = Self::challenge(
+            (index_a, commitment_a, &schnorr_a.commited_blindings),
+            (index_b, commitment_b, &schnorr_b.commited_blindings),
+        );
+        let exponents = |messages: &[E::ScalarField], r: &E::ScalarField| {
+            let mut exponents = messages.to_vec();
+            exponents.push(*r);
+            exponents
+        };
+
+        Ok(Self {
+            index_a,
+            index_b,
+            commitment_a: commitment_a.clone(),
+            commitment_b: commitment_b.clone(),
+            announcement_a: schnorr_a.commited_blindings,
+            announcement_b: schnorr_b.commited_blindings,
+            responses_a: SchnorrProtocol::prove(
+                &schnorr_a,
+                &exponents(messages_a, r_a),
+                &challenge,
+            )
+            .0,
+            responses_b: SchnorrProtocol::prove(
+                &schnorr_b,
+                &exponents(messages_b, r_b),
+                &challenge,
+            )
+            .0,
+        })
+    }
+
+    /// Check both openings and that the responses at the two chosen positions are equal
+    pub fn verify(&self, pp_a: &PublicParams<E>, pp_b: &PublicParams<E>) -> bool {
+        if self.index_a >= pp_a.n
+            || self.index_b >= pp_b.n
+            || self.responses_a.len() != pp_a.n + 1
+            || self.responses_b.len() != pp_b.n + 1
+        {
+            return false;
+        }
+        let challenge = Self::challenge(
+            (self.index_a, &self.commitment_a, &self.announcement_a),
+            (self.index_b, &self.commitment_b, &self.announcement_b),
+        );
+        self.responses_a[self.index_a] == self.responses_b[self.index_b]
+            && SchnorrProtocol::verify_schnorr(
+                pp_a.get_g1_bases(),
+                &self.commitment_a.cm,
+                &self.announcement_a,
+                &self.responses_a,
+                &challenge,
+            )
+            && SchnorrProtocol::verify_schnorr(
+                pp_b.get_g1_bases(),
+                &self.commitment_b.cm,
+                &self.announcement_b,
+                &self.responses_b,
+                &challenge,
+            )
+    }
+
+    // Each side is (index, commitment, announcement)
+    fn challenge(
+        a: (usize, &Commitment<E>, &E::G1Affine),
+        b: (usize, &Commitment<E>, &E::G1Affine),
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-attribute-equality");
+        for (index, commitment, announcement) in [a, b] {
+            transcript.append_message(b"index", &(index as u64).to_le_bytes());
+            transcript.append_serializable(b"cm", &commitment.cm);
+            transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+            transcript.append_serializable(b"announcement", announcement);
+        }
+        transcript.challenge_scalar(b"c")
+    }
+}
+
 /// Module functions for simplified access
 pub struct IdentityBinding;
 
@@ -159,12 +641,12 @@ impl IdentityBinding {
         IdentityBindingProof::prove(commitments, messages, randomness, public_params, rng)
     }
 
-    /// Verify an identity binding proof
+    /// Verify an identity binding proof from `prove`, with the user ID at attribute 0
     pub fn verify<E: Pairing>(
         proof: &IdentityBindingProof<E>,
         public_params: &[&PublicParams<E>],
     ) -> Result<bool, Error> {
-        proof.verify(public_params)
+        proof.verify(public_params, 0)
     }
 }
 
@@ -172,6 +654,7 @@ impl IdentityBinding {
 mod tests {
     use super::*;
     use crate::commitment::CommitmentKey;
+    use crate::serialization::{from_bytes, to_bytes};
     use ark_bls12_381::{Bls12_381, Fr};
     use ark_std::test_rng;
 
@@ -247,6 +730,16 @@ mod tests {
             "Identity binding proof should verify successfully"
         );
 
+        // The challenge is bound to the statement: neither a chosen challenge nor another
+        // commitment passes, even with the ID responses still equal
+        let params = [&pp1, &pp2, &pp3];
+        let mut tampered: IdentityBindingProof<Bls12_381> = from_bytes(&to_bytes(&proof)).unwrap();
+        tampered.challenge += Fr::from(1u64);
+        assert!(!tampered.verify(&params, 0).unwrap());
+        tampered.challenge = proof.challenge;
+        tampered.commitments[1] = ck2.commit(&pp2, &messages1, &Fr::rand(&mut rng));
+        assert!(!tampered.verify(&params, 0).unwrap());
+
         // Test negative case: different user ID
         let different_id = Fr::rand(&mut rng);
         let mut messages4 = vec![different_id]; // Different ID!
@@ -270,4 +763,106 @@ mod tests {
             "Proof with different user IDs should fail"
         );
     }
+
+    #[test]
+    fn test_attribute_equality_proof() {
+        let mut rng = test_rng();
+        let (protocol_a, sk_a, vk_a) = crate::protocol::MimcAbc::<Bls12_381>::setup(3, &mut rng);
+        let (protocol_b, sk_b, vk_b) = crate::protocol::MimcAbc::<Bls12_381>::setup(5, &mut rng);
+        let (pp_a, pp_b) = (&protocol_a.pp, &protocol_b.pp);
+
+        // The date of birth is attribute 1 of a 3-attribute ID and attribute 3 of a
+        // 5-attribute licence
+        let dob = Fr::from(19_900_315u64);
+        let mut messages_a: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        messages_a[1] = dob;
+        let mut messages_b: Vec<Fr> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        messages_b[3] = dob;
+        let mut cred_a = Credential::new(&protocol_a.ck, pp_a, &messages_a, Fr::rand(&mut rng));
+        let mut cred_b = Credential::new(&protocol_b.ck, pp_b, &messages_b, Fr::rand(&mut rng));
+        protocol_a
+            .obtain_and_issue(&mut cred_a, &sk_a, &mut rng)
+            .unwrap();
+        protocol_b
+            .obtain_and_issue(&mut cred_b, &sk_b, &mut rng)
+            .unwrap();
+
+        let proof =
+            AttributeEqualityProof::prove(&cred_a, 1, &cred_b, 3, pp_a, pp_b, &mut rng).unwrap();
+        assert!(proof.verify(pp_a, pp_b));
+
+        // Over two presentations, which verify on their own
+        let show_a = cred_a.show_auto(pp_a, &mut rng).unwrap();
+        let show_b = cred_b.show_auto(pp_b, &mut rng).unwrap();
+        let proof = AttributeEqualityProof::prove_shown(
+            &cred_a, &show_a, 1, &cred_b, &show_b, 3, pp_a, pp_b, &mut rng,
+        )
+        .unwrap();
+        assert!(proof.verify(pp_a, pp_b));
+        assert!(show_a.verify(pp_a, &vk_a) && show_b.verify(pp_b, &vk_b));
+        assert_eq!(proof.commitment_a.cm, show_a.randomized_commitment.cm);
+
+        // Claiming other positions, or differing attributes, fails
+        let mut moved = proof.clone();
+        moved.index_b = 2;
+        assert!(!moved.verify(pp_a, pp_b));
+        assert!(
+            AttributeEqualityProof::prove(&cred_a, 0, &cred_b, 3, pp_a, pp_b, &mut rng).is_err()
+        );
+        assert!(
+            AttributeEqualityProof::prove(&cred_a, 3, &cred_b, 3, pp_a, pp_b, &mut rng).is_err()
+        );
+    }
+
+    #[test]
+    fn test_identity_binding_shared_params() {
+        let mut rng = test_rng();
+
+        // Three credentials from the same issuer
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+
+        let user_id = Fr::rand(&mut rng);
+        let mut commitments = Vec::new();
+        let mut messages = Vec::new();
+        let mut randomness = Vec::new();
+        for _ in 0..3 {
+            let mut msgs = vec![user_id];
+            msgs.extend((1..n).map(|_| Fr::rand(&mut rng)));
+            let r = Fr::rand(&mut rng);
+            commitments.push(ck.commit(&pp, &msgs, &r));
+            messages.push(msgs);
+            randomness.push(r);
+        }
+
+        // Bases are computed once and shared by all three credentials
+        let params = [&pp, &pp, &pp];
+        let (unique_bases, bases_index) = shared_bases(&params);
+        assert_eq!(unique_bases.len(), 1, "Bases should be computed once");
+        assert_eq!(bases_index, vec![0, 0, 0]);
+
+        // A copy of the same parameters is also recognised
+        let pp_copy = pp.clone();
+        let (unique_bases, _) = shared_bases(&[&pp, &pp_copy]);
+        assert_eq!(unique_bases.len(), 1, "Cloned params should share bases");
+
+        // Verification result is unchanged
+        let proof = IdentityBinding::prove(&commitments, &messages, &randomness, &params, &mut rng)
+            .expect("Proof creation should succeed");
+        assert!(IdentityBinding::verify(&proof, &params).expect("Verification should complete"));
+
+        // The encoding carries announcements only and round-trips
+        let bytes = to_bytes(&proof);
+        let decoded: IdentityBindingProof<Bls12_381> = from_bytes(&bytes).unwrap();
+        assert!(decoded.schnorr_commitments[0].random_blindings.is_empty());
+        assert!(IdentityBinding::verify(&decoded, &params).unwrap());
+
+        let mut future = bytes;
+        future[0] = IDENTITY_BINDING_VERSION + 1;
+        assert!(from_bytes::<IdentityBindingProof<Bls12_381>>(&future).is_err());
+    }
 }
@@ -0,0 +1,4 @@
+// mimc_abc/src/inequality.rs
+// Proofs that committed attributes differ, without revealing them
+
+pub mod distinct;
@@ -0,0 +1,226 @@
+// mimc_abc/src/issuer_or.rs
+use crate::commitment::Commitment;
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::signature::{Signature, VerificationKey};
+use crate::transcript::Transcript;
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::ops::Mul;
+use ark_std::rand::Rng;
+
+/// Proof that a randomized signature verifies under one of several candidate keys, without revealing which
+///
+/// The holder blinds sigma2 by sigma1^t, so for the real key vk_k
+///     e(sigma2', g_tilde) - e(sigma1, cm_tilde) - e(sigma1, vk_k) = t * e(sigma1, g_tilde)
+/// and proves knowledge of t for one of the candidates with a CDS OR-composition of Schnorr proofs in G_T.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct IssuerOrProof<E: Pairing> {
+    pub announcements: Vec<PairingOutput<E>>,
+    pub challenges: Vec<E::ScalarField>,
+    pub responses: Vec<E::ScalarField>,
+}
+
+impl<E: Pairing> IssuerOrProof<E> {
+    /// Blind the signature and prove it verifies under `candidate_vks[real_index]`
+    /// Returns the blinded signature (to be sent instead of the original) and the proof
+    pub fn prove(
+        pp: &PublicParams<E>,
+        signature: &Signature<E>,
+        commitment: &Commitment<E>,
+        candidate_vks: &[&VerificationKey<E>],
+        real_index: usize,
+        rng: &mut impl Rng,
+    ) -> Result<(Signature<E>, Self), Error> {
+        if real_index >= candidate_vks.len() {
+            return Err(Error::Other(
+                "Real issuer index out of candidate range".to_string(),
+            ));
+        }
+
+        // Blind sigma2 so the real equation no longer evaluates to the identity
+        let t = E::ScalarField::rand(rng);
+        let blinded = Signature {
+            sigma1: signature.sigma1,
+            sigma2: (signature.sigma2 + signature.sigma1.mul(t)).into_affine(),
+        };
+
+        let base = E::pairing(blinded.sigma1, pp.g_tilde);
+        let statements = Self::statements(pp, &blinded, commitment, candidate_vks);
+
+        // Simulate every branch except the real one
+        let mut announcements = Vec::with_capacity(candidate_vks.len());
+        let mut challenges = Vec::with_capacity(candidate_vks.len());
+        let mut responses = Vec::with_capacity(candidate_vks.len());
+        let blinding = E::ScalarField::rand(rng);
+        for (j, statement) in statements.iter().enumerate() {
+            if j == real_index {
+                announcements.push(base.mul(blinding));
+                challenges.push(E::ScalarField::zero());
+                responses.push(E::ScalarField::zero());
+            } else {
+                let c_j = E::ScalarField::rand(rng);
+                let z_j = E::ScalarField::rand(rng);
+                announcements.push(base.mul(z_j) - statement.mul(c_j));
+                challenges.push(c_j);
+                responses.push(z_j);
+            }
+        }
+
+        // The real branch takes whatever challenge is left over
+        let challenge = Self::challenge(&blinded, commitment, candidate_vks, &announcements);
+        let simulated: E::ScalarField = challenges.iter().sum();
+        challenges[real_index] = challenge - simulated;
+        responses[real_index] = blinding + challenges[real_index] * t;
+
+        Ok((
+            blinded,
+            Self {
+                announcements,
+                challenges,
+                responses,
+            },
+        ))
+    }
+
+    /// Verify the blinded signature verifies under at least one candidate key
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        signature: &Signature<E>,
+        commitment: &Commitment<E>,
+        candidate_vks: &[&VerificationKey<E>],
+    ) -> bool {
+        let count = candidate_vks.len();
+        if count == 0
+            || self.announcements.len() != count
+            || self.challenges.len() != count
+            || self.responses.len() != count
+        {
+            return false;
+        }
+        // A degenerate sigma1 would satisfy every branch
+        if signature.sigma1.is_zero() {
+            return false;
+        }
+
+        let challenge = Self::challenge(signature, commitment, candidate_vks, &self.announcements);
+        let sum: E::ScalarField = self.challenges.iter().sum();
+        if sum != challenge {
+            return false;
+        }
+
+        let base = E::pairing(signature.sigma1, pp.g_tilde);
+        let statements = Self::statements(pp, signature, commitment, candidate_vks);
+        for (j, statement) in statements.iter().enumerate() {
+            let lhs = base.mul(self.responses[j]);
+            let rhs = self.announcements[j] + statement.mul(self.challenges[j]);
+            if lhs != rhs {
+                return false;
+            }
+        }
+        true
+    }
+
+    // X_j = e(sigma2', g_tilde) - e(sigma1, cm_tilde) - e(sigma1, vk_j)
+    fn statements(
+        pp: &PublicParams<E>,
+        signature: &Signature<E>,
+        commitment: &Commitment<E>,
+        candidate_vks: &[&VerificationKey<E>],
+    ) -> Vec<PairingOutput<E>> {
+        let common = E::pairing(signature.sigma2, pp.g_tilde)
+            - E::pairing(signature.sigma1, commitment.cm_tilde);
+        candidate_vks
+            .iter()
+            .map(|vk| common - E::pairing(signature.sigma1, vk.vk_tilde))
+            .collect()
+    }
+
+    fn challenge(
+        signature: &Signature<E>,
+        commitment: &Commitment<E>,
+        candidate_vks: &[&VerificationKey<E>],
+        announcements: &[PairingOutput<E>],
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-issuer-or");
+        transcript.append_serializable(b"sigma1", &signature.sigma1);
+        transcript.append_serializable(b"sigma2", &signature.sigma2);
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+        for vk in candidate_vks {
+            transcript.append_serializable(b"vk_tilde", &vk.vk_tilde);
+        }
+        for announcement in announcements {
+            transcript.append_serializable(b"announcement", announcement);
+        }
+        transcript.challenge_scalar(b"c")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::credential::Credential;
+    use crate::proof::CommitmentProof;
+    use crate::protocol::MimcAbc;
+    use crate::signature::generate_keys;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_show_issuer_or() {
+        let mut rng = test_rng();
+        let n = 4;
+
+        // Three issuers sharing the same public parameters
+        let (protocol, sk_a, vk_a) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let (_, vk_b) = generate_keys(&protocol.pp, &mut rng);
+        let (_, vk_c) = generate_keys(&protocol.pp, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        let proof = credential.prove_commitment(&protocol.pp, &mut rng);
+        let signature = protocol.issue(&proof, &sk_a, &mut rng).unwrap();
+        credential.add_signature(signature).unwrap();
+
+        // Real issuer is among the candidates
+        let presentation = credential
+            .show_issuer_or(&protocol.pp, &[&vk_b, &vk_a, &vk_c], &mut rng)
+            .expect("Issuer is a candidate, proof should build");
+        assert!(
+            presentation.verify_issuer_or(&protocol.pp, &[&vk_b, &vk_a, &vk_c]),
+            "OR-proof over candidates including the issuer should verify"
+        );
+
+        // The proof doesn't transfer to a candidate set without the issuer
+        assert!(
+            !presentation.verify_issuer_or(&protocol.pp, &[&vk_b, &vk_c, &vk_c]),
+            "OR-proof should fail against a different candidate set"
+        );
+
+        // The captured signature, commitment and OR-proof next to a fresh proof of a
+        // commitment the replayer can open
+        let own: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let own_r = Fr::rand(&mut rng);
+        let own_commitment = protocol.ck.commit(&protocol.pp, &own, &own_r);
+        let mut replayed = presentation.clone();
+        replayed.proof =
+            CommitmentProof::prove(&protocol.pp, &own_commitment, &own, &own_r, &mut rng);
+        assert!(replayed.proof.verify_against(&protocol.pp));
+        assert!(
+            !replayed.verify_issuer_or(&protocol.pp, &[&vk_b, &vk_a, &vk_c]),
+            "Opening proof of another commitment should fail"
+        );
+
+        // Real issuer absent: the prover can't build a proof
+        let result = credential.show_issuer_or(&protocol.pp, &[&vk_b, &vk_c], &mut rng);
+        assert!(
+            result.is_err(),
+            "Prover without a matching candidate shouldn't build a proof"
+        );
+    }
+}
@@ -1,15 +1,37 @@
+pub mod attributes;
+pub mod batch_verifier;
 pub mod commitment;
 pub mod credential;
+pub mod descent;
 pub mod error;
 pub mod identity_binding;
+pub mod inequality;
+pub mod issuer_or;
+pub mod linear;
 pub mod linked_credentials;
+pub mod membership;
 pub mod multi_credential;
 pub mod multi_identity_credentials;
 pub mod multi_issuer;
+pub mod nullifier;
+pub mod ordering;
 pub mod pairing;
+pub mod policy;
+pub mod predicate;
+pub mod presentation;
+pub mod projection;
 pub mod proof;
 pub mod protocol;
 pub mod public_params;
+pub mod range_proof;
+pub mod reissue;
+pub mod revocation;
 pub mod schnorr;
+pub mod serialization;
 pub mod signature;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_support;
+pub mod threshold;
+pub mod transcript;
+pub mod verifier_context;
 pub mod verkey;
@@ -0,0 +1,211 @@
+// mimc_abc/src/linear.rs
+// Linear relations between a hidden attribute and public values
+use crate::attributes::decode_u128;
+use crate::commitment::Commitment;
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::range_proof::{pedersen_bases, RangeProof, RangeProver};
+use crate::schnorr::SchnorrProtocol;
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::CurveGroup;
+use ark_ff::UniformRand;
+use ark_std::ops::Mul;
+use ark_std::rand::Rng;
+
+/// Proof that hidden attribute `index` equals k * `public` for some hidden integer k
+///
+/// k is committed to as K = k * g + s * ck[0] and its Schnorr blinding, scaled by `public`,
+/// is the blinding of the attribute in the opening proof of the commitment, so the responses
+/// satisfy z_attr = public * z_k exactly when attr = public * k. Every field element is a
+/// multiple of a nonzero `public` in the field, so k is also proven to lie in [0, 2^64):
+/// with public < 2^64 the product can't wrap around, and the relation holds over the integers.
+#[derive(Clone, Debug)]
+pub struct MultipleOfProof<E: Pairing> {
+    pub index: usize,
+    pub public: u64,
+    pub k_commitment: E::G1Affine,
+    pub k_announcement: E::G1Affine,
+    pub k_responses: (E::ScalarField, E::ScalarField), // k, then s
+    pub opening_announcement: E::G1Affine,
+    pub opening_responses: Vec<E::ScalarField>, // attributes, then r
+    pub k_range: RangeProof<E>,
+}
+
+impl<E: Pairing> MultipleOfProof<E> {
+    pub fn prove(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        index: usize,
+        messages: &[E::ScalarField],
+        public: u64,
+        r: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        if messages.len() != pp.n || index >= pp.n {
+            return Err(Error::Other("Attribute index out of range".to_string()));
+        }
+        if public == 0 {
+            return Err(Error::Other("Public divisor must be nonzero".to_string()));
+        }
+        let value = decode_u128(&messages[index])?;
+        if value % public as u128 != 0 {
+            return Err(Error::Other(format!(
+                "Attribute {} is not a multiple of {}",
+                index, public
+            )));
+        }
+        let k = u64::try_from(value / public as u128)
+            .map_err(|_| Error::Other("Multiple doesn't fit in 64 bits".to_string()))?;
+
+        let (g, h) = pedersen_bases(pp);
+        let k_scalar = E::ScalarField::from(k);
+        let s = E::ScalarField::rand(rng);
+        let k_commitment = (g.mul(k_scalar) + h.mul(s)).into_affine();
+        let k_opening = SchnorrProtocol::commit(&[g, h], rng);
+        let k_nonce = k_opening.random_blindings[0];
+
+        // The attribute's blinding is public * k's, everything else is fresh
+        let mut blindings: Vec<E::ScalarField> =
+            (0..=pp.n).map(|_| E::ScalarField::rand(rng)).collect();
+        blindings[index] = E::ScalarField::from(public) * k_nonce;
+        let opening =
+            SchnorrProtocol::commit_with_prepared_blindings(pp.get_g1_bases(), &blindings);
+
+        let k_range = RangeProver::commit(pp, index, k, 0, u64::MAX, &k_nonce, rng)?;
+
+        let mut transcript = Self::transcript(
+            index,
+            public,
+            commitment,
+            &k_commitment,
+            &k_opening.commited_blindings,
+            &opening.commited_blindings,
+        );
+        k_range.absorb(&mut transcript);
+        let challenge: E::ScalarField = transcript.challenge_scalar(b"c");
+
+        let k_responses = SchnorrProtocol::prove(&k_opening, &[k_scalar, s], &challenge).0;
+        let mut exponents = messages.to_vec();
+        exponents.push(*r);
+
+        Ok(Self {
+            index,
+            public,
+            k_commitment,
+            k_announcement: k_opening.commited_blindings,
+            k_responses: (k_responses[0], k_responses[1]),
+            opening_announcement: opening.commited_blindings,
+            opening_responses: SchnorrProtocol::prove(&opening, &exponents, &challenge).0,
+            k_range: k_range.respond(&challenge),
+        })
+    }
+
+    pub fn verify(&self, pp: &PublicParams<E>, commitment: &Commitment<E>) -> bool {
+        if self.index >= pp.n
+            || self.public == 0
+            || self.opening_responses.len() != pp.n + 1
+            || self.k_range.index != self.index
+            || self.k_range.min != 0
+            || self.k_range.max != u64::MAX
+        {
+            return false;
+        }
+
+        let mut transcript = Self::transcript(
+            self.index,
+            self.public,
+            commitment,
+            &self.k_commitment,
+            &self.k_announcement,
+            &self.opening_announcement,
+        );
+        self.k_range.absorb(&mut transcript);
+        let challenge: E::ScalarField = transcript.challenge_scalar(b"c");
+
+        let (g, h) = pedersen_bases(pp);
+        let (k_response, s_response) = self.k_responses;
+        if !SchnorrProtocol::verify_schnorr(
+            &[g, h],
+            &self.k_commitment,
+            &self.k_announcement,
+            &[k_response, s_response],
+            &challenge,
+        ) {
+            return false;
+        }
+        if !SchnorrProtocol::verify_schnorr(
+            pp.get_g1_bases(),
+            &commitment.cm,
+            &self.opening_announcement,
+            &self.opening_responses,
+            &challenge,
+        ) {
+            return false;
+        }
+
+        self.opening_responses[self.index] == E::ScalarField::from(self.public) * k_response
+            && self.k_range.verify(pp, &challenge, &k_response)
+    }
+
+    fn transcript(
+        index: usize,
+        public: u64,
+        commitment: &Commitment<E>,
+        k_commitment: &E::G1Affine,
+        k_announcement: &E::G1Affine,
+        opening_announcement: &E::G1Affine,
+    ) -> Transcript {
+        let mut transcript = Transcript::new(b"mimc-abc-multiple-of");
+        transcript.append_message(b"index", &(index as u64).to_le_bytes());
+        transcript.append_message(b"public", &public.to_le_bytes());
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+        transcript.append_serializable(b"k_commitment", k_commitment);
+        transcript.append_serializable(b"k_announcement", k_announcement);
+        transcript.append_serializable(b"opening_announcement", opening_announcement);
+        transcript
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commitment::CommitmentKey;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_multiple_of_proof() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let mut messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+
+        // Quantity 120 is a multiple of 10
+        messages[1] = Fr::from(120u64);
+        let commitment = ck.commit(&pp, &messages, &r);
+        let proof =
+            MultipleOfProof::prove(&pp, &commitment, 1, &messages, 10, &r, &mut rng).unwrap();
+        assert!(proof.verify(&pp, &commitment));
+
+        // Claiming another divisor or another attribute fails
+        let mut other = proof.clone();
+        other.public = 7;
+        assert!(!other.verify(&pp, &commitment));
+        let mut other = proof;
+        other.index = 2;
+        other.k_range.index = 2;
+        assert!(!other.verify(&pp, &commitment));
+
+        // 125 isn't a multiple of 10
+        messages[1] = Fr::from(125u64);
+        let commitment = ck.commit(&pp, &messages, &r);
+        assert!(MultipleOfProof::prove(&pp, &commitment, 1, &messages, 10, &r, &mut rng).is_err());
+    }
+}
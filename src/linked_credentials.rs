@@ -1,28 +1,53 @@
+use crate::commitment::Commitment;
 use crate::credential::{Credential, ShowCredential};
 use crate::error::Error;
-use crate::identity_binding::{IdentityBinding, IdentityBindingProof};
+use crate::identity_binding::IdentityBindingProof;
+use crate::multi_credential::batch_verify_commitment_consistency;
+use crate::pairing::{nonzero_scalar, PairingCheck};
 use crate::public_params::PublicParams;
 use crate::signature::VerificationKey;
 use ark_ec::pairing::Pairing;
-use ark_ff::UniformRand;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{One, UniformRand, Zero};
+use ark_std::ops::{Add, Neg};
 use ark_std::rand::Rng;
+use std::collections::BTreeMap;
 
 /// Represents multiple credentials shown together with proof that they share the same identity
 pub struct LinkedCredentialPresentation<E: Pairing> {
     pub credential_presentations: Vec<ShowCredential<E>>, // Individual presentations of each credential
     pub identity_proof: IdentityBindingProof<E>, // Proof that all credentials share the same identity
+    pub disclosed: Vec<BTreeMap<usize, E::ScalarField>>, // Revealed attributes of each credential
 }
 
 impl<E: Pairing> LinkedCredentialPresentation<E> {
     /// Create a linked presentation from multiple credentials
+    /// `disclosed[i]` lists the attributes of credential i to reveal; the user ID stays hidden
     pub fn create(
         credentials: &[&Credential<E>],
         public_params: &[&PublicParams<E>],
+        disclosed: &[Vec<usize>],
         rng: &mut impl Rng,
     ) -> Result<Self, Error> {
         if credentials.is_empty() {
             return Err(Error::Other("No credentials provided".to_string()));
         }
+        if disclosed.len() != credentials.len() {
+            return Err(Error::Other(
+                "One disclosure list is needed per credential".to_string(),
+            ));
+        }
+
+        // The binding is over one attribute, every credential must keep the ID there
+        let id_index = credentials[0].id_index();
+        if credentials
+            .iter()
+            .any(|credential| credential.id_index() != id_index)
+        {
+            return Err(Error::Other(
+                "Credentials keep the user ID at different indices".to_string(),
+            ));
+        }
 
         // First, create individual credential presentations with randomization
         let mut credential_presentations = Vec::with_capacity(credentials.len());
@@ -31,12 +56,8 @@ impl<E: Pairing> LinkedCredentialPresentation<E> {
         let mut randomness = Vec::with_capacity(credentials.len());
 
         for (i, credential) in credentials.iter().enumerate() {
-            // Generate randomization factors
-            let delta_r = E::ScalarField::rand(rng);
-            let delta_u = E::ScalarField::rand(rng);
-
             // Show the credential (creating randomized presentation)
-            let presentation = credential.show(public_params[i], &delta_r, &delta_u, rng);
+            let presentation = credential.show_auto(public_params[i], rng)?;
 
             // Store the randomized values for the identity binding proof
             randomized_commitments.push(presentation.randomized_commitment.clone());
@@ -47,28 +68,87 @@ impl<E: Pairing> LinkedCredentialPresentation<E> {
         }
 
         // Create the identity binding proof using the randomized commitments
-        let identity_proof = IdentityBinding::prove(
+        let identity_proof = IdentityBindingProof::prove_with_id_index(
             &randomized_commitments,
             &messages,
             &randomness,
+            disclosed,
+            id_index,
             public_params,
             rng,
         )?;
 
+        let disclosed = disclosed
+            .iter()
+            .zip(&messages)
+            .map(|(indices, msgs)| indices.iter().map(|&j| (j, msgs[j])).collect())
+            .collect();
+
         Ok(LinkedCredentialPresentation {
             credential_presentations,
             identity_proof,
+            disclosed,
         })
     }
 
-    /// Verify a linked credential presentation
+    /// Sum of the randomized commitments, a session anchor for downstream protocols
+    pub fn aggregate_commitment(&self) -> Commitment<E> {
+        let (cm, cm_tilde) = self.credential_presentations.iter().fold(
+            (E::G1::zero(), E::G2::zero()),
+            |(cm, cm_tilde), presentation| {
+                (
+                    cm + presentation.randomized_commitment.cm,
+                    cm_tilde + presentation.randomized_commitment.cm_tilde,
+                )
+            },
+        );
+        Commitment {
+            cm: cm.into_affine(),
+            cm_tilde: cm_tilde.into_affine(),
+        }
+    }
+
+    /// Check `aggregate` is the sum of this presentation's commitments and every summand is
+    /// consistent across G1 and G2, so the aggregate is too. Summands under the same
+    /// parameters share one weighted pairing check; with a single issuer that is
+    /// e(cm, g_tilde) == e(g, cm_tilde) on the aggregate itself.
+    pub fn verify_aggregate_commitment(
+        &self,
+        aggregate: &Commitment<E>,
+        public_params: &[&PublicParams<E>],
+    ) -> bool {
+        if public_params.len() != self.credential_presentations.len() {
+            return false;
+        }
+        let expected = self.aggregate_commitment();
+        if expected.cm != aggregate.cm || expected.cm_tilde != aggregate.cm_tilde {
+            return false;
+        }
+
+        let mut groups: Vec<(&PublicParams<E>, Vec<&Commitment<E>>)> = Vec::new();
+        for (presentation, &pp) in self.credential_presentations.iter().zip(public_params) {
+            let commitment = &presentation.randomized_commitment;
+            match groups
+                .iter_mut()
+                .find(|(other, _)| other.g == pp.g && other.g_tilde == pp.g_tilde)
+            {
+                Some((_, commitments)) => commitments.push(commitment),
+                None => groups.push((pp, vec![commitment])),
+            }
+        }
+        groups
+            .iter()
+            .all(|(pp, commitments)| batch_verify_commitment_consistency(commitments, pp))
+    }
+
+    /// Verify a linked credential presentation whose schemas keep the user ID at `id_index`
     pub fn verify(
         &self,
         public_params: &[&PublicParams<E>],
         verification_keys: &[&VerificationKey<E>],
+        id_index: usize,
     ) -> Result<bool, Error> {
-        // Verify the identity binding proof
-        if !IdentityBinding::verify(&self.identity_proof, public_params)? {
+        if !self.verify_binding(public_params, id_index)? {
             return Ok(false);
         }
 
@@ -81,6 +161,116 @@ impl<E: Pairing> LinkedCredentialPresentation<E> {
 
         Ok(true)
     }
+
+    /// `verify` with the signature and commitment consistency equations of every credential
+    /// folded into one randomly weighted `PairingCheck`, so a single final exponentiation
+    /// replaces the four pairings per credential. The identity binding proof and each
+    /// opening proof are still checked on their own.
+    pub fn verify_batched(
+        &self,
+        public_params: &[&PublicParams<E>],
+        verification_keys: &[&VerificationKey<E>],
+        id_index: usize,
+        rng: &mut impl Rng,
+    ) -> Result<bool, Error> {
+        if public_params.len() != self.credential_presentations.len()
+            || verification_keys.len() != self.credential_presentations.len()
+        {
+            return Ok(false);
+        }
+        if !self.verify_binding(public_params, id_index)? {
+            return Ok(false);
+        }
+
+        let mut check = PairingCheck::<E>::new();
+        for ((presentation, &pp), &vk) in self
+            .credential_presentations
+            .iter()
+            .zip(public_params)
+            .zip(verification_keys)
+        {
+            if !presentation.verify_proof(pp)
+                || presentation.recompute_commitment(pp, &presentation.disclosed)
+                    != presentation.randomized_commitment.cm
+            {
+                return Ok(false);
+            }
+
+            // e(sigma2, g_tilde) == e(sigma1, vk_tilde + cm_tilde), e(cm, g_tilde) == e(g, cm_tilde)
+            let signature = &presentation.randomized_signature;
+            let commitment = &presentation.randomized_commitment;
+            let vk_plus_cm_tilde = vk.vk_tilde.add(commitment.cm_tilde).into_affine();
+            check.merge(&PairingCheck::<E>::scaled(
+                nonzero_scalar(|| E::ScalarField::rand(rng)),
+                &[
+                    (&signature.sigma2, &pp.g_tilde),
+                    (
+                        &signature.sigma1.into_group().neg().into_affine(),
+                        &vk_plus_cm_tilde,
+                    ),
+                ],
+                &E::TargetField::one(),
+            ));
+            check.merge(&PairingCheck::<E>::scaled(
+                nonzero_scalar(|| E::ScalarField::rand(rng)),
+                &[
+                    (&commitment.cm, &pp.g_tilde),
+                    (&pp.g.into_group().neg().into_affine(), &commitment.cm_tilde),
+                ],
+                &E::TargetField::one(),
+            ));
+        }
+
+        Ok(check.verify())
+    }
+
+    // The identity binding proof is about the presented commitments, links them through
+    // `id_index` and opens the disclosed attributes
+    fn verify_binding(
+        &self,
+        public_params: &[&PublicParams<E>],
+        id_index: usize,
+    ) -> Result<bool, Error> {
+        if self.credential_presentations.len() != self.identity_proof.commitments.len() {
+            return Ok(false);
+        }
+
+        // The binding proof has to be about the commitments being presented
+        for (presentation, commitment) in self
+            .credential_presentations
+            .iter()
+            .zip(&self.identity_proof.commitments)
+        {
+            if presentation.randomized_commitment.cm != commitment.cm
+                || presentation.randomized_commitment.cm_tilde != commitment.cm_tilde
+            {
+                return Ok(false);
+            }
+        }
+
+        // Verify the identity binding proof, which also opens the disclosed attributes
+        self.identity_proof
+            .verify_with_disclosure(public_params, &self.disclosed, id_index)
+    }
+
+    /// `verify`, also requiring every credential to come from a different issuer,
+    /// e.g. for two independent attestations of the same user
+    pub fn verify_distinct_issuers(
+        &self,
+        public_params: &[&PublicParams<E>],
+        verification_keys: &[&VerificationKey<E>],
+        id_index: usize,
+    ) -> Result<bool, Error> {
+        for (i, vk) in verification_keys.iter().enumerate() {
+            if verification_keys[..i]
+                .iter()
+                .any(|other| other.vk_tilde == vk.vk_tilde)
+            {
+                return Ok(false);
+            }
+        }
+        self.verify(public_params, verification_keys, id_index)
+    }
 }
 
 #[cfg(test)]
@@ -88,6 +278,7 @@ mod tests {
     use super::*;
     use crate::protocol::MimcAbc;
     use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::UniformRand;
     use ark_std::test_rng;
 
     #[test]
@@ -131,20 +322,21 @@ mod tests {
             .issue(&proof2, &sk2, &mut rng)
             .expect("Issuance failed");
 
-        credential1.add_signature(signature1);
-        credential2.add_signature(signature2);
+        credential1.add_signature(signature1).unwrap();
+        credential2.add_signature(signature2).unwrap();
 
         // Create linked presentation
         let linked_presentation = LinkedCredentialPresentation::create(
             &[&credential1, &credential2],
             &[&protocol1.pp, &protocol2.pp],
+            &[vec![], vec![]],
             &mut rng,
         )
         .expect("Linked presentation creation failed");
 
         // Verify the linked presentation
         let is_valid = linked_presentation
-            .verify(&[&protocol1.pp, &protocol2.pp], &[&vk1, &vk2])
+            .verify(&[&protocol1.pp, &protocol2.pp], &[&vk1, &vk2], 0)
             .expect("Verification failed");
 
         assert!(is_valid, "Linked credential presentation should verify");
@@ -163,12 +355,13 @@ mod tests {
         let signature3 = protocol1
             .issue(&proof3, &sk1, &mut rng)
             .expect("Issuance failed");
-        credential3.add_signature(signature3);
+        credential3.add_signature(signature3).unwrap();
 
         // This should fail during creation
         let invalid_presentation = LinkedCredentialPresentation::create(
             &[&credential1, &credential3],
             &[&protocol1.pp, &protocol1.pp],
+            &[vec![], vec![]],
             &mut rng,
         );
 
@@ -177,4 +370,402 @@ mod tests {
             "Creating linked presentation with different IDs should fail"
         );
     }
+
+    #[test]
+    fn test_aggregate_commitment() {
+        use ark_ec::pairing::PairingOutput;
+        use ark_std::ops::Add;
+
+        let mut rng = test_rng();
+        let n = 4;
+        let user_id = Fr::rand(&mut rng);
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        // Two credentials for one user from the same issuer
+        let credentials: Vec<Credential<Bls12_381>> = (0..2)
+            .map(|_| {
+                let mut messages = vec![user_id];
+                messages.extend((1..n).map(|_| Fr::rand(&mut rng)));
+                let mut credential =
+                    Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+                protocol
+                    .obtain_and_issue(&mut credential, &sk, &mut rng)
+                    .unwrap();
+                credential
+            })
+            .collect();
+        let pps = [&protocol.pp, &protocol.pp];
+        let presentation = LinkedCredentialPresentation::create(
+            &[&credentials[0], &credentials[1]],
+            &pps,
+            &[vec![], vec![]],
+            &mut rng,
+        )
+        .unwrap();
+        assert!(presentation.verify(&pps, &[&vk, &vk], 0).unwrap());
+
+        let aggregate = presentation.aggregate_commitment();
+        let [first, second] = [0, 1].map(|i| &presentation.credential_presentations[i]);
+        assert_eq!(
+            aggregate.cm,
+            first
+                .randomized_commitment
+                .cm
+                .add(second.randomized_commitment.cm)
+                .into_affine()
+        );
+        assert_eq!(
+            aggregate.cm_tilde,
+            first
+                .randomized_commitment
+                .cm_tilde
+                .add(second.randomized_commitment.cm_tilde)
+                .into_affine()
+        );
+        let lhs: PairingOutput<Bls12_381> = Bls12_381::pairing(aggregate.cm, protocol.pp.g_tilde);
+        assert_eq!(lhs, Bls12_381::pairing(protocol.pp.g, aggregate.cm_tilde));
+        assert!(presentation.verify_aggregate_commitment(&aggregate, &pps));
+
+        // Any other point isn't accepted as the aggregate
+        let mut wrong = aggregate;
+        wrong.cm = (wrong.cm + protocol.pp.g).into_affine();
+        assert!(!presentation.verify_aggregate_commitment(&wrong, &pps));
+    }
+
+    #[test]
+    fn test_linked_presentation_with_disclosure() {
+        let mut rng = test_rng();
+        let n = 4;
+        let user_id = Fr::rand(&mut rng);
+
+        // Three issuers, each signing a credential for the same user
+        let mut protocols = Vec::new();
+        let mut vks = Vec::new();
+        let mut credentials = Vec::new();
+        for _ in 0..3 {
+            let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+            let mut messages = vec![user_id];
+            messages.extend((1..n).map(|_| Fr::rand(&mut rng)));
+            let r = Fr::rand(&mut rng);
+            let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+            let proof = protocol.obtain(&credential, &mut rng);
+            credential
+                .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+                .unwrap();
+            protocols.push(protocol);
+            vks.push(vk);
+            credentials.push(credential);
+        }
+        let cred_refs: Vec<&Credential<Bls12_381>> = credentials.iter().collect();
+        let pp_refs: Vec<&PublicParams<Bls12_381>> = protocols.iter().map(|p| &p.pp).collect();
+        let vk_refs: Vec<&VerificationKey<Bls12_381>> = vks.iter().collect();
+
+        // Attribute 1 from A, attribute 2 from B, nothing from C
+        let presentation = LinkedCredentialPresentation::create(
+            &cred_refs,
+            &pp_refs,
+            &[vec![1], vec![2], vec![]],
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(
+            presentation.disclosed[0][&1],
+            credentials[0].get_messages()[1]
+        );
+        assert_eq!(
+            presentation.disclosed[1][&2],
+            credentials[1].get_messages()[2]
+        );
+        assert!(presentation.disclosed[2].is_empty());
+        assert!(presentation.verify(&pp_refs, &vk_refs, 0).unwrap());
+
+        // A wrong disclosed value fails
+        let mut tampered = presentation;
+        tampered.disclosed[1].insert(2, Fr::rand(&mut rng));
+        assert!(!tampered.verify(&pp_refs, &vk_refs, 0).unwrap());
+
+        // The user ID can't be disclosed, it carries the binding
+        assert!(LinkedCredentialPresentation::create(
+            &cred_refs,
+            &pp_refs,
+            &[vec![0], vec![], vec![]],
+            &mut rng,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_linked_presentation_discloses_across_credentials() {
+        let mut rng = test_rng();
+        let n = 5;
+        let user_id = Fr::rand(&mut rng);
+
+        // Credentials A and B from two issuers, bound to the same user
+        let mut protocols = Vec::new();
+        let mut vks = Vec::new();
+        let mut credentials = Vec::new();
+        for _ in 0..2 {
+            let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+            let mut messages = vec![user_id];
+            messages.extend((1..n).map(|_| Fr::rand(&mut rng)));
+            let mut credential =
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+            let proof = protocol.obtain(&credential, &mut rng);
+            credential
+                .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+                .unwrap();
+            protocols.push(protocol);
+            vks.push(vk);
+            credentials.push(credential);
+        }
+        let cred_refs: Vec<&Credential<Bls12_381>> = credentials.iter().collect();
+        let pp_refs: Vec<&PublicParams<Bls12_381>> = protocols.iter().map(|p| &p.pp).collect();
+        let vk_refs: Vec<&VerificationKey<Bls12_381>> = vks.iter().collect();
+
+        // Attribute 2 from A, attribute 4 from B
+        let presentation = LinkedCredentialPresentation::create(
+            &cred_refs,
+            &pp_refs,
+            &[vec![2], vec![4]],
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(presentation.disclosed[0].len(), 1);
+        assert_eq!(presentation.disclosed[1].len(), 1);
+        assert_eq!(
+            presentation.disclosed[0][&2],
+            credentials[0].get_messages()[2]
+        );
+        assert_eq!(
+            presentation.disclosed[1][&4],
+            credentials[1].get_messages()[4]
+        );
+        assert!(presentation.verify(&pp_refs, &vk_refs, 0).unwrap());
+
+        // A's value claimed for B's commitment doesn't open it
+        let mut swapped = presentation;
+        swapped.disclosed[1] = BTreeMap::from([(4, credentials[0].get_messages()[4])]);
+        assert!(!swapped.verify(&pp_refs, &vk_refs, 0).unwrap());
+    }
+
+    #[test]
+    fn test_verify_batched() {
+        let mut rng = test_rng();
+        let n = 4;
+        let user_id = Fr::rand(&mut rng);
+
+        let mut protocols = Vec::new();
+        let mut vks = Vec::new();
+        let mut credentials = Vec::new();
+        for _ in 0..3 {
+            let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+            let mut messages = vec![user_id];
+            messages.extend((1..n).map(|_| Fr::rand(&mut rng)));
+            let mut credential =
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+            let proof = protocol.obtain(&credential, &mut rng);
+            credential
+                .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+                .unwrap();
+            protocols.push(protocol);
+            vks.push(vk);
+            credentials.push(credential);
+        }
+        let cred_refs: Vec<&Credential<Bls12_381>> = credentials.iter().collect();
+        let pp_refs: Vec<&PublicParams<Bls12_381>> = protocols.iter().map(|p| &p.pp).collect();
+        let vk_refs: Vec<&VerificationKey<Bls12_381>> = vks.iter().collect();
+
+        let presentation = LinkedCredentialPresentation::create(
+            &cred_refs,
+            &pp_refs,
+            &[vec![1], vec![], vec![3]],
+            &mut rng,
+        )
+        .unwrap();
+        assert!(presentation.verify(&pp_refs, &vk_refs, 0).unwrap());
+        assert!(presentation
+            .verify_batched(&pp_refs, &vk_refs, 0, &mut rng)
+            .unwrap());
+
+        // Keys in the wrong order break the signature equations inside the batch
+        let swapped = [vk_refs[1], vk_refs[0], vk_refs[2]];
+        assert!(!presentation
+            .verify_batched(&pp_refs, &swapped, 0, &mut rng)
+            .unwrap());
+        assert!(!presentation
+            .verify_batched(&pp_refs, &vk_refs[..2], 0, &mut rng)
+            .unwrap());
+
+        // So does a signature that isn't on the presented commitment
+        let mut tampered = presentation;
+        tampered.credential_presentations[2].randomized_signature = tampered
+            .credential_presentations[0]
+            .randomized_signature
+            .clone();
+        assert!(!tampered
+            .verify_batched(&pp_refs, &vk_refs, 0, &mut rng)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_linked_presentation_with_id_index() {
+        let mut rng = test_rng();
+        let n = 4;
+        let id_index = 2;
+        let user_id = Fr::rand(&mut rng);
+
+        let mut protocols = Vec::new();
+        let mut vks = Vec::new();
+        let mut credentials = Vec::new();
+        for _ in 0..2 {
+            let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+            let mut messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+            messages[id_index] = user_id;
+            let mut credential =
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng))
+                    .with_id_index(id_index)
+                    .unwrap();
+            protocol
+                .obtain_and_issue(&mut credential, &sk, &mut rng)
+                .unwrap();
+            assert_eq!(*credential.get_user_id(), user_id);
+            protocols.push(protocol);
+            vks.push(vk);
+            credentials.push(credential);
+        }
+        let cred_refs: Vec<&Credential<Bls12_381>> = credentials.iter().collect();
+        let pp_refs: Vec<&PublicParams<Bls12_381>> = protocols.iter().map(|p| &p.pp).collect();
+        let vk_refs: Vec<&VerificationKey<Bls12_381>> = vks.iter().collect();
+
+        // Attribute 0 is an ordinary attribute now, and can be disclosed around the ID
+        let presentation = LinkedCredentialPresentation::create(
+            &cred_refs,
+            &pp_refs,
+            &[vec![0], vec![1, 3]],
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(presentation.identity_proof.id_index, id_index);
+        assert!(presentation.verify(&pp_refs, &vk_refs, id_index).unwrap());
+        assert!(!presentation.verify(&pp_refs, &vk_refs, 0).unwrap());
+
+        // The ID at index 2 can't be disclosed
+        assert!(LinkedCredentialPresentation::create(
+            &cred_refs,
+            &pp_refs,
+            &[vec![2], vec![]],
+            &mut rng,
+        )
+        .is_err());
+
+        // Credentials sharing attribute 0 but with different IDs at index 2 don't link
+        let (protocol, sk, _) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let mut messages = credentials[0].get_messages().clone();
+        messages[id_index] = Fr::rand(&mut rng);
+        let mut other = Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng))
+            .with_id_index(id_index)
+            .unwrap();
+        protocol
+            .obtain_and_issue(&mut other, &sk, &mut rng)
+            .unwrap();
+        assert!(LinkedCredentialPresentation::create(
+            &[&credentials[0], &other],
+            &[&protocols[0].pp, &protocol.pp],
+            &[vec![], vec![]],
+            &mut rng,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_rejects_prover_chosen_id_index() {
+        let mut rng = test_rng();
+        let n = 4;
+        let country = Fr::from(49u64);
+        let (protocol_a, sk_a, vk_a) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let (protocol_b, sk_b, vk_b) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        // Two users with their own IDs in slot 0 who live in the same country, slot 3,
+        // presenting as if slot 3 held the ID
+        let mut issue = |protocol: &MimcAbc<Bls12_381>, sk| {
+            let mut messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+            messages[3] = country;
+            let mut credential =
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng))
+                    .with_id_index(3)
+                    .unwrap();
+            protocol
+                .obtain_and_issue(&mut credential, sk, &mut rng)
+                .unwrap();
+            credential
+        };
+        let alice = issue(&protocol_a, &sk_a);
+        let bob = issue(&protocol_b, &sk_b);
+        assert_ne!(alice.get_messages()[0], bob.get_messages()[0]);
+
+        let pps = [&protocol_a.pp, &protocol_b.pp];
+        let vks = [&vk_a, &vk_b];
+        let presentation = LinkedCredentialPresentation::create(
+            &[&alice, &bob],
+            &pps,
+            &[vec![], vec![]],
+            &mut rng,
+        )
+        .unwrap();
+
+        // A verifier whose schema keeps the ID in slot 0 rejects the link
+        assert!(!presentation.identity_proof.verify(&pps, 0).unwrap());
+        assert!(!presentation.verify(&pps, &vks, 0).unwrap());
+        assert!(!presentation
+            .verify_batched(&pps, &vks, 0, &mut rng)
+            .unwrap());
+        assert!(!presentation.verify_distinct_issuers(&pps, &vks, 0).unwrap());
+    }
+
+    #[test]
+    fn test_verify_distinct_issuers() {
+        let mut rng = test_rng();
+        let n = 4;
+        let user_id = Fr::rand(&mut rng);
+        let (protocol_a, sk_a, vk_a) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let (protocol_b, sk_b, vk_b) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let mut issue = |protocol: &MimcAbc<Bls12_381>, sk| {
+            let mut messages = vec![user_id];
+            messages.extend((1..n).map(|_| Fr::rand(&mut rng)));
+            let mut credential =
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+            protocol
+                .obtain_and_issue(&mut credential, sk, &mut rng)
+                .unwrap();
+            credential
+        };
+        let first_a = issue(&protocol_a, &sk_a);
+        let second_a = issue(&protocol_a, &sk_a);
+        let from_b = issue(&protocol_b, &sk_b);
+
+        // Two attestations from one issuer are valid but not independent
+        let pps = [&protocol_a.pp, &protocol_a.pp];
+        let vks = [&vk_a, &vk_a];
+        let same_issuer = LinkedCredentialPresentation::create(
+            &[&first_a, &second_a],
+            &pps,
+            &[vec![], vec![]],
+            &mut rng,
+        )
+        .unwrap();
+        assert!(same_issuer.verify(&pps, &vks, 0).unwrap());
+        assert!(!same_issuer.verify_distinct_issuers(&pps, &vks, 0).unwrap());
+
+        let pps = [&protocol_a.pp, &protocol_b.pp];
+        let vks = [&vk_a, &vk_b];
+        let distinct = LinkedCredentialPresentation::create(
+            &[&first_a, &from_b],
+            &pps,
+            &[vec![], vec![]],
+            &mut rng,
+        )
+        .unwrap();
+        assert!(distinct.verify_distinct_issuers(&pps, &vks, 0).unwrap());
+    }
 }
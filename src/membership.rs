@@ -0,0 +1,286 @@
+// mimc_abc/src/membership.rs
+
+pub mod sorted;
+
+use crate::commitment::Commitment;
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::range_proof::pedersen_bases;
+use crate::schnorr::SchnorrProtocol;
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ff::{PrimeField, UniformRand};
+use ark_std::ops::Mul;
+use ark_std::rand::Rng;
+
+/// Coefficients (lowest degree first) of P(x) = prod (x - a) over the allowed set
+pub fn vanishing_polynomial<F: PrimeField>(allowed: &[F]) -> Vec<F> {
+    let mut coefficients = vec![F::one()];
+    for root in allowed {
+        // multiply by (x - root)
+        let mut next = vec![F::zero(); coefficients.len() + 1];
+        for (k, c) in coefficients.iter().enumerate() {
+            next[k + 1] += c;
+            next[k] -= *c * root;
+        }
+        coefficients = next;
+    }
+    coefficients
+}
+
+/// Proof that a committed attribute is a root of the allow-list's vanishing polynomial
+///
+/// The prover commits to the powers m, m^2, ..., m^d of the attribute (C_k = m^k G + s_k H),
+/// proves each C_k = m * C_{k-1} + t_k H, and that sum(p_k C_k) + p_0 G has no G component,
+/// i.e. P(m) = 0. The attribute's response is shared with the proof of the credential opening.
+///
+/// Size is linear in the list: d power commitments and d + 2 Schnorr proofs, all with
+/// one challenge. That's the same order as an OR-proof but with cheap single-base checks;
+/// for large lists a Merkle-tree membership proof, logarithmic in the list, is smaller.
+#[derive(Clone, Debug)]
+pub struct PolyMembershipProof<E: Pairing> {
+    pub index: usize,
+    pub power_commitments: Vec<E::G1Affine>, // C_1..C_d
+    pub opening_announcement: E::G1Affine,
+    pub opening_responses: Vec<E::ScalarField>, // attributes, then r
+    pub link_announcement: E::G1Affine,         // for C_1 = m G + s_1 H
+    pub link_response: E::ScalarField,
+    pub power_announcements: Vec<E::G1Affine>, // for C_k = m C_{k-1} + t_k H, k >= 2
+    pub power_responses: Vec<E::ScalarField>,
+    pub zero_announcement: E::G1Affine, // for sum(p_k C_k) + p_0 G = S H
+    pub zero_response: E::ScalarField,
+}
+
+impl<E: Pairing> PolyMembershipProof<E> {
+    pub fn prove(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        index: usize,
+        messages: &[E::ScalarField],
+        allowed: &[E::ScalarField],
+        r: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        if messages.len() != pp.n || index >= messages.len() {
+            return Err(Error::Other("Attribute index out of range".to_string()));
+        }
+        let value = messages[index];
+        if !allowed.contains(&value) {
+            return Err(Error::Other(
+                "Attribute is not in the allowed set".to_string(),
+            ));
+        }
+        let (g, h) = pedersen_bases(pp);
+        let coefficients = vanishing_polynomial(allowed);
+        let degree = allowed.len();
+
+        // Opening of the credential commitment
+        let bases = pp.get_g1_bases();
+        let opening = SchnorrProtocol::commit(bases, rng);
+        let value_nonce = opening.random_blindings[index];
+
+        // Commitments to the powers of the attribute
+        let blindings: Vec<E::ScalarField> =
+            (0..degree).map(|_| E::ScalarField::rand(rng)).collect();
+        let mut power = value;
+        let mut power_commitments = Vec::with_capacity(degree);
+        for s_k in &blindings {
+            power_commitments.push((g.mul(power) + h.mul(*s_k)).into_affine());
+            power *= value;
+        }
+
+        let link_nonce = E::ScalarField::rand(rng);
+        let link_announcement = (g.mul(value_nonce) + h.mul(link_nonce)).into_affine();
+
+        // t_k = s_k - m s_{k-1} so that C_k - m C_{k-1} = t_k H
+        let power_nonces: Vec<E::ScalarField> =
+            (1..degree).map(|_| E::ScalarField::rand(rng)).collect();
+        let power_announcements: Vec<E::G1Affine> = (1..degree)
+            .map(|k| {
+                (power_commitments[k - 1].mul(value_nonce) + h.mul(power_nonces[k - 1]))
+                    .into_affine()
+            })
+            .collect();
+
+        let zero_nonce = E::ScalarField::rand(rng);
+        let zero_announcement = h.mul(zero_nonce).into_affine();
+
+        let challenge = Self::challenge(
+            &coefficients,
+            commitment,
+            index,
+            &power_commitments,
+            &opening.commited_blindings,
+            &link_announcement,
+            &power_announcements,
+            &zero_announcement,
+        );
+
+        let mut exponents = messages.to_vec();
+        exponents.push(*r);
+        let opening_responses = SchnorrProtocol::prove(&opening, &exponents, &challenge).0;
+        let link_response = link_nonce + challenge * blindings[0];
+        let power_responses = (1..degree)
+            .map(|k| power_nonces[k - 1] + challenge * (blindings[k] - value * blindings[k - 1]))
+            .collect();
+        let zero_secret: E::ScalarField = coefficients[1..]
+            .iter()
+            .zip(blindings.iter())
+            .map(|(p, s)| *p * s)
+            .sum();
+        let zero_response = zero_nonce + challenge * zero_secret;
+
+        Ok(Self {
+            index,
+            power_commitments,
+            opening_announcement: opening.commited_blindings,
+            opening_responses,
+            link_announcement,
+            link_response,
+            power_announcements,
+            power_responses,
+            zero_announcement,
+            zero_response,
+        })
+    }
+
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        allowed: &[E::ScalarField],
+    ) -> bool {
+        let degree = allowed.len();
+        if degree == 0
+            || self.index >= pp.n
+            || self.opening_responses.len() != pp.n + 1
+            || self.power_commitments.len() != degree
+            || self.power_announcements.len() != degree - 1
+            || self.power_responses.len() != degree - 1
+        {
+            return false;
+        }
+        let (g, h) = pedersen_bases(pp);
+        let coefficients = vanishing_polynomial(allowed);
+
+        let challenge = Self::challenge(
+            &coefficients,
+            commitment,
+            self.index,
+            &self.power_commitments,
+            &self.opening_announcement,
+            &self.link_announcement,
+            &self.power_announcements,
+            &self.zero_announcement,
+        );
+
+        if !SchnorrProtocol::verify_schnorr(
+            pp.get_g1_bases(),
+            &commitment.cm,
+            &self.opening_announcement,
+            &self.opening_responses,
+            &challenge,
+        ) {
+            return false;
+        }
+
+        let value_response = self.opening_responses[self.index];
+        if g.mul(value_response) + h.mul(self.link_response)
+            != self.link_announcement + self.power_commitments[0].mul(challenge)
+        {
+            return false;
+        }
+
+        for k in 1..degree {
+            let lhs = self.power_commitments[k - 1].mul(value_response)
+                + h.mul(self.power_responses[k - 1]);
+            let rhs = self.power_announcements[k - 1] + self.power_commitments[k].mul(challenge);
+            if lhs != rhs {
+                return false;
+            }
+        }
+
+        // sum_{k>=1} p_k C_k + p_0 G must be a multiple of H alone
+        let zero = E::G1::msm_unchecked(&self.power_commitments, &coefficients[1..])
+            + g.mul(coefficients[0]);
+        h.mul(self.zero_response) == self.zero_announcement + zero.mul(challenge)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn challenge(
+        coefficients: &[E::ScalarField],
+        commitment: &Commitment<E>,
+        index: usize,
+        power_commitments: &[E::G1Affine],
+        opening_announcement: &E::G1Affine,
+        link_announcement: &E::G1Affine,
+        power_announcements: &[E::G1Affine],
+        zero_announcement: &E::G1Affine,
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-poly-membership");
+        transcript.append_serializable(b"polynomial", coefficients);
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_message(b"index", &(index as u64).to_le_bytes());
+        transcript.append_serializable(b"power_commitments", power_commitments);
+        transcript.append_serializable(b"opening_announcement", opening_announcement);
+        transcript.append_serializable(b"link_announcement", link_announcement);
+        transcript.append_serializable(b"power_announcements", power_announcements);
+        transcript.append_serializable(b"zero_announcement", zero_announcement);
+        transcript.challenge_scalar(b"c")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commitment::CommitmentKey;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::Zero;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_poly_membership() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let allowed: Vec<Fr> = [1u64, 3, 5, 7, 9].iter().map(|v| Fr::from(*v)).collect();
+
+        // Member: attribute 2 is 5
+        let mut messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        messages[2] = Fr::from(5u64);
+        let r = Fr::rand(&mut rng);
+        let commitment = ck.commit(&pp, &messages, &r);
+        let proof =
+            PolyMembershipProof::prove(&pp, &commitment, 2, &messages, &allowed, &r, &mut rng)
+                .expect("Member value should prove");
+        assert!(proof.verify(&pp, &commitment, &allowed));
+
+        // The proof is specific to the list it was made for
+        let other_list: Vec<Fr> = [1u64, 3, 7].iter().map(|v| Fr::from(*v)).collect();
+        assert!(!proof.verify(&pp, &commitment, &other_list));
+        let mut tampered = proof.clone();
+        tampered.zero_response += Fr::from(1u64);
+        assert!(!tampered.verify(&pp, &commitment, &allowed));
+
+        // Non-member: the prover can't build a proof, and a proof for another
+        // commitment doesn't transfer
+        messages[2] = Fr::from(4u64);
+        let commitment = ck.commit(&pp, &messages, &r);
+        assert!(
+            PolyMembershipProof::prove(&pp, &commitment, 2, &messages, &allowed, &r, &mut rng)
+                .is_err()
+        );
+        assert!(!proof.verify(&pp, &commitment, &allowed));
+
+        // Sanity check on the polynomial itself
+        let p = vanishing_polynomial(&allowed);
+        let eval = |x: Fr| p.iter().rev().fold(Fr::zero(), |acc, c| acc * x + c);
+        assert!(eval(Fr::from(7u64)).is_zero());
+        assert!(!eval(Fr::from(4u64)).is_zero());
+    }
+}
@@ -0,0 +1,258 @@
+// mimc_abc/src/membership/sorted.rs
+use crate::commitment::Commitment;
+use crate::error::Error;
+use crate::multi_credential::{merkle_node, MerkleRoot};
+use crate::public_params::PublicParams;
+use crate::schnorr::SchnorrProtocol;
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::PrimeField;
+use ark_std::ops::Mul;
+use ark_std::rand::Rng;
+use sha2::{Digest, Sha256};
+
+/// Proof that a committed attribute is an element of an allow-list known to the verifier
+/// only by its Merkle root
+///
+/// The list is sorted and deduplicated before hashing, so its root doesn't depend on the
+/// order it was given in. The matched element is revealed along with its Merkle path and
+/// the credential opening is proven with that attribute fixed to it. The verifier learns the
+/// attribute, nothing about the rest of the list; hiding the attribute as well would need
+/// the path proven in zero knowledge, see `PolyMembershipProof` for that.
+#[derive(Clone, Debug)]
+pub struct SortedMembershipProof<E: Pairing> {
+    pub index: usize,
+    pub element: E::ScalarField,
+    pub siblings: Vec<([u8; 32], bool)>, // sibling digest and whether it sits on the left
+    pub opening_announcement: E::G1Affine,
+    pub opening_responses: Vec<E::ScalarField>, // hidden attributes, then r
+}
+
+/// Root of the allow-list's Merkle tree
+pub fn root<F: PrimeField>(list: &[F]) -> MerkleRoot {
+    let mut level: Vec<[u8; 32]> = sorted(list).iter().map(leaf).collect();
+    if level.is_empty() {
+        return leaf(&F::zero());
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => merkle_node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+/// Prove attribute `index` of `commitment` is in `list`
+pub fn prove<E: Pairing>(
+    pp: &PublicParams<E>,
+    commitment: &Commitment<E>,
+    index: usize,
+    messages: &[E::ScalarField],
+    r: &E::ScalarField,
+    list: &[E::ScalarField],
+    rng: &mut impl Rng,
+) -> Result<SortedMembershipProof<E>, Error> {
+    if messages.len() != pp.n || index >= pp.n {
+        return Err(Error::Other("Attribute index out of range".to_string()));
+    }
+    let element = messages[index];
+    let list = sorted(list);
+    let position = list
+        .binary_search_by_key(&element.into_bigint(), |e| e.into_bigint())
+        .map_err(|_| Error::Other("Attribute is not in the allowed list".to_string()))?;
+
+    // Path from the element's leaf, an unpaired node at the end of a level is carried up
+    let mut siblings = Vec::new();
+    let mut level: Vec<[u8; 32]> = list.iter().map(leaf).collect();
+    let mut position = position;
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        if sibling < level.len() {
+            siblings.push((level[sibling], sibling < position));
+        }
+        position /= 2;
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => merkle_node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    // Opening of cm - element * g_index over the other bases
+    let mut exponents: Vec<E::ScalarField> = messages
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != index)
+        .map(|(_, m)| *m)
+        .collect();
+    exponents.push(*r);
+    let opening = SchnorrProtocol::commit(&hidden_bases(pp, index), rng);
+    let challenge = challenge(
+        commitment,
+        index,
+        &element,
+        &siblings,
+        &opening.commited_blindings,
+    );
+
+    Ok(SortedMembershipProof {
+        index,
+        element,
+        siblings,
+        opening_announcement: opening.commited_blindings,
+        opening_responses: SchnorrProtocol::prove(&opening, &exponents, &challenge).0,
+    })
+}
+
+/// Check the proof's element is attribute `index` of `commitment` and a leaf under `root`
+pub fn verify<E: Pairing>(
+    pp: &PublicParams<E>,
+    commitment: &Commitment<E>,
+    proof: &SortedMembershipProof<E>,
+    root: &MerkleRoot,
+) -> bool {
+    if proof.index >= pp.n || proof.opening_responses.len() != pp.n {
+        return false;
+    }
+    let digest = proof
+        .siblings
+        .iter()
+        .fold(leaf(&proof.element), |acc, (sibling, on_left)| {
+            if *on_left {
+                merkle_node(sibling, &acc)
+            } else {
+                merkle_node(&acc, sibling)
+            }
+        });
+    if &digest != root {
+        return false;
+    }
+
+    let statement =
+        (commitment.cm.into_group() - pp.ck[proof.index].mul(proof.element)).into_affine();
+    let challenge = challenge(
+        commitment,
+        proof.index,
+        &proof.element,
+        &proof.siblings,
+        &proof.opening_announcement,
+    );
+    SchnorrProtocol::verify_schnorr(
+        &hidden_bases(pp, proof.index),
+        &statement,
+        &proof.opening_announcement,
+        &proof.opening_responses,
+        &challenge,
+    )
+}
+
+// The list in canonical order, without repeats
+fn sorted<F: PrimeField>(list: &[F]) -> Vec<F> {
+    let mut list = list.to_vec();
+    list.sort_unstable_by_key(|e| e.into_bigint());
+    list.dedup();
+    list
+}
+
+// Leaves are prefixed 0, inner nodes 1 (see `merkle_node`)
+fn leaf<F: PrimeField>(element: &F) -> [u8; 32] {
+    let mut bytes = Vec::new();
+    element
+        .serialize_compressed(&mut bytes)
+        .expect("serializing into a Vec cannot fail");
+    Sha256::new()
+        .chain_update([0u8])
+        .chain_update(&bytes)
+        .finalize()
+        .into()
+}
+
+// Bases of every attribute but `index`, then g for r
+fn hidden_bases<E: Pairing>(pp: &PublicParams<E>, index: usize) -> Vec<E::G1Affine> {
+    let mut bases: Vec<E::G1Affine> = pp
+        .ck
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != index)
+        .map(|(_, base)| *base)
+        .collect();
+    bases.push(pp.g);
+    bases
+}
+
+fn challenge<E: Pairing>(
+    commitment: &Commitment<E>,
+    index: usize,
+    element: &E::ScalarField,
+    siblings: &[([u8; 32], bool)],
+    opening_announcement: &E::G1Affine,
+) -> E::ScalarField {
+    let mut transcript = Transcript::new(b"mimc-abc-sorted-membership");
+    transcript.append_serializable(b"cm", &commitment.cm);
+    transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+    transcript.append_message(b"index", &(index as u64).to_le_bytes());
+    transcript.append_serializable(b"element", element);
+    for (sibling, on_left) in siblings {
+        transcript.append_message(b"sibling", sibling);
+        transcript.append_message(b"on_left", &[*on_left as u8]);
+    }
+    transcript.append_serializable(b"opening_announcement", opening_announcement);
+    transcript.challenge_scalar(b"c")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commitment::CommitmentKey;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_sorted_membership() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        // Country codes, in no particular order
+        let list: Vec<Fr> = [840u64, 124, 250, 276, 392]
+            .iter()
+            .map(|&c| Fr::from(c))
+            .collect();
+        let allowed = root(&list);
+        let mut reversed = list.clone();
+        reversed.reverse();
+        assert_eq!(root(&reversed), allowed);
+
+        let mut messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        messages[2] = Fr::from(276u64);
+        let r = Fr::rand(&mut rng);
+        let commitment = ck.commit(&pp, &messages, &r);
+
+        let proof = prove(&pp, &commitment, 2, &messages, &r, &list, &mut rng).unwrap();
+        assert!(verify(&pp, &commitment, &proof, &allowed));
+
+        // Another list, or another element, doesn't verify
+        assert!(!verify(&pp, &commitment, &proof, &root(&list[..4])));
+        let mut swapped = proof.clone();
+        swapped.element = Fr::from(840u64);
+        assert!(!verify(&pp, &commitment, &swapped, &allowed));
+
+        // A non-member has no path to prove
+        messages[2] = Fr::from(643u64);
+        let commitment = ck.commit(&pp, &messages, &r);
+        assert!(prove(&pp, &commitment, 2, &messages, &r, &list, &mut rng).is_err());
+    }
+}
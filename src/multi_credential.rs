@@ -1,15 +1,24 @@
+use crate::batch_verifier::BatchVerifier;
 use crate::commitment::Commitment;
 use crate::credential::{Credential, ShowCredential};
 use crate::error::Error;
-use crate::pairing::{create_check, PairingCheck};
+use crate::pairing::{create_check, nonzero_scalar, PairingCheck};
 use crate::proof::CommitmentProof;
 use crate::public_params::PublicParams;
 use crate::signature::{Signature, VerificationKey};
+use crate::transcript::Transcript;
 use ark_ec::pairing::Pairing;
-use ark_ec::{AffineRepr, CurveGroup};
-use ark_ff::UniformRand;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{BigInteger, One, PrimeField, UniformRand, Zero};
+use ark_serialize::CanonicalSerialize;
 use ark_std::ops::{Add, Neg};
 use ark_std::rand::Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::ops::Range;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 /// Aggregate presentation of multiple credentials from the same issuer
 pub struct AggregatePresentation<E: Pairing> {
@@ -18,34 +27,46 @@ pub struct AggregatePresentation<E: Pairing> {
     pub proofs: Vec<CommitmentProof<E>>,
 }
 
+/// Consumes the presentations, moving their parts into the aggregate without cloning
+impl<E: Pairing> FromIterator<ShowCredential<E>> for AggregatePresentation<E> {
+    fn from_iter<I: IntoIterator<Item = ShowCredential<E>>>(presentations: I) -> Self {
+        let presentations = presentations.into_iter();
+        let capacity = presentations.size_hint().0;
+        let mut aggregate = Self {
+            randomized_signatures: Vec::with_capacity(capacity),
+            randomized_commitments: Vec::with_capacity(capacity),
+            proofs: Vec::with_capacity(capacity),
+        };
+        for presentation in presentations {
+            aggregate
+                .randomized_signatures
+                .push(presentation.randomized_signature);
+            aggregate
+                .randomized_commitments
+                .push(presentation.randomized_commitment);
+            aggregate.proofs.push(presentation.proof);
+        }
+        aggregate
+    }
+}
+
 impl<E: Pairing> AggregatePresentation<E> {
     /// Create a new aggregate presentation from individual ShowCredential presentations
     pub fn new(presentations: Vec<ShowCredential<E>>) -> Self {
-        let randomized_signatures = presentations
-            .iter()
-            .map(|p| p.randomized_signature.clone())
-            .collect();
-        let randomized_commitments = presentations
-            .iter()
-            .map(|p| p.randomized_commitment.clone())
-            .collect();
-        let proofs = presentations.iter().map(|p| p.proof.clone()).collect();
-
-        Self {
-            randomized_signatures,
-            randomized_commitments,
-            proofs,
-        }
+        presentations.into_iter().collect()
     }
 
     /// Verify all credentials in the presentation
     /// Standard approach - verify each credential individually
     pub fn verify_all(&self, pp: &PublicParams<E>, vk: &VerificationKey<E>) -> bool {
+        if self.randomized_signatures.len() != self.proofs.len()
+            || self.randomized_commitments.len() != self.proofs.len()
+        {
+            return false;
+        }
         // First verify all individual proofs
-        for proof in &self.proofs {
-            if !proof.verify() {
-                return false;
-            }
+        if !(0..self.proofs.len()).all(|i| self.proof_binds(pp, i)) {
+            return false;
         }
 
         // Then verify all signatures
@@ -58,29 +79,288 @@ impl<E: Pairing> AggregatePresentation<E> {
         true
     }
 
-    pub fn batch_verify(&self, pp: &PublicParams<E>, vk: &VerificationKey<E>) -> bool {
-        // First verify all individual proofs
-        for proof in &self.proofs {
-            if !proof.verify() {
-                return false;
+    /// Same checks as `verify_all`, but every credential is checked and the indices of
+    /// those whose proof or signature fails are returned, so a wallet can drop just them.
+    /// A credential missing its signature, commitment or proof counts as failing.
+    pub fn verify_all_detailed(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+    ) -> Result<(), Vec<usize>> {
+        let count = self
+            .proofs
+            .len()
+            .max(self.randomized_signatures.len())
+            .max(self.randomized_commitments.len());
+        let failed: Vec<usize> = (0..count)
+            .filter(|&i| {
+                match (
+                    self.proofs.get(i),
+                    self.randomized_signatures.get(i),
+                    self.randomized_commitments.get(i),
+                ) {
+                    (Some(_), Some(signature), Some(commitment)) => {
+                        !self.proof_binds(pp, i) || !vk.verify(signature, commitment, pp)
+                    }
+                    _ => true,
+                }
+            })
+            .collect();
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(failed)
+        }
+    }
+
+    /// Same checks as `verify_all`, one credential per rayon task with the `parallel`
+    /// feature and sequentially without it.
+    pub fn par_verify_all(&self, pp: &PublicParams<E>, vk: &VerificationKey<E>) -> bool {
+        if self.randomized_signatures.len() != self.proofs.len()
+            || self.randomized_commitments.len() != self.proofs.len()
+        {
+            return false;
+        }
+        let check = |i: usize| {
+            self.proof_binds(pp, i)
+                && vk.verify_with_pairing_checker(
+                    &self.randomized_signatures[i],
+                    &self.randomized_commitments[i],
+                    pp,
+                )
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            (0..self.proofs.len()).into_par_iter().all(check)
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            (0..self.proofs.len()).all(check)
+        }
+    }
+
+    /// Verify every proof, then all signature and commitment equations in one pairing check
+    /// Each equation is scaled by its own non-zero weight drawn from `rng`, so errors in
+    /// different credentials can't cancel out. See `batch_verify_randomized` for weights
+    /// derived from the presentations instead.
+    pub fn batch_verify<R: Rng + Send>(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        rng: &mut R,
+    ) -> bool {
+        let rng = Mutex::new(rng);
+        self.batch_verify_range(pp, vk, 0..self.proofs.len(), &rng)
+    }
+
+    /// `batch_verify` with the proofs checked and the weighted pairing terms built one
+    /// credential per rayon task with the `parallel` feature, sequentially without it.
+    /// The weights are drawn from `rng` up front and all terms share one final exponentiation.
+    pub fn batch_verify_par(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        rng: &mut impl Rng,
+    ) -> bool {
+        if self.randomized_signatures.len() != self.proofs.len()
+            || self.randomized_commitments.len() != self.proofs.len()
+        {
+            return false;
+        }
+        let weights: Vec<(E::ScalarField, E::ScalarField)> = (0..self.proofs.len())
+            .map(|_| {
+                (
+                    nonzero_scalar(|| E::ScalarField::rand(rng)),
+                    nonzero_scalar(|| E::ScalarField::rand(rng)),
+                )
+            })
+            .collect();
+        let neg_g = pp.g.into_group().neg().into_affine();
+
+        // The credential's weighted signature and commitment equations, None if its proof fails
+        let terms = |i: usize| -> Option<PairingCheck<E>> {
+            let (signature, proof) = (&self.randomized_signatures[i], &self.proofs[i]);
+            if !self.proof_binds(pp, i) || signature.sigma1.is_zero() {
+                return None;
             }
+            let (sig_weight, cm_weight) = weights[i];
+            let vk_plus_cm_tilde = vk.vk_tilde.add(proof.commitment.cm_tilde).into_affine();
+            let mut check = PairingCheck::<E>::scaled(
+                sig_weight,
+                &[
+                    (&signature.sigma2, &pp.g_tilde),
+                    (
+                        &signature.sigma1.into_group().neg().into_affine(),
+                        &vk_plus_cm_tilde,
+                    ),
+                ],
+                &E::TargetField::one(),
+            );
+            check.merge(&PairingCheck::<E>::scaled(
+                cm_weight,
+                &[
+                    (&proof.commitment.cm, &pp.g_tilde),
+                    (&neg_g, &proof.commitment.cm_tilde),
+                ],
+                &E::TargetField::one(),
+            ));
+            Some(check)
+        };
+
+        #[cfg(feature = "parallel")]
+        let checks: Option<Vec<PairingCheck<E>>> =
+            (0..self.proofs.len()).into_par_iter().map(terms).collect();
+        #[cfg(not(feature = "parallel"))]
+        let checks: Option<Vec<PairingCheck<E>>> = (0..self.proofs.len()).map(terms).collect();
+
+        match checks {
+            Some(checks) => {
+                let mut final_check = PairingCheck::<E>::new();
+                for check in &checks {
+                    final_check.merge(check);
+                }
+                final_check.verify()
+            }
+            None => false,
+        }
+    }
+
+    /// `batch_verify` over consecutive chunks of `chunk_size` credentials, each with its own
+    /// randomized pairing check, stopping at the first chunk that fails. Bounds the size of
+    /// the multi-Miller loop for very large batches. A `chunk_size` of zero is rejected.
+    pub fn batch_verify_chunked<R: Rng + Send>(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        chunk_size: usize,
+        rng: &mut R,
+    ) -> bool {
+        if chunk_size == 0 {
+            return false;
+        }
+        let rng = Mutex::new(rng);
+        let total = self.proofs.len();
+        (0..total).step_by(chunk_size).all(|start| {
+            self.batch_verify_range(pp, vk, start..total.min(start + chunk_size), &rng)
+        })
+    }
+
+    fn batch_verify_range<R: Rng + Send>(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        range: Range<usize>,
+        rng: &Mutex<R>,
+    ) -> bool {
+        if self.randomized_signatures.len() != self.proofs.len()
+            || self.randomized_commitments.len() != self.proofs.len()
+        {
+            return false;
+        }
+        // First verify all individual proofs
+        if !range.clone().all(|i| self.proof_binds(pp, i)) {
+            return false;
+        }
+        let proofs = &self.proofs[range.clone()];
+        let signatures = &self.randomized_signatures[range];
+        if signatures
+            .iter()
+            .any(|signature| signature.sigma1.is_zero())
+        {
+            return false;
         }
 
         // Set up a merged pairing check for batch verification
         let mut final_check = PairingCheck::<E>::new();
+        let neg_g = pp.g.into_group().neg().into_affine();
+
+        // For each credential, add its signature and commitment equations to the batch
+        for (signature, proof) in signatures.iter().zip(proofs) {
+            // Use the commitment from the proof since it has been verified
+            let vk_plus_cm_tilde = vk.vk_tilde.add(proof.commitment.cm_tilde).into_affine();
+
+            // e(sigma2, g_tilde) == e(sigma1, vk_tilde + cm_tilde)
+            let sig_check = PairingCheck::<E>::rand(
+                rng,
+                &[
+                    (&signature.sigma2, &pp.g_tilde),
+                    (
+                        &signature.sigma1.into_group().neg().into_affine(),
+                        &vk_plus_cm_tilde,
+                    ),
+                ],
+                &E::TargetField::one(),
+            );
+            // e(cm, g_tilde) == e(g, cm_tilde)
+            let cm_check = PairingCheck::<E>::rand(
+                rng,
+                &[
+                    (&proof.commitment.cm, &pp.g_tilde),
+                    (&neg_g, &proof.commitment.cm_tilde),
+                ],
+                &E::TargetField::one(),
+            );
 
-        // For each signature, add its verification equation to the batch
-        for (_, (signature, proof)) in self
+            final_check.merge(&sig_check);
+            final_check.merge(&cm_check);
+        }
+
+        final_check.verify()
+    }
+
+    /// Batch verify with every equation scaled by a weight derived from the presentations
+    /// Each weight hashes the credential's own data together with the sorted digests of the
+    /// whole batch, so verifiers processing the presentations in any order agree on the result
+    pub fn batch_verify_randomized(&self, pp: &PublicParams<E>, vk: &VerificationKey<E>) -> bool {
+        self.randomized_check(pp, vk)
+            .is_some_and(|check| check.verify())
+    }
+
+    /// The target group value of the `batch_verify_randomized` pairing check, one for a
+    /// valid batch; for telling how far off a failing batch is
+    /// Fails with `ProofVerificationFailed` if a proof fails before any pairing is evaluated
+    pub fn batch_evaluate(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+    ) -> Result<E::TargetField, Error> {
+        self.randomized_check(pp, vk)
+            .map(|check| check.evaluate())
+            .ok_or(Error::ProofVerificationFailed)
+    }
+
+    // The merged weighted pairing check, or None if the proofs don't verify
+    fn randomized_check(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+    ) -> Option<PairingCheck<E>> {
+        if self.randomized_signatures.len() != self.proofs.len()
+            || self.randomized_commitments.len() != self.proofs.len()
+        {
+            return None;
+        }
+        if !(0..self.proofs.len()).all(|i| self.proof_binds(pp, i))
+            || self
+                .randomized_signatures
+                .iter()
+                .any(|signature| signature.sigma1.is_zero())
+        {
+            return None;
+        }
+
+        let mut final_check = PairingCheck::<E>::new();
+        let weights = self.batch_weights(vk);
+        for ((signature, proof), (sig_weight, cm_weight)) in self
             .randomized_signatures
             .iter()
             .zip(self.proofs.iter())
-            .enumerate()
+            .zip(weights)
         {
-            // Use the commitment from the proof since it has been verified
             let vk_plus_cm_tilde = vk.vk_tilde.add(proof.commitment.cm_tilde).into_affine();
-
-            // Signature verification equation
-            let sig_check = create_check::<E>(
+            let sig_check = PairingCheck::<E>::scaled(
+                sig_weight,
                 &[
                     (&signature.sigma2, &pp.g_tilde),
                     (
@@ -88,11 +368,10 @@ impl<E: Pairing> AggregatePresentation<E> {
                         &vk_plus_cm_tilde,
                     ),
                 ],
-                None,
+                &E::TargetField::one(),
             );
-
-            // Commitment consistency check
-            let cm_check = create_check::<E>(
+            let cm_check = PairingCheck::<E>::scaled(
+                cm_weight,
                 &[
                     (&proof.commitment.cm, &pp.g_tilde),
                     (
@@ -100,79 +379,235 @@ impl<E: Pairing> AggregatePresentation<E> {
                         &proof.commitment.cm_tilde,
                     ),
                 ],
-                None,
+                &E::TargetField::one(),
             );
-
             final_check.merge(&sig_check);
             final_check.merge(&cm_check);
         }
 
-        final_check.verify()
+        Some(final_check)
     }
 
-    // /// Batch verify all credentials using pairing optimization
-    // /// This is more efficient for multiple credentials from the same issuer
-    // pub fn batch_verify2(&self, pp: &PublicParams<E>, vk: &VerificationKey<E>) -> bool {
-    //     // First verify all individual proofs
-    //     for proof in &self.proofs {
-    //         if !proof.verify() {
-    //             return false;
-    //         }
-    //     }
-
-    //     // Set up a pairing checker for batch verification
-    //     let mut rng = ark_std::test_rng();
-    //     let mr = std::sync::Mutex::new(rng);
-    //     let mut final_check = PairingCheck::<E>::new();
-
-    //     // For each signature, create a random weight for the batch verification
-    //     for (i, signature) in self.randomized_signatures.iter().enumerate() {
-    //         // Generate a random weight for this signature
-    //         let mut rng = ark_std::rand::thread_rng();
-    //         let weight = E::ScalarField::rand(&mut rng);
-
-    //         // Calculate vk + commitment in G2
-    //         let vk_plus_cm_tilde = vk
-    //             .vk_tilde
-    //             .add(self.randomized_commitments[i].cm_tilde)
-    //             .into_affine();
-
-    //         // Add the pairing check for this signature with the random weight
-    //         let sig_check = PairingCheck::<E>::rand(
-    //             &mr,
-    //             &[
-    //                 (&signature.sigma2.mul(weight).into_affine(), &pp.g_tilde),
-    //                 (
-    //                     &signature.sigma1.mul(weight).neg().into_affine(),
-    //                     &vk_plus_cm_tilde,
-    //                 ),
-    //             ],
-    //             &E::TargetField::one(),
-    //         );
-
-    //         // Add commitment consistency check
-    //         let cm_check = PairingCheck::<E>::rand(
-    //             &mr,
-    //             &[
-    //                 (
-    //                     &self.randomized_commitments[i].cm.mul(weight).into_affine(),
-    //                     &pp.g_tilde,
-    //                 ),
-    //                 (
-    //                     &pp.g.mul(weight).neg().into_affine(),
-    //                     &self.randomized_commitments[i].cm_tilde,
-    //                 ),
-    //             ],
-    //             &E::TargetField::one(),
-    //         );
-
-    //         final_check.merge(&sig_check);
-    //         final_check.merge(&cm_check);
-    //     }
-
-    //     // Verify all pairing equations at once
-    //     final_check.verify()
-    // }
+    // Proof i opens randomized commitment i, checked over the verifier's bases rather than
+    // the ones the prover sent
+    fn proof_binds(&self, pp: &PublicParams<E>, i: usize) -> bool {
+        let proof = &self.proofs[i];
+        proof.commitment.cm == self.randomized_commitments[i].cm && proof.verify_against(pp)
+    }
+
+    // (signature weight, commitment weight) per presentation, independent of presentation order
+    fn batch_weights(&self, vk: &VerificationKey<E>) -> Vec<(E::ScalarField, E::ScalarField)> {
+        let digests: Vec<Vec<u8>> = self
+            .randomized_signatures
+            .iter()
+            .zip(self.proofs.iter())
+            .map(|(signature, proof)| {
+                let mut transcript = Transcript::new(b"mimc-abc-batch-item");
+                transcript.append_serializable(b"sigma1", &signature.sigma1);
+                transcript.append_serializable(b"sigma2", &signature.sigma2);
+                transcript.append_serializable(b"cm", &proof.commitment.cm);
+                transcript.append_serializable(b"cm_tilde", &proof.commitment.cm_tilde);
+                let digest: E::ScalarField = transcript.challenge_scalar(b"digest");
+                digest.into_bigint().to_bytes_le()
+            })
+            .collect();
+
+        // The batch as a set: sorting removes any dependence on order
+        let mut sorted = digests.clone();
+        sorted.sort();
+        let mut seed = Transcript::new(b"mimc-abc-batch-weights");
+        seed.append_serializable(b"vk_tilde", &vk.vk_tilde);
+        for digest in &sorted {
+            seed.append_message(b"item", digest);
+        }
+
+        digests
+            .iter()
+            .map(|digest| {
+                let mut transcript = seed.clone();
+                transcript.append_message(b"this_item", digest);
+                (
+                    nonzero_scalar(|| transcript.challenge_scalar(b"sig_weight")),
+                    nonzero_scalar(|| transcript.challenge_scalar(b"cm_weight")),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Check e(cm_i, g_tilde) == e(g, cm_tilde_i) for every commitment with one randomized check
+///
+/// The equations are combined with hash-derived weights rho_i into
+/// e(sum rho_i cm_i, g_tilde) == e(g, sum rho_i cm_tilde_i), two pairings for any batch size.
+pub fn batch_verify_commitment_consistency<E: Pairing>(
+    commitments: &[&Commitment<E>],
+    pp: &PublicParams<E>,
+) -> bool {
+    if commitments.is_empty() {
+        return true;
+    }
+
+    let mut transcript = Transcript::new(b"mimc-abc-commitment-consistency");
+    for commitment in commitments {
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+    }
+    let weights: Vec<E::ScalarField> = commitments
+        .iter()
+        .map(|_| nonzero_scalar(|| transcript.challenge_scalar(b"weight")))
+        .collect();
+
+    let cms: Vec<E::G1Affine> = commitments.iter().map(|c| c.cm).collect();
+    let cm_tildes: Vec<E::G2Affine> = commitments.iter().map(|c| c.cm_tilde).collect();
+    let folded_cm = E::G1::msm_unchecked(&cms, &weights);
+    let folded_cm_tilde = E::G2::msm_unchecked(&cm_tildes, &weights);
+
+    E::multi_pairing(
+        [folded_cm, pp.g.into_group().neg()],
+        [pp.g_tilde.into_group(), folded_cm_tilde],
+    )
+    .is_zero()
+}
+
+/// Root of a Merkle tree over presentation commitment digests
+pub type MerkleRoot = [u8; 32];
+
+/// Path from one presentation's leaf to the Merkle root
+#[derive(Clone, Debug)]
+pub struct InclusionProof {
+    pub index: usize,
+    pub siblings: Vec<([u8; 32], bool)>, // sibling digest and whether it sits on the left
+}
+
+impl InclusionProof {
+    /// Check `commitment` is the leaf this proof opens under `root`
+    pub fn verify<E: Pairing>(&self, commitment: &Commitment<E>, root: &MerkleRoot) -> bool {
+        let digest =
+            self.siblings
+                .iter()
+                .fold(merkle_leaf(commitment), |acc, (sibling, on_left)| {
+                    if *on_left {
+                        merkle_node(sibling, &acc)
+                    } else {
+                        merkle_node(&acc, sibling)
+                    }
+                });
+        &digest == root
+    }
+}
+
+// Leaves and inner nodes are hashed with different prefixes so one can't pass for the other
+fn merkle_leaf<E: Pairing>(commitment: &Commitment<E>) -> [u8; 32] {
+    let mut bytes = Vec::new();
+    (commitment.cm, commitment.cm_tilde)
+        .serialize_compressed(&mut bytes)
+        .expect("serializing into a Vec cannot fail");
+    Sha256::new()
+        .chain_update([0u8])
+        .chain_update(&bytes)
+        .finalize()
+        .into()
+}
+
+pub(crate) fn merkle_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([1u8])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Batch verify presentations and, if all pass, log them in a Merkle tree
+/// Returns the root and one inclusion proof per presentation, in input order.
+/// An unpaired node at the end of a level is carried up unchanged.
+pub fn verify_and_accumulate<E: Pairing>(
+    presentations: &[ShowCredential<E>],
+    pp: &PublicParams<E>,
+    vk: &VerificationKey<E>,
+) -> Result<(MerkleRoot, Vec<InclusionProof>), Error> {
+    if presentations.is_empty() {
+        return Err(Error::Other("No presentations to accumulate".to_string()));
+    }
+    // The leaves are the randomized commitments, so they must be the ones the proofs open
+    if presentations.iter().any(|p| {
+        p.proof.commitment.cm != p.randomized_commitment.cm
+            || p.proof.commitment.cm_tilde != p.randomized_commitment.cm_tilde
+    }) {
+        return Err(Error::InvalidProof);
+    }
+    if !AggregatePresentation::new(presentations.to_vec()).batch_verify_randomized(pp, vk) {
+        return Err(Error::SignatureVerificationFailed);
+    }
+
+    let mut level: Vec<[u8; 32]> = presentations
+        .iter()
+        .map(|p| merkle_leaf(&p.randomized_commitment))
+        .collect();
+    let mut proofs: Vec<InclusionProof> = (0..presentations.len())
+        .map(|index| InclusionProof {
+            index,
+            siblings: Vec::new(),
+        })
+        .collect();
+    // Position of each leaf's ancestor in the current level
+    let mut positions: Vec<usize> = (0..presentations.len()).collect();
+
+    while level.len() > 1 {
+        for (proof, position) in proofs.iter_mut().zip(positions.iter_mut()) {
+            let sibling = *position ^ 1;
+            if sibling < level.len() {
+                proof.siblings.push((level[sibling], sibling < *position));
+            }
+            *position /= 2;
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => merkle_node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    Ok((level[0], proofs))
+}
+
+/// Outcome of `verify_batch_stats`, for monitoring
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchStats {
+    pub total: usize,
+    pub passed: usize,
+    pub failed_indices: Vec<usize>,
+    pub elapsed: Duration,
+}
+
+/// Verify presentations from one issuer and count the outcomes
+/// The whole batch is tried in one pairing check first; only if it fails is each
+/// presentation verified on its own to find the failures.
+pub fn verify_batch_stats<E: Pairing>(
+    presentations: &[ShowCredential<E>],
+    pp: &PublicParams<E>,
+    vk: &VerificationKey<E>,
+) -> BatchStats {
+    let start = Instant::now();
+    let failed_indices: Vec<usize> = if BatchVerifier::new(pp, vk, presentations).finalize() {
+        Vec::new()
+    } else {
+        presentations
+            .iter()
+            .enumerate()
+            .filter(|(_, presentation)| !presentation.verify(pp, vk))
+            .map(|(index, _)| index)
+            .collect()
+    };
+    BatchStats {
+        total: presentations.len(),
+        passed: presentations.len() - failed_indices.len(),
+        failed_indices,
+        elapsed: start.elapsed(),
+    }
 }
 
 /// Helper functions for credential aggregation
@@ -189,12 +624,8 @@ impl CredentialAggregation {
         let mut presentations = Vec::new();
 
         for credential in credentials {
-            // Generate random values for each credential
-            let delta_r = E::ScalarField::rand(rng);
-            let delta_u = E::ScalarField::rand(rng);
-
-            // Create a presentation
-            let presentation = credential.show(pp, &delta_r, &delta_u, rng);
+            // Create a presentation with fresh randomness
+            let presentation = credential.show_auto(pp, rng)?;
             presentations.push(presentation);
         }
 
@@ -260,7 +691,8 @@ impl<E: Pairing> PlaintextAggregation<E> {
 mod tests {
     use super::*;
     use crate::protocol::MimcAbc;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use crate::serialization::to_bytes;
+    use ark_bls12_381::{Bls12_381, Fr, G1Affine};
     use ark_std::test_rng;
     use ark_std::UniformRand;
 
@@ -290,7 +722,7 @@ mod tests {
             // Issue credential
             let proof = credential.prove_commitment(&protocol.pp, &mut rng);
             let signature = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
-            credential.add_signature(signature);
+            credential.add_signature(signature).unwrap();
 
             // Verify individual credential
             assert!(
@@ -313,7 +745,7 @@ mod tests {
 
         // Verify using batch approach
         let batch_start = std::time::Instant::now();
-        let batch_result = aggregate.batch_verify(&protocol.pp, &issuer_vk);
+        let batch_result = aggregate.batch_verify(&protocol.pp, &issuer_vk, &mut rng);
         let batch_time = batch_start.elapsed();
 
         // Both should succeed
@@ -325,4 +757,479 @@ mod tests {
             credential_count, standard_time, batch_time
         );
     }
+
+    #[test]
+    fn test_aggregate_rejects_unbound_proofs() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let mut presentations: Vec<ShowCredential<Bls12_381>> = (0..4)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let mut credential =
+                    Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+                protocol
+                    .obtain_and_issue(&mut credential, &sk, &mut rng)
+                    .unwrap();
+                credential.show_auto(&protocol.pp, &mut rng).unwrap()
+            })
+            .collect();
+        let spare = presentations.pop().unwrap();
+        let valid = AggregatePresentation::new(presentations);
+        let all_pass = |aggregate: &AggregatePresentation<Bls12_381>, rng: &mut _| {
+            [
+                aggregate.verify_all(&protocol.pp, &vk),
+                aggregate.par_verify_all(&protocol.pp, &vk),
+                aggregate.batch_verify(&protocol.pp, &vk, rng),
+                aggregate.batch_verify_chunked(&protocol.pp, &vk, 2, rng),
+                aggregate.batch_verify_randomized(&protocol.pp, &vk),
+                aggregate.batch_verify_par(&protocol.pp, &vk, rng),
+            ]
+        };
+        assert_eq!(all_pass(&valid, &mut rng), [true; 6]);
+
+        // A valid proof, but for another credential's commitment
+        let mut aggregate = AggregatePresentation {
+            randomized_signatures: valid.randomized_signatures.clone(),
+            randomized_commitments: valid.randomized_commitments.clone(),
+            proofs: valid.proofs.clone(),
+        };
+        aggregate.proofs[0] = spare.proof.clone();
+        assert_eq!(all_pass(&aggregate, &mut rng), [false; 6]);
+
+        // A replayed signature and commitment with a proof over bases of the prover's
+        // choosing, ck[0] = cm, which opens cm without knowing its attributes
+        let stolen = valid.randomized_commitments[1].clone();
+        let mut fake_pp = protocol.pp.clone();
+        fake_pp.ck[0] = stolen.cm;
+        fake_pp.refresh_bases();
+        let mut unit = vec![Fr::from(0u64); n];
+        unit[0] = Fr::from(1u64);
+        let forged = CommitmentProof::prove(&fake_pp, &stolen, &unit, &Fr::from(0u64), &mut rng);
+        assert!(forged.verify());
+        aggregate.proofs[0] = valid.proofs[0].clone();
+        aggregate.proofs[1] = forged;
+        assert_eq!(all_pass(&aggregate, &mut rng), [false; 6]);
+
+        // The identity signature with an honest proof
+        aggregate.proofs[1] = valid.proofs[1].clone();
+        aggregate.randomized_signatures[2].sigma1 = G1Affine::zero();
+        aggregate.randomized_signatures[2].sigma2 = G1Affine::zero();
+        assert_eq!(all_pass(&aggregate, &mut rng), [false; 6]);
+    }
+
+    #[test]
+    fn test_batch_verify_randomized_order_independent() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let mut presentations = Vec::new();
+        for _ in 0..4 {
+            let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+            let r = Fr::rand(&mut rng);
+            let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+            let proof = credential.prove_commitment(&protocol.pp, &mut rng);
+            credential
+                .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+                .unwrap();
+            presentations.push(protocol.show(&credential, &mut rng).unwrap());
+        }
+
+        let forward = AggregatePresentation::new(presentations.clone());
+        let mut shuffled = presentations.clone();
+        shuffled.swap(0, 3);
+        shuffled.swap(1, 2);
+        let shuffled = AggregatePresentation::new(shuffled);
+
+        assert!(forward.batch_verify_randomized(&protocol.pp, &vk));
+        assert!(shuffled.batch_verify_randomized(&protocol.pp, &vk));
+
+        // Each credential gets the same weight whatever its position
+        let forward_weights = forward.batch_weights(&vk);
+        let shuffled_weights = shuffled.batch_weights(&vk);
+        assert_eq!(forward_weights[0], shuffled_weights[3]);
+        assert_eq!(forward_weights[1], shuffled_weights[2]);
+
+        // A bad signature is rejected in either order
+        let mut bad = presentations;
+        bad[1].randomized_signature.sigma2 = bad[2].randomized_signature.sigma2;
+        let bad_forward = AggregatePresentation::new(bad.clone());
+        bad.reverse();
+        let bad_reversed = AggregatePresentation::new(bad);
+        assert!(!bad_forward.batch_verify_randomized(&protocol.pp, &vk));
+        assert!(!bad_reversed.batch_verify_randomized(&protocol.pp, &vk));
+    }
+
+    #[test]
+    fn test_batch_evaluate() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let mut presentations = Vec::new();
+        for _ in 0..3 {
+            let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+            let r = Fr::rand(&mut rng);
+            let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+            let proof = protocol.obtain(&credential, &mut rng);
+            credential
+                .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+                .unwrap();
+            presentations.push(protocol.show(&credential, &mut rng).unwrap());
+        }
+        let identity = <Bls12_381 as Pairing>::TargetField::one();
+
+        let valid = AggregatePresentation::new(presentations.clone());
+        assert_eq!(valid.batch_evaluate(&protocol.pp, &vk).unwrap(), identity);
+
+        let mut corrupted = presentations;
+        corrupted[2].randomized_signature.sigma2 =
+            (corrupted[2].randomized_signature.sigma2 + protocol.pp.g).into_affine();
+        let corrupted = AggregatePresentation::new(corrupted);
+        let value = corrupted.batch_evaluate(&protocol.pp, &vk).unwrap();
+        assert_ne!(value, identity);
+        assert!(!corrupted.batch_verify_randomized(&protocol.pp, &vk));
+    }
+
+    #[test]
+    fn test_batch_verify_commitment_consistency() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, _, _) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let mut commitments: Vec<Commitment<Bls12_381>> = (0..16)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let r = Fr::rand(&mut rng);
+                protocol.ck.commit(&protocol.pp, &messages, &r)
+            })
+            .collect();
+
+        let refs: Vec<&Commitment<Bls12_381>> = commitments.iter().collect();
+        assert!(batch_verify_commitment_consistency(&refs, &protocol.pp));
+
+        // One commitment whose G2 side opens to something else
+        commitments[9].cm_tilde = commitments[3].cm_tilde;
+        let refs: Vec<&Commitment<Bls12_381>> = commitments.iter().collect();
+        assert!(
+            !batch_verify_commitment_consistency(&refs, &protocol.pp),
+            "An inconsistent cm_tilde should fail the batch"
+        );
+    }
+
+    #[test]
+    fn test_verify_and_accumulate() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let presentations: Vec<ShowCredential<Bls12_381>> = (0..8)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let r = Fr::rand(&mut rng);
+                let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+                let proof = protocol.obtain(&credential, &mut rng);
+                credential
+                    .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+                    .unwrap();
+                protocol.show(&credential, &mut rng).unwrap()
+            })
+            .collect();
+
+        let (root, proofs) = verify_and_accumulate(&presentations, &protocol.pp, &vk).unwrap();
+        assert_eq!(proofs.len(), 8);
+        for (presentation, proof) in presentations.iter().zip(&proofs) {
+            assert_eq!(proof.siblings.len(), 3);
+            assert!(proof.verify(&presentation.randomized_commitment, &root));
+        }
+        // A proof opens only its own leaf
+        assert!(!proofs[0].verify(&presentations[1].randomized_commitment, &root));
+
+        // Odd-sized batches carry the last node up
+        let (root, proofs) = verify_and_accumulate(&presentations[..5], &protocol.pp, &vk).unwrap();
+        for (presentation, proof) in presentations.iter().zip(&proofs) {
+            assert!(proof.verify(&presentation.randomized_commitment, &root));
+        }
+
+        // A presentation whose commitment isn't the one its proof opens is refused
+        let mut unbound = presentations.clone();
+        unbound[2].randomized_commitment.cm_tilde = unbound[3].randomized_commitment.cm_tilde;
+        assert!(matches!(
+            verify_and_accumulate(&unbound, &protocol.pp, &vk),
+            Err(Error::InvalidProof)
+        ));
+
+        // Nothing is accumulated if any presentation fails
+        let mut bad = presentations;
+        bad[5].randomized_signature.sigma2 = bad[6].randomized_signature.sigma2;
+        assert!(verify_and_accumulate(&bad, &protocol.pp, &vk).is_err());
+    }
+
+    #[test]
+    fn test_verify_batch_stats() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let mut presentations: Vec<ShowCredential<Bls12_381>> = (0..6)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let mut credential =
+                    Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+                protocol
+                    .obtain_and_issue(&mut credential, &sk, &mut rng)
+                    .unwrap();
+                credential.show_auto(&protocol.pp, &mut rng).unwrap()
+            })
+            .collect();
+
+        // All pass on the fast path
+        let stats = verify_batch_stats(&presentations, &protocol.pp, &vk);
+        assert_eq!(stats.total, 6);
+        assert_eq!(stats.passed, 6);
+        assert!(stats.failed_indices.is_empty());
+
+        // A forged signature and a broken proof are both found
+        presentations[1].randomized_signature.sigma2 = presentations[2].randomized_signature.sigma2;
+        presentations[4].proof.responses[0] += Fr::from(1u64);
+        let stats = verify_batch_stats(&presentations, &protocol.pp, &vk);
+        assert_eq!(stats.total, 6);
+        assert_eq!(stats.passed, 4);
+        assert_eq!(stats.failed_indices, vec![1, 4]);
+
+        // sigma2 + D and sigma2 - D cancel under equal batch weights: the batch must fail,
+        // and the fallback report both
+        presentations[1] = presentations[2].clone();
+        presentations[4].proof.responses[0] -= Fr::from(1u64);
+        let shift = G1Affine::rand(&mut rng);
+        let sigma2 = &mut presentations[0].randomized_signature.sigma2;
+        *sigma2 = (*sigma2 + shift).into_affine();
+        let sigma2 = &mut presentations[3].randomized_signature.sigma2;
+        *sigma2 = (*sigma2 - shift).into_affine();
+        let stats = verify_batch_stats(&presentations, &protocol.pp, &vk);
+        assert_eq!(stats.passed, 4);
+        assert_eq!(stats.failed_indices, vec![0, 3]);
+
+        let stats = verify_batch_stats::<Bls12_381>(&[], &protocol.pp, &vk);
+        assert_eq!((stats.total, stats.passed), (0, 0));
+    }
+
+    // Runs with and without the `parallel` feature, the results must not depend on it
+    #[test]
+    fn test_par_verify_all() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let presentations: Vec<ShowCredential<Bls12_381>> = (0..4)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let mut credential =
+                    Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+                protocol
+                    .obtain_and_issue(&mut credential, &sk, &mut rng)
+                    .unwrap();
+                credential.show_auto(&protocol.pp, &mut rng).unwrap()
+            })
+            .collect();
+        let mut aggregate = AggregatePresentation::new(presentations);
+        assert!(aggregate.par_verify_all(&protocol.pp, &vk));
+        assert_eq!(
+            aggregate.par_verify_all(&protocol.pp, &vk),
+            aggregate.verify_all(&protocol.pp, &vk)
+        );
+
+        aggregate.randomized_signatures[3].sigma2 = aggregate.randomized_signatures[0].sigma2;
+        assert!(!aggregate.par_verify_all(&protocol.pp, &vk));
+        aggregate.randomized_commitments.pop();
+        assert!(!aggregate.par_verify_all(&protocol.pp, &vk));
+    }
+
+    #[test]
+    fn test_batch_verify_weights_equations() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let presentations: Vec<ShowCredential<Bls12_381>> = (0..5)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let mut credential =
+                    Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+                protocol
+                    .obtain_and_issue(&mut credential, &sk, &mut rng)
+                    .unwrap();
+                credential.show_auto(&protocol.pp, &mut rng).unwrap()
+            })
+            .collect();
+        let mut aggregate = AggregatePresentation::new(presentations);
+        assert!(aggregate.batch_verify(&protocol.pp, &vk, &mut rng));
+
+        // One invalid signature in the batch of five
+        let valid = aggregate.randomized_signatures[2].sigma2;
+        aggregate.randomized_signatures[2].sigma2 = (valid + protocol.pp.g).into_affine();
+        assert!(!aggregate.batch_verify(&protocol.pp, &vk, &mut rng));
+
+        // Two invalid signatures whose errors cancel under equal weights
+        let shifted = aggregate.randomized_signatures[3].sigma2;
+        aggregate.randomized_signatures[3].sigma2 = (shifted - protocol.pp.g).into_affine();
+        assert!(!aggregate.batch_verify(&protocol.pp, &vk, &mut rng));
+    }
+
+    #[test]
+    fn test_batch_verify_par() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let presentations: Vec<ShowCredential<Bls12_381>> = (0..6)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let mut credential =
+                    Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+                protocol
+                    .obtain_and_issue(&mut credential, &sk, &mut rng)
+                    .unwrap();
+                credential.show_auto(&protocol.pp, &mut rng).unwrap()
+            })
+            .collect();
+        let mut aggregate = AggregatePresentation::new(presentations);
+        assert!(aggregate.batch_verify_par(&protocol.pp, &vk, &mut rng));
+
+        // Agrees with the sequential batch on a forged signature
+        let valid = aggregate.randomized_signatures[4].sigma2;
+        aggregate.randomized_signatures[4].sigma2 = (valid + protocol.pp.g).into_affine();
+        assert!(!aggregate.batch_verify(&protocol.pp, &vk, &mut rng));
+        assert!(!aggregate.batch_verify_par(&protocol.pp, &vk, &mut rng));
+        aggregate.randomized_signatures[4].sigma2 = valid;
+
+        // And on a failing proof
+        aggregate.proofs.swap(0, 1);
+        assert!(!aggregate.batch_verify_par(&protocol.pp, &vk, &mut rng));
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let credentials: Vec<Credential<Bls12_381>> = (0..5)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let mut credential =
+                    Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+                protocol
+                    .obtain_and_issue(&mut credential, &sk, &mut rng)
+                    .unwrap();
+                credential
+            })
+            .collect();
+
+        // Presentations stream straight into the aggregate
+        let streamed = AggregatePresentation::from_iter(
+            credentials
+                .iter()
+                .map(|credential| credential.show_auto(&protocol.pp, &mut rng).unwrap()),
+        );
+        assert_eq!(streamed.proofs.len(), 5);
+        assert!(streamed.verify_all(&protocol.pp, &vk));
+        assert!(streamed.batch_verify(&protocol.pp, &vk, &mut rng));
+
+        // `new` gives the same aggregate from the same presentations
+        let presentations: Vec<ShowCredential<Bls12_381>> = credentials
+            .iter()
+            .map(|credential| credential.show_auto(&protocol.pp, &mut rng).unwrap())
+            .collect();
+        let collected: AggregatePresentation<Bls12_381> =
+            presentations.clone().into_iter().collect();
+        let built = AggregatePresentation::new(presentations);
+        assert_eq!(
+            to_bytes(&collected.randomized_commitments),
+            to_bytes(&built.randomized_commitments)
+        );
+        assert_eq!(
+            to_bytes(&collected.randomized_signatures),
+            to_bytes(&built.randomized_signatures)
+        );
+        assert_eq!(to_bytes(&collected.proofs), to_bytes(&built.proofs));
+        assert!(built.verify_all(&protocol.pp, &vk));
+    }
+
+    #[test]
+    fn test_verify_all_detailed() {
+        let mut rng = test_rng();
+        let n = 3;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let mut aggregate: AggregatePresentation<Bls12_381> = (0..6)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let mut credential =
+                    Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+                protocol
+                    .obtain_and_issue(&mut credential, &sk, &mut rng)
+                    .unwrap();
+                credential.show_auto(&protocol.pp, &mut rng).unwrap()
+            })
+            .collect();
+        assert!(aggregate.verify_all_detailed(&protocol.pp, &vk).is_ok());
+
+        // A forged signature on 1 and a broken proof on 4
+        let sigma2 = aggregate.randomized_signatures[1].sigma2;
+        aggregate.randomized_signatures[1].sigma2 = (sigma2 + protocol.pp.g).into_affine();
+        aggregate.proofs[4].responses[0] += Fr::from(1u64);
+        assert_eq!(
+            aggregate.verify_all_detailed(&protocol.pp, &vk),
+            Err(vec![1, 4])
+        );
+
+        // A valid proof moved onto another credential's commitment
+        aggregate.proofs[2] = aggregate.proofs[3].clone();
+        assert_eq!(
+            aggregate.verify_all_detailed(&protocol.pp, &vk),
+            Err(vec![1, 2, 4])
+        );
+
+        // A presentation missing its proof fails too
+        aggregate.proofs.pop();
+        assert_eq!(
+            aggregate.verify_all_detailed(&protocol.pp, &vk),
+            Err(vec![1, 2, 4, 5])
+        );
+    }
+
+    #[test]
+    fn test_batch_verify_chunked() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let presentations: Vec<ShowCredential<Bls12_381>> = (0..7)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let mut credential =
+                    Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+                protocol
+                    .obtain_and_issue(&mut credential, &sk, &mut rng)
+                    .unwrap();
+                credential.show_auto(&protocol.pp, &mut rng).unwrap()
+            })
+            .collect();
+        let mut aggregate = AggregatePresentation::new(presentations);
+        assert!(aggregate.batch_verify(&protocol.pp, &vk, &mut rng));
+        // Chunks that divide the batch, that don't, and one bigger than it
+        for chunk_size in [1, 3, 7, 10] {
+            assert!(aggregate.batch_verify_chunked(&protocol.pp, &vk, chunk_size, &mut rng));
+        }
+        // An empty chunk size is refused rather than panicking
+        assert!(!aggregate.batch_verify_chunked(&protocol.pp, &vk, 0, &mut rng));
+
+        // A forged signature in the last, partial chunk
+        aggregate.randomized_signatures[6].sigma2 = aggregate.randomized_signatures[0].sigma2;
+        assert!(!aggregate.batch_verify(&protocol.pp, &vk, &mut rng));
+        for chunk_size in [1, 3, 7, 10] {
+            assert!(!aggregate.batch_verify_chunked(&protocol.pp, &vk, chunk_size, &mut rng));
+        }
+    }
 }
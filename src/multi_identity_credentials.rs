@@ -41,7 +41,8 @@ impl<E: Pairing> User<E> {
         let cred_refs: Vec<&Credential<E>> = credentials.iter().map(|c| &**c).collect();
 
         // Create a linked credential presentation
-        LinkedCredentialPresentation::create(&cred_refs, &public_params, rng)
+        let disclosed = vec![Vec::new(); cred_refs.len()];
+        LinkedCredentialPresentation::create(&cred_refs, &public_params, &disclosed, rng)
     }
 }
 
@@ -70,8 +71,8 @@ pub fn verify_linked_credentials<E: Pairing>(
         public_params.push(&issuer.protocol.pp);
     }
 
-    // Simply verify the presentation without any batching
-    presentation.verify(&public_params, &verification_keys)
+    // Simply verify the presentation without any batching; users keep their ID in slot 0
+    presentation.verify(&public_params, &verification_keys, 0)
 }
 
 #[cfg(test)]
@@ -161,7 +162,7 @@ mod tests {
             .expect("Credential issuance for other user should succeed");
 
         // Try to create a presentation with credentials from both users (different IDs)
-        let mismatched_creds = vec![
+        let mismatched_creds = [
             user.credentials.get(&(2, 202)).unwrap(),
             user.credentials.get(&(3, 303)).unwrap(),
             other_user.credentials.get(&(1, 101)).unwrap(),
@@ -175,8 +176,12 @@ mod tests {
 
         // This should fail because the user IDs don't match
         let refs: Vec<&Credential<Bls12_381>> = mismatched_creds.iter().map(|c| &**c).collect();
-        let invalid_presentation =
-            LinkedCredentialPresentation::create(&refs, &mismatched_params, &mut rng);
+        let invalid_presentation = LinkedCredentialPresentation::create(
+            &refs,
+            &mismatched_params,
+            &[vec![], vec![], vec![]],
+            &mut rng,
+        );
 
         assert!(
             invalid_presentation.is_err(),
@@ -184,4 +189,49 @@ mod tests {
         );
         println!("Successfully prevented presentation with different user IDs!");
     }
+
+    #[test]
+    fn test_removed_issuer_fails_cleanly() {
+        let mut rng = test_rng();
+        let mut system = MultiIssuerSystem::<Bls12_381>::new();
+        system.setup_issuers(3, &[4, 5, 3], &mut rng);
+        let mut user = User::<Bls12_381>::new(&mut rng);
+        for (issuer_id, n) in [(0, 4), (1, 5), (2, 3)] {
+            let attributes: Vec<Fr> = (1..n).map(|_| Fr::rand(&mut rng)).collect();
+            user.obtain_credential(issuer_id, 10 + issuer_id, &system, attributes, &mut rng)
+                .unwrap();
+        }
+        let keys = [(0, 10), (2, 12)];
+        let presentation = user
+            .show_linked_credentials(&keys, &system, &mut rng)
+            .unwrap();
+
+        assert!(system.remove_issuer(2).is_some());
+        assert!(system.remove_issuer(2).is_none());
+
+        // Verifying an earlier presentation over the removed issuer
+        let Err(Error::Other(message)) = verify_linked_credentials(&presentation, &system, &[0, 2])
+        else {
+            panic!("Removed issuer should be reported");
+        };
+        assert!(message.contains("Issuer 2 not found"));
+
+        // Showing with the credential still held, then after dropping it
+        assert!(matches!(
+            user.show_linked_credentials(&keys, &system, &mut rng),
+            Err(Error::Other(_))
+        ));
+        assert_eq!(user.drop_credentials_from_issuer(2), 1);
+        assert_eq!(user.credentials.len(), 2);
+        assert!(matches!(
+            user.show_linked_credentials(&keys, &system, &mut rng),
+            Err(Error::Other(_))
+        ));
+
+        // Credentials from the remaining issuers still link
+        let presentation = user
+            .show_linked_credentials(&[(0, 10), (1, 11)], &system, &mut rng)
+            .unwrap();
+        assert!(verify_linked_credentials(&presentation, &system, &[0, 1]).unwrap());
+    }
 }
@@ -1,13 +1,22 @@
-use crate::credential::{Credential, ShowCredential};
+use crate::credential::{Credential, CredentialBuilder, ShowCredential};
+use crate::descent::path_tweak;
 use crate::error::Error;
 use crate::protocol::MimcAbc;
-use crate::signature::{SecretKey, VerificationKey};
+use crate::public_params::PublicParams;
+use crate::serialization::{from_bytes, read_version, to_bytes, write_version};
+use crate::signature::{generate_keys, KeyRotationProof, SecretKey, VerificationKey};
 use ark_ec::pairing::Pairing;
 use ark_ff::UniformRand;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
 use ark_std::rand::Rng;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
 
 /// Structure to represent an issuer in the system
+#[derive(Clone)]
 pub struct Issuer<E: Pairing> {
     pub id: usize,
     pub protocol: MimcAbc<E>,
@@ -26,13 +35,90 @@ impl<E: Pairing> Issuer<E> {
             vk,
         }
     }
+
+    /// Replace the issuer's keys with fresh ones, returning a proof linking the old
+    /// verification key to the new one. Credentials issued before keep verifying under the
+    /// old key, verifiers that trust it check the proof with `verify_rotation`
+    pub fn rotate_key(&mut self, rng: &mut impl Rng) -> KeyRotationProof<E> {
+        let (sk, vk) = generate_keys(&self.protocol.pp, rng);
+        let proof = KeyRotationProof::prove(&self.protocol.pp, &self.sk, &sk, rng);
+        self.sk = sk;
+        self.vk = vk;
+        proof
+    }
+}
+
+// Keys and parameters are left out, only the ID and attribute count are printed
+impl<E: Pairing> fmt::Debug for Issuer<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Issuer")
+            .field("id", &self.id)
+            .field("attributes", &self.protocol.pp.n)
+            .finish_non_exhaustive()
+    }
+}
+
+// The commitment key is rebuilt from the parameters on decode, so only these are encoded
+impl<E: Pairing> CanonicalSerialize for Issuer<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.id.serialize_with_mode(&mut writer, compress)?;
+        self.protocol
+            .pp
+            .serialize_with_mode(&mut writer, compress)?;
+        self.sk.serialize_with_mode(&mut writer, compress)?;
+        self.vk.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.id.serialized_size(compress)
+            + self.protocol.pp.serialized_size(compress)
+            + self.sk.serialized_size(compress)
+            + self.vk.serialized_size(compress)
+    }
+}
+
+impl<E: Pairing> Valid for Issuer<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
 }
 
+impl<E: Pairing> CanonicalDeserialize for Issuer<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let id = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let pp = PublicParams::deserialize_with_mode(&mut reader, compress, validate)?;
+        let protocol = MimcAbc::new(pp).map_err(|_| SerializationError::InvalidData)?;
+        Ok(Self {
+            id,
+            protocol,
+            sk: SecretKey::deserialize_with_mode(&mut reader, compress, validate)?,
+            vk: VerificationKey::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+const MULTI_ISSUER_SYSTEM_VERSION: u8 = 1;
+
 /// Multi-issuer system manager
+#[derive(Clone)]
 pub struct MultiIssuerSystem<E: Pairing> {
     pub issuers: HashMap<usize, Issuer<E>>,
 }
 
+impl<E: Pairing> Default for MultiIssuerSystem<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<E: Pairing> MultiIssuerSystem<E> {
     /// Create a new multi-issuer system
     pub fn new() -> Self {
@@ -46,6 +132,12 @@ impl<E: Pairing> MultiIssuerSystem<E> {
         self.issuers.insert(issuer.id, issuer);
     }
 
+    /// Decommission an issuer, returning it if it was present
+    /// Presentations referencing it fail with `Error::Other` from then on
+    pub fn remove_issuer(&mut self, issuer_id: usize) -> Option<Issuer<E>> {
+        self.issuers.remove(&issuer_id)
+    }
+
     /// Generate multiple issuers for the system
     pub fn setup_issuers(
         &mut self,
@@ -69,14 +161,105 @@ impl<E: Pairing> MultiIssuerSystem<E> {
     pub fn get_issuer(&self, issuer_id: usize) -> Option<&Issuer<E>> {
         self.issuers.get(&issuer_id)
     }
+
+    /// Encode every issuer's parameters and keys, for restoring after a restart
+    ///
+    /// The output contains the issuers' SECRET KEYS in the clear: anyone who reads it can
+    /// issue credentials under these issuers. Store it encrypted and access-controlled.
+    pub fn export(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+
+    /// Restore a system written by `export`
+    pub fn import(bytes: &[u8]) -> Result<Self, Error> {
+        from_bytes(bytes)
+    }
+}
+
+impl<E: Pairing> fmt::Debug for MultiIssuerSystem<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiIssuerSystem")
+            .field("issuers", &self.sorted_issuers())
+            .finish()
+    }
+}
+
+// Issuers are written in id order so the encoding doesn't depend on map iteration
+impl<E: Pairing> CanonicalSerialize for MultiIssuerSystem<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        write_version(&mut writer, MULTI_ISSUER_SYSTEM_VERSION)?;
+        // Same layout as a Vec: length, then the items
+        (self.issuers.len() as u64).serialize_with_mode(&mut writer, compress)?;
+        for issuer in self.sorted_issuers() {
+            issuer.serialize_with_mode(&mut writer, compress)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + 8
+            + self
+                .issuers
+                .values()
+                .map(|issuer| issuer.serialized_size(compress))
+                .sum::<usize>()
+    }
+}
+
+impl<E: Pairing> MultiIssuerSystem<E> {
+    fn sorted_issuers(&self) -> Vec<&Issuer<E>> {
+        let mut issuers: Vec<&Issuer<E>> = self.issuers.values().collect();
+        issuers.sort_by_key(|issuer| issuer.id);
+        issuers
+    }
+}
+
+impl<E: Pairing> Valid for MultiIssuerSystem<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for MultiIssuerSystem<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        read_version(&mut reader, MULTI_ISSUER_SYSTEM_VERSION)?;
+        let issuers = Vec::<Issuer<E>>::deserialize_with_mode(&mut reader, compress, validate)?;
+        let mut system = Self::new();
+        for issuer in issuers {
+            if system.issuers.contains_key(&issuer.id) {
+                return Err(SerializationError::InvalidData);
+            }
+            system.add_issuer(issuer);
+        }
+        Ok(system)
+    }
 }
 
 /// Structure to represent a user with multiple credentials from various issuers
+#[derive(Clone)]
 pub struct User<E: Pairing> {
     pub id: E::ScalarField,
     pub credentials: HashMap<(usize, usize), Credential<E>>, // (issuer_id, credential_id) -> Credential
 }
 
+// Only the (issuer_id, credential_id) keys are printed, never the ID or attribute values
+impl<E: Pairing> fmt::Debug for User<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys: BTreeSet<&(usize, usize)> = self.credentials.keys().collect();
+        f.debug_struct("User")
+            .field("credentials", &keys)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<E: Pairing> User<E> {
     /// Create a new user with a random ID
     pub fn new(rng: &mut impl Rng) -> Self {
@@ -87,6 +270,24 @@ impl<E: Pairing> User<E> {
         }
     }
 
+    /// Derive a child identity (department, team) from this one
+    /// The child's ID is id + H(path), so a DescentProof can link it back without revealing id
+    pub fn derive_child(&self, path: &[u8]) -> User<E> {
+        Self {
+            id: self.id + path_tweak::<E>(path),
+            credentials: HashMap::new(),
+        }
+    }
+
+    /// Remove every credential from `issuer_id`, e.g. after the issuer is decommissioned
+    /// Returns how many were removed
+    pub fn drop_credentials_from_issuer(&mut self, issuer_id: usize) -> usize {
+        let before = self.credentials.len();
+        self.credentials
+            .retain(|(issuer, _), _| *issuer != issuer_id);
+        before - self.credentials.len()
+    }
+
     /// Obtain a credential from a specific issuer
     pub fn obtain_credential(
         &mut self,
@@ -100,23 +301,19 @@ impl<E: Pairing> User<E> {
             .get_issuer(issuer_id)
             .ok_or_else(|| Error::Other(format!("Issuer {} not found", issuer_id)))?;
 
-        // Create a credential with the user's ID as the first attribute
-        let mut all_attributes = vec![self.id];
-        all_attributes.extend(attributes);
-
-        // Check if attribute count matches the issuer's expected count
-        if all_attributes.len() != issuer.protocol.pp.n {
-            return Err(Error::Other(format!(
-                "Attribute count mismatch: expected {}, got {}",
-                issuer.protocol.pp.n,
-                all_attributes.len()
-            )));
-        }
-
-        // Create the credential
-        let r = E::ScalarField::rand(rng);
-        let mut credential =
-            Credential::new(&issuer.protocol.ck, &issuer.protocol.pp, &all_attributes, r);
+        // The user ID takes the first slot, so the caller supplies n - 1 attributes
+        let attribute_count = attributes.len();
+        let mut credential = CredentialBuilder::new()
+            .user_id(self.id)
+            .attributes(attributes)
+            .build(&issuer.protocol.pp, &issuer.protocol.ck, rng)
+            .map_err(|_| {
+                Error::Other(format!(
+                    "Attribute count mismatch: issuer expects {} total attributes including the user ID; you supplied {} additional attributes",
+                    issuer.protocol.pp.n,
+                    attribute_count
+                ))
+            })?;
 
         // Generate proof for issuance
         let proof = credential.prove_commitment(&issuer.protocol.pp, rng);
@@ -125,7 +322,7 @@ impl<E: Pairing> User<E> {
         let signature = issuer.protocol.issue(&proof, &issuer.sk, rng)?;
 
         // Add signature to credential
-        credential.add_signature(signature);
+        credential.add_signature(signature)?;
 
         // Store the credential
         self.credentials
@@ -158,7 +355,7 @@ impl<E: Pairing> User<E> {
                 .get_issuer(*issuer_id)
                 .ok_or_else(|| Error::Other(format!("Issuer {} not found", issuer_id)))?;
 
-            let presentation = issuer.protocol.show(credential, rng);
+            let presentation = issuer.protocol.show(credential, rng)?;
             presentations.push(presentation);
         }
 
@@ -188,8 +385,7 @@ mod tests {
         let mut user = User::<Bls12_381>::new(&mut rng);
 
         // Obtain credentials from different issuers
-        for issuer_id in 0..3 {
-            let attr_count = attributes_per_issuer[issuer_id];
+        for (issuer_id, &attr_count) in attributes_per_issuer.iter().enumerate() {
             let attributes: Vec<Fr> = (0..(attr_count - 1)).map(|_| Fr::rand(&mut rng)).collect();
 
             user.obtain_credential(issuer_id, 0, &system, attributes, &mut rng)
@@ -213,4 +409,250 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let mut rng = ark_std::test_rng();
+        let mut system = MultiIssuerSystem::<Bls12_381>::new();
+        system.setup_issuers(3, &[4, 6, 5], &mut rng);
+
+        let bytes = system.export();
+        let restored = MultiIssuerSystem::<Bls12_381>::import(&bytes).unwrap();
+        assert_eq!(restored.issuers.len(), 3);
+        assert_eq!(restored.export(), bytes);
+
+        // The restored issuers sign credentials the original keys accept
+        let mut user = User::<Bls12_381>::new(&mut rng);
+        for issuer_id in 0..3 {
+            let n = restored.get_issuer(issuer_id).unwrap().protocol.pp.n;
+            let attributes: Vec<Fr> = (1..n).map(|_| Fr::rand(&mut rng)).collect();
+            user.obtain_credential(issuer_id, 0, &restored, attributes, &mut rng)
+                .unwrap();
+            let presentation = user
+                .show_credentials(&[(issuer_id, 0)], &restored, &mut rng)
+                .unwrap()
+                .remove(0);
+            let original = system.get_issuer(issuer_id).unwrap();
+            assert!(presentation.verify(&original.protocol.pp, &original.vk));
+        }
+
+        // Truncated or foreign-version input is rejected
+        assert!(MultiIssuerSystem::<Bls12_381>::import(&bytes[..bytes.len() - 1]).is_err());
+        let mut other_version = bytes;
+        other_version[0] = 2;
+        assert!(MultiIssuerSystem::<Bls12_381>::import(&other_version).is_err());
+    }
+
+    #[test]
+    fn test_obtain_credential_count_error() {
+        let mut rng = ark_std::test_rng();
+        let mut system = MultiIssuerSystem::<Bls12_381>::new();
+        system.setup_issuers(1, &[5], &mut rng);
+        let mut user = User::<Bls12_381>::new(&mut rng);
+
+        // Passing all 5 attributes forgets the user ID slot
+        let attributes: Vec<Fr> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let err = user
+            .obtain_credential(0, 0, &system, attributes, &mut rng)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Other error: Attribute count mismatch: issuer expects 5 total attributes including the user ID; you supplied 5 additional attributes"
+        );
+
+        let attributes: Vec<Fr> = (0..2).map(|_| Fr::rand(&mut rng)).collect();
+        let err = user
+            .obtain_credential(0, 0, &system, attributes, &mut rng)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("you supplied 2 additional attributes"));
+        assert!(user.credentials.is_empty(), "Nothing should be stored");
+    }
+
+    #[test]
+    fn test_derive_children_prove_descent() {
+        use crate::descent::DescentProof;
+
+        let mut rng = ark_std::test_rng();
+        let mut system = MultiIssuerSystem::<Bls12_381>::new();
+        system.setup_issuers(2, &[4, 5], &mut rng);
+
+        let root = User::<Bls12_381>::new(&mut rng);
+        let mut engineering = root.derive_child(b"org/engineering");
+        let mut operations = root.derive_child(b"org/operations");
+        assert_ne!(engineering.id, operations.id);
+        assert_eq!(engineering.id, root.derive_child(b"org/engineering").id);
+
+        let attributes: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        engineering
+            .obtain_credential(0, 0, &system, attributes, &mut rng)
+            .unwrap();
+        let attributes: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        operations
+            .obtain_credential(1, 0, &system, attributes, &mut rng)
+            .unwrap();
+
+        let eng_presentation = engineering
+            .show_credentials(&[(0, 0)], &system, &mut rng)
+            .unwrap()
+            .remove(0);
+        let ops_presentation = operations
+            .show_credentials(&[(1, 0)], &system, &mut rng)
+            .unwrap()
+            .remove(0);
+        let pp0 = &system.get_issuer(0).unwrap().protocol.pp;
+        let pp1 = &system.get_issuer(1).unwrap().protocol.pp;
+
+        let proof = DescentProof::prove(
+            &[&eng_presentation, &ops_presentation],
+            &[
+                &engineering.credentials[&(0, 0)],
+                &operations.credentials[&(1, 0)],
+            ],
+            &[b"org/engineering", b"org/operations"],
+            &[pp0, pp1],
+            &mut rng,
+        )
+        .expect("Children of one root should prove descent");
+        assert!(proof.verify(
+            &[&eng_presentation, &ops_presentation],
+            &[pp0, pp1],
+            &[0, 0]
+        ));
+
+        // Claiming the wrong path breaks the link
+        let mut tampered = proof.clone();
+        tampered.paths[1] = b"org/finance".to_vec();
+        assert!(!tampered.verify(
+            &[&eng_presentation, &ops_presentation],
+            &[pp0, pp1],
+            &[0, 0]
+        ));
+
+        // A child of a different root can't join the proof
+        let mut stranger = User::<Bls12_381>::new(&mut rng).derive_child(b"org/operations");
+        let attributes: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        stranger
+            .obtain_credential(1, 0, &system, attributes, &mut rng)
+            .unwrap();
+        let stranger_presentation = stranger
+            .show_credentials(&[(1, 0)], &system, &mut rng)
+            .unwrap()
+            .remove(0);
+        let result = DescentProof::prove(
+            &[&eng_presentation, &stranger_presentation],
+            &[
+                &engineering.credentials[&(0, 0)],
+                &stranger.credentials[&(1, 0)],
+            ],
+            &[b"org/engineering", b"org/operations"],
+            &[pp0, pp1],
+            &mut rng,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_descent_with_id_index() {
+        use crate::credential::Credential;
+        use crate::descent::{path_tweak, DescentProof};
+
+        let mut rng = ark_std::test_rng();
+        let mut system = MultiIssuerSystem::<Bls12_381>::new();
+        system.setup_issuers(2, &[4, 5], &mut rng);
+        let root = Fr::rand(&mut rng);
+
+        // Children of one root with the ID in slot 2 for one issuer and slot 0 for the other
+        let mut credentials = Vec::new();
+        let mut presentations = Vec::new();
+        for (issuer_id, id_index, path) in [(0, 2, b"org/a"), (1, 0, b"org/b")] {
+            let issuer = system.get_issuer(issuer_id).unwrap();
+            let protocol = &issuer.protocol;
+            let mut messages: Vec<Fr> = (0..protocol.pp.n).map(|_| Fr::rand(&mut rng)).collect();
+            messages[id_index] = root + path_tweak::<Bls12_381>(path);
+            let mut credential =
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng))
+                    .with_id_index(id_index)
+                    .unwrap();
+            protocol
+                .obtain_and_issue(&mut credential, &issuer.sk, &mut rng)
+                .unwrap();
+            presentations.push(credential.show_auto(&protocol.pp, &mut rng).unwrap());
+            credentials.push(credential);
+        }
+        let pp0 = &system.get_issuer(0).unwrap().protocol.pp;
+        let pp1 = &system.get_issuer(1).unwrap().protocol.pp;
+        let shows = [&presentations[0], &presentations[1]];
+
+        let proof = DescentProof::prove(
+            &shows,
+            &[&credentials[0], &credentials[1]],
+            &[b"org/a", b"org/b"],
+            &[pp0, pp1],
+            &mut rng,
+        )
+        .unwrap();
+        assert!(proof.verify(&shows, &[pp0, pp1], &[2, 0]));
+
+        // Checked against other slots than the issuers' schemas put the ID in, it fails
+        assert!(!proof.verify(&shows, &[pp0, pp1], &[0, 0]));
+        assert!(!proof.verify(&shows, &[pp0, pp1], &[2, 5]));
+    }
+
+    #[test]
+    fn test_debug_hides_secrets() {
+        let mut rng = ark_std::test_rng();
+        let mut system = MultiIssuerSystem::<Bls12_381>::new();
+        system.setup_issuers(2, &[3, 4], &mut rng);
+        let mut user = User::<Bls12_381>::new(&mut rng);
+        let attributes: Vec<Fr> = (0..2).map(|_| Fr::rand(&mut rng)).collect();
+        user.obtain_credential(0, 7, &system, attributes.clone(), &mut rng)
+            .unwrap();
+
+        let snapshot = user.clone();
+        let debug = format!("{:?}", snapshot);
+        assert!(debug.contains("(0, 7)"));
+        assert!(!debug.contains(&user.id.to_string()));
+        assert!(!debug.contains(&attributes[0].to_string()));
+
+        let debug = format!("{:?}", system.clone());
+        assert!(debug.contains("id: 0, attributes: 3"));
+        assert!(debug.contains("id: 1, attributes: 4"));
+    }
+
+    #[test]
+    fn test_rotate_key() {
+        use crate::signature::verify_rotation;
+        let mut rng = ark_std::test_rng();
+        let mut system = MultiIssuerSystem::<Bls12_381>::new();
+        system.setup_issuers(1, &[3], &mut rng);
+        let mut user = User::<Bls12_381>::new(&mut rng);
+        let attributes: Vec<Fr> = (0..2).map(|_| Fr::rand(&mut rng)).collect();
+        user.obtain_credential(0, 1, &system, attributes.clone(), &mut rng)
+            .unwrap();
+
+        let issuer = system.issuers.get_mut(&0).unwrap();
+        let old_vk = issuer.vk.clone();
+        let proof = issuer.rotate_key(&mut rng);
+        let new_vk = issuer.vk.clone();
+        let pp = issuer.protocol.pp.clone();
+        assert!(verify_rotation(&pp, &old_vk, &new_vk, &proof));
+        assert!(!verify_rotation(&pp, &new_vk, &old_vk, &proof));
+
+        // The old credential verifies under the old key only, new ones under the new key
+        let credential = &user.credentials[&(0, 1)];
+        assert!(credential.verify(&pp, &old_vk));
+        assert!(!credential.verify(&pp, &new_vk));
+        user.obtain_credential(0, 2, &system, attributes, &mut rng)
+            .unwrap();
+        assert!(user.credentials[&(0, 2)].verify(&pp, &new_vk));
+
+        // A proof for another key pair doesn't link these
+        let mut other = Issuer::<Bls12_381>::new(9, 3, &mut rng);
+        let other_old = other.vk.clone();
+        let other_proof = other.rotate_key(&mut rng);
+        assert!(!verify_rotation(&pp, &old_vk, &new_vk, &other_proof));
+        assert!(!verify_rotation(&pp, &other_old, &new_vk, &proof));
+    }
 }
@@ -0,0 +1,169 @@
+// mimc_abc/src/nullifier.rs
+use crate::commitment::Commitment;
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::schnorr::SchnorrProtocol;
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::Field;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::ops::Mul;
+use ark_std::rand::Rng;
+use std::collections::HashSet;
+
+/// Map a verifier context (election id, coupon batch) to a scalar
+pub fn context_scalar<E: Pairing>(context: &[u8]) -> E::ScalarField {
+    let mut transcript = Transcript::new(b"mimc-abc-nullifier-context");
+    transcript.append_message(b"context", context);
+    transcript.challenge_scalar(b"context")
+}
+
+/// Deterministic per-context nullifier N = g^{1 / (user_id + H(context))}
+/// Same user and context always give the same N, different contexts are unlinkable
+pub fn nullifier<E: Pairing>(
+    pp: &PublicParams<E>,
+    user_id: &E::ScalarField,
+    context: &[u8],
+) -> Result<E::G1Affine, Error> {
+    let exponent = (*user_id + context_scalar::<E>(context))
+        .inverse()
+        .ok_or_else(|| Error::Other("Nullifier undefined for this context".to_string()))?;
+    Ok(pp.g.mul(exponent).into_affine())
+}
+
+/// Proof that a nullifier was derived from the user ID (attribute `id_index`) in a commitment
+/// Shows knowledge of the opening and that user_id * N = g - H(context) * N,
+/// with the user ID's response shared between the two statements
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct NullifierProof<E: Pairing> {
+    pub nullifier: E::G1Affine,
+    pub opening_announcement: E::G1Affine,
+    pub nullifier_announcement: E::G1Affine,
+    pub responses: Vec<E::ScalarField>, // attributes, then r
+}
+
+impl<E: Pairing> NullifierProof<E> {
+    pub fn prove(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        id_index: usize,
+        context: &[u8],
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let user_id = messages
+            .get(id_index)
+            .ok_or_else(|| Error::Other("Messages must include the user ID".to_string()))?;
+        let nullifier = nullifier(pp, user_id, context)?;
+
+        let opening = SchnorrProtocol::commit(pp.get_g1_bases(), rng);
+        let nullifier_announcement = nullifier
+            .mul(opening.random_blindings[id_index])
+            .into_affine();
+        let challenge = Self::challenge(
+            commitment,
+            &nullifier,
+            id_index,
+            context,
+            &opening.commited_blindings,
+            &nullifier_announcement,
+        );
+
+        let mut exponents = messages.to_vec();
+        exponents.push(*r);
+        let responses = SchnorrProtocol::prove(&opening, &exponents, &challenge);
+
+        Ok(Self {
+            nullifier,
+            opening_announcement: opening.commited_blindings,
+            nullifier_announcement,
+            responses: responses.0,
+        })
+    }
+
+    /// Check against the user ID at attribute `id_index`, the verifier's schema slot: a holder
+    /// choosing it could derive a fresh nullifier from another attribute each time
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        id_index: usize,
+        context: &[u8],
+    ) -> bool {
+        if self.responses.len() != pp.n + 1 || id_index >= pp.n || self.nullifier.is_zero() {
+            return false;
+        }
+        let challenge = Self::challenge(
+            commitment,
+            &self.nullifier,
+            id_index,
+            context,
+            &self.opening_announcement,
+            &self.nullifier_announcement,
+        );
+
+        if !SchnorrProtocol::verify_schnorr(
+            pp.get_g1_bases(),
+            &commitment.cm,
+            &self.opening_announcement,
+            &self.responses,
+            &challenge,
+        ) {
+            return false;
+        }
+
+        // user_id * N = g - H(context) * N
+        let statement = pp.g.into_group() - self.nullifier.mul(context_scalar::<E>(context));
+        self.nullifier.mul(self.responses[id_index])
+            == self.nullifier_announcement + statement.mul(challenge)
+    }
+
+    fn challenge(
+        commitment: &Commitment<E>,
+        nullifier: &E::G1Affine,
+        id_index: usize,
+        context: &[u8],
+        opening_announcement: &E::G1Affine,
+        nullifier_announcement: &E::G1Affine,
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-nullifier");
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_serializable(b"nullifier", nullifier);
+        transcript.append_message(b"id_index", &(id_index as u64).to_le_bytes());
+        transcript.append_message(b"context", context);
+        transcript.append_serializable(b"opening_announcement", opening_announcement);
+        transcript.append_serializable(b"nullifier_announcement", nullifier_announcement);
+        transcript.challenge_scalar(b"c")
+    }
+}
+
+/// Spent-nullifier store for one-time-use credentials
+#[derive(Debug, Default)]
+pub struct NullifierSet {
+    spent: HashSet<Vec<u8>>,
+}
+
+impl NullifierSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the nullifier; returns false if it was already spent
+    pub fn check_and_insert<G: CanonicalSerialize>(&mut self, nullifier: &G) -> bool {
+        let mut bytes = Vec::new();
+        nullifier
+            .serialize_compressed(&mut bytes)
+            .expect("serializing into a Vec cannot fail");
+        self.spent.insert(bytes)
+    }
+
+    pub fn len(&self) -> usize {
+        self.spent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spent.is_empty()
+    }
+}
@@ -8,6 +8,7 @@ use ark_std::test_rng;
 // use ark_std::{ops::Mul, rand::Rng,  sync::Mutex, One, UniformRand, Zero};
 use ark_std::{ops::Mul, rand::Rng, sync::Mutex, One, UniformRand, Zero};
 // use itertools::Itertools;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use std::ops::MulAssign;
 
@@ -18,9 +19,9 @@ use std::ops::MulAssign;
 /// exponentiation when verifying if all checks are verified.
 /// It is a tuple:
 /// - a miller loop result that is to be multiplied by other miller loop results
-/// before going into a final exponentiation result
+///   before going into a final exponentiation result
 /// - a right side result which is already in the right subgroup Gt which is to
-/// be compared to the left side when "final_exponentiatiat"-ed
+///   be compared to the left side when "final_exponentiatiat"-ed
 #[derive(Debug, Copy, Clone)]
 pub struct PairingCheck<E: Pairing> {
     left: <E as Pairing>::TargetField,
@@ -30,6 +31,12 @@ pub struct PairingCheck<E: Pairing> {
     non_randomized: u8,
 }
 
+impl<E: Pairing> Default for PairingCheck<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<E> PairingCheck<E>
 where
     E: Pairing,
@@ -104,9 +111,22 @@ where
         it: &[(&'a E::G1Affine, &'a E::G2Affine)],
         out: &'a <E as Pairing>::TargetField,
     ) -> PairingCheck<E> {
-        let coeff = rand_fr::<E, R>(&rng);
-        let miller_out = it
-            .into_par_iter()
+        let coeff = rand_fr::<E, R>(rng);
+        Self::scaled(coeff, it, out)
+    }
+
+    /// Same as `rand` but scaled by a caller-chosen non-zero coefficient,
+    /// e.g. one derived by hashing the equation's inputs
+    pub fn scaled(
+        coeff: E::ScalarField,
+        it: &[(&E::G1Affine, &E::G2Affine)],
+        out: &<E as Pairing>::TargetField,
+    ) -> PairingCheck<E> {
+        #[cfg(feature = "parallel")]
+        let pairs = it.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let pairs = it.iter();
+        let miller_out = pairs
             .map(|(a, b)| {
                 let na = a.mul(coeff).into_affine();
                 (E::G1Prepared::from(na), E::G2Prepared::from(**b))
@@ -114,11 +134,11 @@ where
             .map(|(a, b)| E::miller_loop(a, b))
             .map(|res| res.0)
             .product();
-        let mut outt = out.clone();
+        let mut outt = *out;
         if out != &<E as Pairing>::TargetField::one() {
             // we only need to make this expensive operation is the output is
             // not one since 1^r = 1
-            outt = outt.pow(&(coeff.into_bigint()));
+            outt = outt.pow(coeff.into_bigint());
         }
         PairingCheck {
             left: miller_out,
@@ -151,14 +171,34 @@ where
         }
         E::final_exponentiation(MillerLoopOutput(self.left)) == Some(PairingOutput(self.right))
     }
+
+    /// The value `verify` compares against one, FinalExponentiation(left) / right, for
+    /// diagnosing a failed check; zero if the Miller loop output is degenerate.
+    /// Ignores the count of non-randomized checks.
+    pub fn evaluate(&self) -> <E as Pairing>::TargetField {
+        match (
+            E::final_exponentiation(MillerLoopOutput(self.left)),
+            self.right.inverse(),
+        ) {
+            (Some(PairingOutput(left)), Some(right_inverse)) => left * right_inverse,
+            _ => <E as Pairing>::TargetField::zero(),
+        }
+    }
 }
 
 fn rand_fr<E: Pairing, R: Rng + Send>(r: &Mutex<R>) -> E::ScalarField {
     let rng: &mut R = &mut r.lock().unwrap();
+    nonzero_scalar(|| E::ScalarField::rand(rng))
+}
+
+/// Scalar from `draw`, redrawn in the (negligible) event it comes out zero
+/// For randomization factors and pairing-check weights, where a zero would drop the term;
+/// `draw` samples an rng or squeezes a transcript
+pub(crate) fn nonzero_scalar<F: PrimeField>(mut draw: impl FnMut() -> F) -> F {
     loop {
-        let c = E::ScalarField::rand(rng);
-        if c != E::ScalarField::zero() {
-            return c;
+        let scalar = draw();
+        if !scalar.is_zero() {
+            return scalar;
         }
     }
 }
@@ -168,7 +208,7 @@ fn mul_if_not_one<E: Pairing>(
 ) {
     let one = <E as Pairing>::TargetField::one();
     if left == &one {
-        *left = right.clone();
+        *left = *right;
         return;
     } else if right == &one {
         // nothing to do here
@@ -190,7 +230,7 @@ pub fn verify_pairing_equation<E: Pairing>(
     target: Option<&E::TargetField>,
 ) -> bool {
     let rng = test_rng();
-    let target_value = target.cloned().unwrap_or_else(|| E::TargetField::one());
+    let target_value = target.cloned().unwrap_or_else(E::TargetField::one);
 
     let check = PairingCheck::<E>::rand(&Mutex::new(rng), pairs, &target_value);
 
@@ -205,7 +245,7 @@ pub fn create_check<E: Pairing>(
     target: Option<&E::TargetField>,
 ) -> PairingCheck<E> {
     let rng = test_rng();
-    let target_value = target.cloned().unwrap_or_else(|| E::TargetField::one());
+    let target_value = target.cloned().unwrap_or_else(E::TargetField::one);
 
     PairingCheck::<E>::rand(&Mutex::new(rng), pairs, &target_value)
 }
@@ -0,0 +1,693 @@
+// mimc_abc/src/policy.rs
+// Proofs that a credential satisfies a boolean policy over attribute predicates
+use crate::attributes::decode_u128;
+use crate::commitment::Commitment;
+use crate::credential::Credential;
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::range_proof::{pedersen_bases, RangeProof, RangeProver};
+use crate::schnorr::SchnorrProtocol;
+use crate::signature::{Signature, VerificationKey};
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{UniformRand, Zero};
+use ark_std::ops::Mul;
+use ark_std::rand::Rng;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A boolean policy over integer attributes (see `encode_u128`), e.g.
+///     (age >= 18 AND country in {US, CA}) OR vip == 1
+/// Attributes that don't decode to a u64 satisfy no predicate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Policy {
+    And(Vec<Policy>),
+    Or(Vec<Policy>),
+    Not(Box<Policy>),
+    /// The attribute lies in [min, max]
+    Range {
+        index: usize,
+        min: u64,
+        max: u64,
+    },
+    /// The attribute is one of `values`
+    Membership {
+        index: usize,
+        values: Vec<u64>,
+    },
+    Equals {
+        index: usize,
+        value: u64,
+    },
+    /// The attribute is 1, a set flag
+    Boolean {
+        index: usize,
+    },
+}
+
+/// Proof of a policy: the credential's opening, a Pedersen commitment to every attribute
+/// the policy reads, and the policy tree compiled into range proofs on those commitments.
+/// AND nodes pass their challenge down, OR nodes split it among their children
+/// (CDS composition), so the branches that don't hold are simulated and indistinguishable.
+#[derive(Clone)]
+pub struct PolicyProof<E: Pairing> {
+    pub randomized_signature: Signature<E>,
+    pub randomized_commitment: Commitment<E>,
+    pub schnorr_commitment: E::G1Affine,
+    pub responses: Vec<E::ScalarField>, // attributes, then r
+    // C_i = m_i G + s_i H per attribute the policy reads, in index order
+    pub attribute_commitments: Vec<E::G1Affine>,
+    pub attribute_announcements: Vec<E::G1Affine>,
+    pub attribute_responses: Vec<E::ScalarField>, // for s_i; m_i shares the opening's response
+    pub root: PolicyNode<E>,
+}
+
+/// Proof of one node of the compiled policy, in the shape of its `Clause`
+#[derive(Clone, Debug)]
+pub enum PolicyNode<E: Pairing> {
+    Leaf(LeafProof<E>),
+    And(Vec<PolicyNode<E>>),
+    Or {
+        challenges: Vec<E::ScalarField>, // summing to the node's challenge
+        children: Vec<PolicyNode<E>>,
+    },
+}
+
+/// Range proof on an attribute commitment C = m G + s H under the leaf's challenge
+#[derive(Clone, Debug)]
+pub struct LeafProof<E: Pairing> {
+    pub announcement: E::G1Affine,
+    pub value_response: E::ScalarField,
+    pub blinding_response: E::ScalarField,
+    pub range: RangeProof<E>,
+}
+
+// Negation-free form of a policy: NOT is pushed down to the leaves, which are all ranges
+enum Clause {
+    Range { index: usize, min: u64, max: u64 },
+    And(Vec<Clause>),
+    Or(Vec<Clause>),
+}
+
+// Prover state between the announcements and the challenge
+enum Pending<E: Pairing> {
+    Leaf {
+        index: usize,
+        announcement: E::G1Affine,
+        value_nonce: E::ScalarField,
+        blinding_nonce: E::ScalarField,
+        prover: RangeProver<E>,
+    },
+    Simulated(PolicyNode<E>),
+    And(Vec<Pending<E>>),
+    Or {
+        real: usize,
+        challenges: Vec<E::ScalarField>, // the real child's is set on response
+        children: Vec<Pending<E>>,
+    },
+}
+
+impl Policy {
+    /// The attribute is at least `min`
+    pub fn at_least(index: usize, min: u64) -> Self {
+        Policy::Range {
+            index,
+            min,
+            max: u64::MAX,
+        }
+    }
+
+    /// Prove a signed credential satisfies the policy, revealing nothing else
+    pub fn prove<E: Pairing>(
+        &self,
+        credential: &Credential<E>,
+        pp: &PublicParams<E>,
+        rng: &mut impl Rng,
+    ) -> Result<PolicyProof<E>, Error> {
+        let clause = self.compile(false);
+        let messages = credential.get_messages();
+        let indices = clause.indices();
+        if indices.iter().any(|&index| index >= messages.len()) {
+            return Err(Error::Other(
+                "Policy reads an attribute out of range".to_string(),
+            ));
+        }
+        let values: Vec<Option<u64>> = messages
+            .iter()
+            .map(|m| decode_u128(m).ok().and_then(|v| u64::try_from(v).ok()))
+            .collect();
+        if !clause.holds(&values) {
+            return Err(Error::Other(
+                "Credential doesn't satisfy the policy".to_string(),
+            ));
+        }
+
+        let (g, h) = pedersen_bases(pp);
+        let (randomized_signature, randomized_commitment, r_new) =
+            credential.randomize(pp, &E::ScalarField::rand(rng), &E::ScalarField::rand(rng))?;
+        let opening = SchnorrProtocol::commit(pp.get_g1_bases(), rng);
+
+        let mut commitments = BTreeMap::new();
+        let mut blindings = BTreeMap::new();
+        let mut blinding_nonces = Vec::with_capacity(indices.len());
+        let mut attribute_announcements = Vec::with_capacity(indices.len());
+        for &index in &indices {
+            let blinding = E::ScalarField::rand(rng);
+            let nonce = E::ScalarField::rand(rng);
+            commitments.insert(
+                index,
+                (g.mul(messages[index]) + h.mul(blinding)).into_affine(),
+            );
+            blindings.insert(index, blinding);
+            blinding_nonces.push(nonce);
+            attribute_announcements
+                .push((g.mul(opening.random_blindings[index]) + h.mul(nonce)).into_affine());
+        }
+        let attribute_commitments: Vec<E::G1Affine> = commitments.values().copied().collect();
+
+        let pending = clause.commit(&values, &commitments, pp, rng)?;
+        let mut transcript = transcript(
+            &randomized_signature,
+            &randomized_commitment,
+            &opening.commited_blindings,
+            &attribute_commitments,
+            &attribute_announcements,
+        );
+        pending.absorb(&mut transcript);
+        let challenge: E::ScalarField = transcript.challenge_scalar(b"c");
+
+        let mut exponents = messages.clone();
+        exponents.push(r_new);
+        let attribute_responses = blinding_nonces
+            .iter()
+            .zip(blindings.values())
+            .map(|(nonce, blinding)| *nonce + challenge * blinding)
+            .collect();
+
+        Ok(PolicyProof {
+            randomized_signature,
+            randomized_commitment,
+            schnorr_commitment: opening.commited_blindings,
+            responses: SchnorrProtocol::prove(&opening, &exponents, &challenge).0,
+            attribute_commitments,
+            attribute_announcements,
+            attribute_responses,
+            root: pending.respond(&challenge, messages, &blindings),
+        })
+    }
+
+    /// Verify a proof that a credential signed under `vk` satisfies the policy
+    pub fn verify<E: Pairing>(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        proof: &PolicyProof<E>,
+    ) -> bool {
+        let clause = self.compile(false);
+        let indices = clause.indices();
+        if indices.iter().any(|&index| index >= pp.n)
+            || proof.responses.len() != pp.n + 1
+            || proof.attribute_commitments.len() != indices.len()
+            || proof.attribute_announcements.len() != indices.len()
+            || proof.attribute_responses.len() != indices.len()
+        {
+            return false;
+        }
+
+        let mut transcript = transcript(
+            &proof.randomized_signature,
+            &proof.randomized_commitment,
+            &proof.schnorr_commitment,
+            &proof.attribute_commitments,
+            &proof.attribute_announcements,
+        );
+        proof.root.absorb(&mut transcript);
+        let challenge: E::ScalarField = transcript.challenge_scalar(b"c");
+
+        if !SchnorrProtocol::verify_schnorr(
+            pp.get_g1_bases(),
+            &proof.randomized_commitment.cm,
+            &proof.schnorr_commitment,
+            &proof.responses,
+            &challenge,
+        ) {
+            return false;
+        }
+
+        // Each attribute commitment holds the attribute the opening proves
+        let (g, h) = pedersen_bases(pp);
+        for (k, &index) in indices.iter().enumerate() {
+            if g.mul(proof.responses[index]) + h.mul(proof.attribute_responses[k])
+                != proof.attribute_announcements[k] + proof.attribute_commitments[k].mul(challenge)
+            {
+                return false;
+            }
+        }
+        let commitments: BTreeMap<usize, E::G1Affine> = indices
+            .into_iter()
+            .zip(proof.attribute_commitments.iter().copied())
+            .collect();
+
+        clause.verify(pp, &commitments, &proof.root, &challenge)
+            && !proof.randomized_signature.sigma1.is_zero()
+            && vk.verify_with_pairing_checker(
+                &proof.randomized_signature,
+                &proof.randomized_commitment,
+                pp,
+            )
+    }
+
+    // Push NOT down to the leaves, complementing the ranges it reaches
+    fn compile(&self, negated: bool) -> Clause {
+        let compile_all = |policies: &[Policy]| -> Vec<Clause> {
+            policies.iter().map(|p| p.compile(negated)).collect()
+        };
+        match self {
+            Policy::And(policies) if !negated => Clause::And(compile_all(policies)),
+            Policy::And(policies) => Clause::Or(compile_all(policies)),
+            Policy::Or(policies) if !negated => Clause::Or(compile_all(policies)),
+            Policy::Or(policies) => Clause::And(compile_all(policies)),
+            Policy::Not(policy) => policy.compile(!negated),
+            Policy::Range { index, min, max } => ranges(*index, vec![(*min, *max)], negated),
+            Policy::Membership { index, values } => {
+                ranges(*index, values.iter().map(|&v| (v, v)).collect(), negated)
+            }
+            Policy::Equals { index, value } => ranges(*index, vec![(*value, *value)], negated),
+            Policy::Boolean { index } => ranges(*index, vec![(1, 1)], negated),
+        }
+    }
+}
+
+// The attribute lies in one of the intervals, or in none of them if negated
+fn ranges(index: usize, mut intervals: Vec<(u64, u64)>, negated: bool) -> Clause {
+    if negated {
+        // The gaps between the intervals; empty intervals cover nothing
+        intervals.retain(|(min, max)| min <= max);
+        intervals.sort_unstable();
+        let mut gaps = Vec::new();
+        let mut uncovered = Some(0u64);
+        for (min, max) in intervals {
+            let Some(start) = uncovered else { break };
+            if min > start {
+                gaps.push((start, min - 1));
+            }
+            if max >= start {
+                uncovered = max.checked_add(1);
+            }
+        }
+        if let Some(start) = uncovered {
+            gaps.push((start, u64::MAX));
+        }
+        intervals = gaps;
+    }
+    match intervals.as_slice() {
+        [(min, max)] => Clause::Range {
+            index,
+            min: *min,
+            max: *max,
+        },
+        _ => Clause::Or(
+            intervals
+                .into_iter()
+                .map(|(min, max)| Clause::Range { index, min, max })
+                .collect(),
+        ),
+    }
+}
+
+impl Clause {
+    fn indices(&self) -> BTreeSet<usize> {
+        let mut indices = BTreeSet::new();
+        self.collect_indices(&mut indices);
+        indices
+    }
+
+    fn collect_indices(&self, indices: &mut BTreeSet<usize>) {
+        match self {
+            Clause::Range { index, .. } => {
+                indices.insert(*index);
+            }
+            Clause::And(clauses) | Clause::Or(clauses) => {
+                for clause in clauses {
+                    clause.collect_indices(indices);
+                }
+            }
+        }
+    }
+
+    fn holds(&self, values: &[Option<u64>]) -> bool {
+        match self {
+            Clause::Range { index, min, max } => {
+                values[*index].is_some_and(|v| *min <= v && v <= *max)
+            }
+            Clause::And(clauses) => clauses.iter().all(|clause| clause.holds(values)),
+            Clause::Or(clauses) => clauses.iter().any(|clause| clause.holds(values)),
+        }
+    }
+
+    // Announce for a clause that holds, simulating the OR branches that don't
+    fn commit<E: Pairing>(
+        &self,
+        values: &[Option<u64>],
+        commitments: &BTreeMap<usize, E::G1Affine>,
+        pp: &PublicParams<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Pending<E>, Error> {
+        match self {
+            Clause::Range { index, min, max } => {
+                let (g, h) = pedersen_bases(pp);
+                let value = values[*index]
+                    .ok_or_else(|| Error::Other(format!("Attribute {} isn't an integer", index)))?;
+                let value_nonce = E::ScalarField::rand(rng);
+                let blinding_nonce = E::ScalarField::rand(rng);
+                Ok(Pending::Leaf {
+                    index: *index,
+                    announcement: (g.mul(value_nonce) + h.mul(blinding_nonce)).into_affine(),
+                    value_nonce,
+                    blinding_nonce,
+                    prover: RangeProver::commit(pp, *index, value, *min, *max, &value_nonce, rng)?,
+                })
+            }
+            Clause::And(clauses) => Ok(Pending::And(
+                clauses
+                    .iter()
+                    .map(|clause| clause.commit(values, commitments, pp, rng))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Clause::Or(clauses) => {
+                let real = clauses
+                    .iter()
+                    .position(|clause| clause.holds(values))
+                    .ok_or_else(|| Error::Other("No branch of the policy holds".to_string()))?;
+                let mut challenges = Vec::with_capacity(clauses.len());
+                let mut children = Vec::with_capacity(clauses.len());
+                for (k, clause) in clauses.iter().enumerate() {
+                    if k == real {
+                        challenges.push(E::ScalarField::zero());
+                        children.push(clause.commit(values, commitments, pp, rng)?);
+                    } else {
+                        let challenge = E::ScalarField::rand(rng);
+                        challenges.push(challenge);
+                        children.push(Pending::Simulated(clause.simulate(
+                            &challenge,
+                            commitments,
+                            pp,
+                            rng,
+                        )?));
+                    }
+                }
+                Ok(Pending::Or {
+                    real,
+                    challenges,
+                    children,
+                })
+            }
+        }
+    }
+
+    // A proof accepted for `challenge` whether or not the clause holds
+    fn simulate<E: Pairing>(
+        &self,
+        challenge: &E::ScalarField,
+        commitments: &BTreeMap<usize, E::G1Affine>,
+        pp: &PublicParams<E>,
+        rng: &mut impl Rng,
+    ) -> Result<PolicyNode<E>, Error> {
+        match self {
+            Clause::Range { index, min, max } => {
+                let (g, h) = pedersen_bases(pp);
+                let value_response = E::ScalarField::rand(rng);
+                let blinding_response = E::ScalarField::rand(rng);
+                let announcement = (g.mul(value_response) + h.mul(blinding_response)
+                    - commitments[index].mul(*challenge))
+                .into_affine();
+                Ok(PolicyNode::Leaf(LeafProof {
+                    announcement,
+                    value_response,
+                    blinding_response,
+                    range: RangeProof::simulate(
+                        pp,
+                        *index,
+                        *min,
+                        *max,
+                        challenge,
+                        &value_response,
+                        rng,
+                    )?,
+                }))
+            }
+            Clause::And(clauses) => Ok(PolicyNode::And(
+                clauses
+                    .iter()
+                    .map(|clause| clause.simulate(challenge, commitments, pp, rng))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Clause::Or(clauses) => {
+                let Some(last) = clauses.len().checked_sub(1) else {
+                    return Err(Error::Other(
+                        "Policy has a clause no attribute satisfies".to_string(),
+                    ));
+                };
+                let mut challenges: Vec<E::ScalarField> =
+                    (0..last).map(|_| E::ScalarField::rand(rng)).collect();
+                challenges.push(*challenge - challenges.iter().sum::<E::ScalarField>());
+                let children = clauses
+                    .iter()
+                    .zip(&challenges)
+                    .map(|(clause, c)| clause.simulate(c, commitments, pp, rng))
+                    .collect::<Result<_, _>>()?;
+                Ok(PolicyNode::Or {
+                    challenges,
+                    children,
+                })
+            }
+        }
+    }
+
+    fn verify<E: Pairing>(
+        &self,
+        pp: &PublicParams<E>,
+        commitments: &BTreeMap<usize, E::G1Affine>,
+        node: &PolicyNode<E>,
+        challenge: &E::ScalarField,
+    ) -> bool {
+        match (self, node) {
+            (Clause::Range { index, min, max }, PolicyNode::Leaf(leaf)) => {
+                let (g, h) = pedersen_bases(pp);
+                leaf.range.index == *index
+                    && leaf.range.min == *min
+                    && leaf.range.max == *max
+                    && g.mul(leaf.value_response) + h.mul(leaf.blinding_response)
+                        == leaf.announcement + commitments[index].mul(*challenge)
+                    && leaf.range.verify(pp, challenge, &leaf.value_response)
+            }
+            (Clause::And(clauses), PolicyNode::And(children)) => {
+                clauses.len() == children.len()
+                    && clauses
+                        .iter()
+                        .zip(children)
+                        .all(|(clause, child)| clause.verify(pp, commitments, child, challenge))
+            }
+            (
+                Clause::Or(clauses),
+                PolicyNode::Or {
+                    challenges,
+                    children,
+                },
+            ) => {
+                !clauses.is_empty()
+                    && clauses.len() == children.len()
+                    && challenges.len() == children.len()
+                    && challenges.iter().sum::<E::ScalarField>() == *challenge
+                    && clauses
+                        .iter()
+                        .zip(children)
+                        .zip(challenges)
+                        .all(|((clause, child), c)| clause.verify(pp, commitments, child, c))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<E: Pairing> Pending<E> {
+    fn absorb(&self, transcript: &mut Transcript) {
+        match self {
+            Pending::Leaf {
+                announcement,
+                prover,
+                ..
+            } => {
+                transcript.append_serializable(b"leaf_announcement", announcement);
+                prover.absorb(transcript);
+            }
+            Pending::Simulated(node) => node.absorb(transcript),
+            Pending::And(children) | Pending::Or { children, .. } => {
+                for child in children {
+                    child.absorb(transcript);
+                }
+            }
+        }
+    }
+
+    fn respond(
+        self,
+        challenge: &E::ScalarField,
+        messages: &[E::ScalarField],
+        blindings: &BTreeMap<usize, E::ScalarField>,
+    ) -> PolicyNode<E> {
+        match self {
+            Pending::Leaf {
+                index,
+                announcement,
+                value_nonce,
+                blinding_nonce,
+                prover,
+            } => PolicyNode::Leaf(LeafProof {
+                announcement,
+                value_response: value_nonce + *challenge * messages[index],
+                blinding_response: blinding_nonce + *challenge * blindings[&index],
+                range: prover.respond(challenge),
+            }),
+            Pending::Simulated(node) => node,
+            Pending::And(children) => PolicyNode::And(
+                children
+                    .into_iter()
+                    .map(|child| child.respond(challenge, messages, blindings))
+                    .collect(),
+            ),
+            Pending::Or {
+                real,
+                mut challenges,
+                children,
+            } => {
+                challenges[real] = *challenge - challenges.iter().sum::<E::ScalarField>();
+                let children = children
+                    .into_iter()
+                    .zip(&challenges)
+                    .map(|(child, c)| child.respond(c, messages, blindings))
+                    .collect();
+                PolicyNode::Or {
+                    challenges,
+                    children,
+                }
+            }
+        }
+    }
+}
+
+impl<E: Pairing> PolicyNode<E> {
+    fn absorb(&self, transcript: &mut Transcript) {
+        match self {
+            PolicyNode::Leaf(leaf) => {
+                transcript.append_serializable(b"leaf_announcement", &leaf.announcement);
+                leaf.range.absorb(transcript);
+            }
+            PolicyNode::And(children) | PolicyNode::Or { children, .. } => {
+                for child in children {
+                    child.absorb(transcript);
+                }
+            }
+        }
+    }
+}
+
+fn transcript<E: Pairing>(
+    signature: &Signature<E>,
+    commitment: &Commitment<E>,
+    schnorr_commitment: &E::G1Affine,
+    attribute_commitments: &[E::G1Affine],
+    attribute_announcements: &[E::G1Affine],
+) -> Transcript {
+    let mut transcript = Transcript::new(b"mimc-abc-policy");
+    transcript.append_serializable(b"sigma1", &signature.sigma1);
+    transcript.append_serializable(b"sigma2", &signature.sigma2);
+    transcript.append_serializable(b"cm", &commitment.cm);
+    transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+    transcript.append_serializable(b"schnorr_commitment", schnorr_commitment);
+    transcript.append_serializable(b"attribute_commitments", &attribute_commitments.to_vec());
+    transcript.append_serializable(
+        b"attribute_announcements",
+        &attribute_announcements.to_vec(),
+    );
+    transcript
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::MimcAbc;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+
+    const AGE: usize = 1;
+    const COUNTRY: usize = 2;
+    const VIP: usize = 3;
+    const US: u64 = 840;
+    const CA: u64 = 124;
+    const FR: u64 = 250;
+
+    #[test]
+    fn test_policy_proof() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let mut issue = |age: u64, country: u64, vip: u64| {
+            let messages = vec![
+                Fr::rand(&mut rng),
+                Fr::from(age),
+                Fr::from(country),
+                Fr::from(vip),
+            ];
+            let mut credential =
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+            protocol
+                .obtain_and_issue(&mut credential, &sk, &mut rng)
+                .unwrap();
+            credential
+        };
+        let adult_us = issue(34, US, 0);
+        let minor_vip = issue(16, CA, 1);
+        let adult_fr = issue(40, FR, 0);
+
+        // (age >= 18 AND country in {US, CA}) OR vip == 1
+        let policy = Policy::Or(vec![
+            Policy::And(vec![
+                Policy::at_least(AGE, 18),
+                Policy::Membership {
+                    index: COUNTRY,
+                    values: vec![US, CA],
+                },
+            ]),
+            Policy::Boolean { index: VIP },
+        ]);
+
+        // Satisfied through either branch, without revealing which
+        for credential in [&adult_us, &minor_vip] {
+            let proof = policy.prove(credential, &protocol.pp, &mut rng).unwrap();
+            assert!(policy.verify(&protocol.pp, &vk, &proof));
+        }
+
+        // Failing the country clause and not a VIP: no proof
+        assert!(policy.prove(&adult_fr, &protocol.pp, &mut rng).is_err());
+
+        // NOT complements the clause: adults outside the US
+        let not_us = Policy::And(vec![
+            Policy::at_least(AGE, 18),
+            Policy::Not(Box::new(Policy::Equals {
+                index: COUNTRY,
+                value: US,
+            })),
+        ]);
+        let proof = not_us.prove(&adult_fr, &protocol.pp, &mut rng).unwrap();
+        assert!(not_us.verify(&protocol.pp, &vk, &proof));
+        assert!(not_us.prove(&adult_us, &protocol.pp, &mut rng).is_err());
+
+        // A proof is bound to its policy
+        assert!(!policy.verify(&protocol.pp, &vk, &proof));
+        let stricter = Policy::Boolean { index: VIP };
+        let proof = policy.prove(&adult_us, &protocol.pp, &mut rng).unwrap();
+        assert!(!stricter.verify(&protocol.pp, &vk, &proof));
+    }
+}
@@ -0,0 +1,279 @@
+// mimc_abc/src/predicate.rs
+// Interactive predicate checks where the verifier learns a single accept/reject
+use crate::attributes::decode_u128;
+use crate::commitment::Commitment;
+use crate::credential::Credential;
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::range_proof::{RangeProof, RangeProver};
+use crate::schnorr::SchnorrProtocol;
+use crate::signature::{Signature, VerificationKey};
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::AffineRepr;
+use ark_ff::UniformRand;
+use ark_std::rand::Rng;
+
+/// A hidden attribute lies in [min, max]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Predicate {
+    pub index: usize,
+    pub min: u64,
+    pub max: u64,
+}
+
+impl Predicate {
+    /// attribute >= min, e.g. age >= 18
+    pub fn at_least(index: usize, min: u64) -> Self {
+        Self {
+            index,
+            min,
+            max: u64::MAX,
+        }
+    }
+}
+
+/// Verifier side of a blind predicate check:
+///  1. the verifier sends a fresh `challenge`
+///  2. the holder answers with `BlindResponse::respond`, bound to the challenge
+///  3. `finish` accepts or rejects
+///
+/// Every attribute stays hidden and the holder answers the same way whether or not the
+/// predicate holds: when it doesn't, the range proof is made for a stand-in value, which
+/// fails to link to the attribute. The response has the same shape either way, so the
+/// verifier learns the outcome and nothing else. `finish` consumes the check, so a
+/// challenge can't be answered twice.
+pub struct BlindCheck {
+    predicate: Predicate,
+    challenge: [u8; 32],
+}
+
+/// Holder's answer to a `BlindCheck` challenge
+#[derive(Clone)]
+pub struct BlindResponse<E: Pairing> {
+    pub randomized_signature: Signature<E>,
+    pub randomized_commitment: Commitment<E>,
+    pub schnorr_commitment: E::G1Affine,
+    pub responses: Vec<E::ScalarField>, // attributes, then r
+    pub range_proof: RangeProof<E>,
+}
+
+impl BlindCheck {
+    pub fn new(predicate: Predicate, rng: &mut impl Rng) -> Self {
+        let mut challenge = [0u8; 32];
+        rng.fill_bytes(&mut challenge);
+        Self {
+            predicate,
+            challenge,
+        }
+    }
+
+    pub fn predicate(&self) -> Predicate {
+        self.predicate
+    }
+
+    /// Sent to the holder with the predicate
+    pub fn challenge(&self) -> [u8; 32] {
+        self.challenge
+    }
+
+    /// Accept iff the response answers this challenge and the predicate holds for a
+    /// credential signed under `vk`
+    pub fn finish<E: Pairing>(
+        self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        response: &BlindResponse<E>,
+    ) -> bool {
+        let proof = &response.range_proof;
+        let predicate = self.predicate;
+        if predicate.index >= pp.n
+            || proof.index != predicate.index
+            || proof.min != predicate.min
+            || proof.max != predicate.max
+            || response.responses.len() != pp.n + 1
+        {
+            return false;
+        }
+
+        let challenge: E::ScalarField = transcript(
+            &self.challenge,
+            &response.randomized_signature,
+            &response.randomized_commitment,
+            &response.schnorr_commitment,
+            proof,
+        )
+        .challenge_scalar(b"c");
+
+        SchnorrProtocol::verify_schnorr(
+            pp.get_g1_bases(),
+            &response.randomized_commitment.cm,
+            &response.schnorr_commitment,
+            &response.responses,
+            &challenge,
+        ) && proof.verify(pp, &challenge, &response.responses[predicate.index])
+            && !response.randomized_signature.sigma1.is_zero()
+            && vk.verify_with_pairing_checker(
+                &response.randomized_signature,
+                &response.randomized_commitment,
+                pp,
+            )
+    }
+}
+
+impl<E: Pairing> BlindResponse<E> {
+    /// Answer a blind check with a signed credential
+    /// Only malformed requests error; an unsatisfied predicate yields a response that is rejected
+    pub fn respond(
+        credential: &Credential<E>,
+        pp: &PublicParams<E>,
+        predicate: Predicate,
+        challenge: &[u8; 32],
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let messages = credential.get_messages();
+        if predicate.index >= messages.len() || predicate.min > predicate.max {
+            return Err(Error::Other(format!(
+                "Malformed predicate on attribute {}",
+                predicate.index
+            )));
+        }
+
+        let (randomized_signature, randomized_commitment, r_new) =
+            credential.randomize(pp, &E::ScalarField::rand(rng), &E::ScalarField::rand(rng))?;
+        let mut exponents = messages.clone();
+        exponents.push(r_new);
+        let opening = SchnorrProtocol::commit(pp.get_g1_bases(), rng);
+
+        // A stand-in in range when the predicate fails; it won't link to the attribute
+        let value = match decode_u128(&messages[predicate.index]).map(u64::try_from) {
+            Ok(Ok(v)) if predicate.min <= v && v <= predicate.max => v,
+            _ => predicate.min,
+        };
+        let prover = RangeProver::commit(
+            pp,
+            predicate.index,
+            value,
+            predicate.min,
+            predicate.max,
+            &opening.random_blindings[predicate.index],
+            rng,
+        )?;
+
+        let mut transcript = Transcript::new(b"mimc-abc-blind-check");
+        absorb_statement(
+            &mut transcript,
+            challenge,
+            &randomized_signature,
+            &randomized_commitment,
+            &opening.commited_blindings,
+        );
+        prover.absorb(&mut transcript);
+        let c: E::ScalarField = transcript.challenge_scalar(b"c");
+
+        Ok(Self {
+            randomized_signature,
+            randomized_commitment,
+            schnorr_commitment: opening.commited_blindings,
+            responses: SchnorrProtocol::prove(&opening, &exponents, &c).0,
+            range_proof: prover.respond(&c),
+        })
+    }
+}
+
+fn absorb_statement<E: Pairing>(
+    transcript: &mut Transcript,
+    challenge: &[u8; 32],
+    signature: &Signature<E>,
+    commitment: &Commitment<E>,
+    schnorr_commitment: &E::G1Affine,
+) {
+    transcript.append_message(b"verifier_challenge", challenge);
+    transcript.append_serializable(b"sigma1", &signature.sigma1);
+    transcript.append_serializable(b"sigma2", &signature.sigma2);
+    transcript.append_serializable(b"cm", &commitment.cm);
+    transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+    transcript.append_serializable(b"schnorr_commitment", schnorr_commitment);
+}
+
+fn transcript<E: Pairing>(
+    challenge: &[u8; 32],
+    signature: &Signature<E>,
+    commitment: &Commitment<E>,
+    schnorr_commitment: &E::G1Affine,
+    range_proof: &RangeProof<E>,
+) -> Transcript {
+    let mut transcript = Transcript::new(b"mimc-abc-blind-check");
+    absorb_statement(
+        &mut transcript,
+        challenge,
+        signature,
+        commitment,
+        schnorr_commitment,
+    );
+    range_proof.absorb(&mut transcript);
+    transcript
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::MimcAbc;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_blind_check() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let mut issue = |age: u64| {
+            let mut messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+            messages[2] = Fr::from(age);
+            let r = Fr::rand(&mut rng);
+            let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+            let proof = protocol.obtain(&credential, &mut rng);
+            credential
+                .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+                .unwrap();
+            credential
+        };
+        let adult = issue(34);
+        let minor = issue(16);
+        let predicate = Predicate::at_least(2, 18);
+
+        // Satisfied: accepted
+        let check = BlindCheck::new(predicate, &mut rng);
+        let response = BlindResponse::respond(
+            &adult,
+            &protocol.pp,
+            predicate,
+            &check.challenge(),
+            &mut rng,
+        )
+        .unwrap();
+        assert!(check.finish(&protocol.pp, &vk, &response));
+
+        // A response to another challenge is rejected
+        let replayed = BlindCheck::new(predicate, &mut rng);
+        assert!(!replayed.finish(&protocol.pp, &vk, &response));
+
+        // Unsatisfied: the holder still answers, with a same-shaped response that's rejected
+        let check = BlindCheck::new(predicate, &mut rng);
+        let rejected = BlindResponse::respond(
+            &minor,
+            &protocol.pp,
+            predicate,
+            &check.challenge(),
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(rejected.responses.len(), response.responses.len());
+        assert_eq!(
+            rejected.range_proof.lower.bit_commitments.len(),
+            response.range_proof.lower.bit_commitments.len()
+        );
+        assert!(!check.finish(&protocol.pp, &vk, &rejected));
+    }
+}
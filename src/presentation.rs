@@ -0,0 +1,547 @@
+// mimc_abc/src/presentation.rs
+use crate::attributes::decode_u128;
+use crate::commitment::Commitment;
+use crate::credential::{Credential, ShowCredential};
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::range_proof::{RangeProof, RangeProver};
+use crate::schnorr::SchnorrProtocol;
+use crate::signature::{Signature, VerificationKey};
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::AffineRepr;
+use ark_ff::UniformRand;
+use ark_std::rand::Rng;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Composite presentation: selective disclosure, range proofs and a verifier nonce
+/// All components answer one Fiat-Shamir challenge, so none can be swapped between presentations
+#[derive(Clone)]
+pub struct RichPresentation<E: Pairing> {
+    pub randomized_signature: Signature<E>,
+    pub randomized_commitment: Commitment<E>,
+    pub disclosed: BTreeMap<usize, E::ScalarField>,
+    pub schnorr_commitment: E::G1Affine, // announcement over the hidden bases
+    pub responses: Vec<E::ScalarField>,  // hidden attributes in index order, then r
+    pub range_proofs: Vec<RangeProof<E>>,
+    pub nonce: Option<Vec<u8>>,
+}
+
+/// Assembles a `RichPresentation` from a signed credential
+/// ```ignore
+/// let presentation = PresentationBuilder::new(&credential, &pp)
+///     .disclose(&[1, 3])
+///     .range(2, 18, 120)
+///     .nonce(b"verifier-session")
+///     .build(&mut rng)?;
+/// ```
+pub struct PresentationBuilder<'a, E: Pairing> {
+    credential: &'a Credential<E>,
+    pp: &'a PublicParams<E>,
+    disclosed: Vec<usize>,
+    ranges: Vec<(usize, u64, u64)>,
+    nonce: Option<Vec<u8>>,
+}
+
+impl<'a, E: Pairing> PresentationBuilder<'a, E> {
+    pub fn new(credential: &'a Credential<E>, pp: &'a PublicParams<E>) -> Self {
+        Self {
+            credential,
+            pp,
+            disclosed: Vec::new(),
+            ranges: Vec::new(),
+            nonce: None,
+        }
+    }
+
+    /// Reveal the attributes at these indices
+    pub fn disclose(mut self, indices: &[usize]) -> Self {
+        self.disclosed.extend_from_slice(indices);
+        self
+    }
+
+    /// Prove the hidden attribute at `index` lies in [min, max]
+    pub fn range(mut self, index: usize, min: u64, max: u64) -> Self {
+        self.ranges.push((index, min, max));
+        self
+    }
+
+    /// Bind a verifier-chosen nonce into the challenge
+    pub fn nonce(mut self, nonce: &[u8]) -> Self {
+        self.nonce = Some(nonce.to_vec());
+        self
+    }
+
+    pub fn build(self, rng: &mut impl Rng) -> Result<RichPresentation<E>, Error> {
+        let pp = self.pp;
+        let messages = self.credential.get_messages();
+
+        let disclosed: BTreeMap<usize, E::ScalarField> = self
+            .disclosed
+            .iter()
+            .map(|&i| {
+                messages
+                    .get(i)
+                    .map(|m| (i, *m))
+                    .ok_or_else(|| Error::Other(format!("Disclosed index {} out of range", i)))
+            })
+            .collect::<Result<_, _>>()?;
+        let hidden = hidden_indices(messages.len(), &disclosed);
+
+        let delta_r = E::ScalarField::rand(rng);
+        let delta_u = E::ScalarField::rand(rng);
+        let (randomized_signature, randomized_commitment, r_new) =
+            self.credential.randomize(pp, &delta_r, &delta_u)?;
+
+        // Schnorr announcement over the hidden attributes and r
+        let bases = hidden_bases(pp, &hidden);
+        let mut exponents: Vec<E::ScalarField> = hidden.iter().map(|&i| messages[i]).collect();
+        exponents.push(r_new);
+        let schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
+
+        // Range provers reuse the attribute's blinding so its response is shared
+        let mut range_provers = Vec::with_capacity(self.ranges.len());
+        for &(index, min, max) in &self.ranges {
+            let position = hidden.iter().position(|&i| i == index).ok_or_else(|| {
+                Error::Other(format!(
+                    "Range attribute {} must be hidden and in range",
+                    index
+                ))
+            })?;
+            let value = u64::try_from(decode_u128(&messages[index])?)
+                .map_err(|_| Error::Other(format!("Attribute {} doesn't fit in u64", index)))?;
+            range_provers.push(RangeProver::commit(
+                pp,
+                index,
+                value,
+                min,
+                max,
+                &schnorr_commitment.random_blindings[position],
+                rng,
+            )?);
+        }
+
+        let mut transcript = transcript(
+            &randomized_signature,
+            &randomized_commitment,
+            &disclosed,
+            self.nonce.as_deref(),
+            &schnorr_commitment.commited_blindings,
+        );
+        for prover in &range_provers {
+            prover.absorb(&mut transcript);
+        }
+        let challenge: E::ScalarField = transcript.challenge_scalar(b"c");
+
+        let responses = SchnorrProtocol::prove(&schnorr_commitment, &exponents, &challenge);
+        let range_proofs = range_provers
+            .into_iter()
+            .map(|prover| prover.respond(&challenge))
+            .collect();
+
+        Ok(RichPresentation {
+            randomized_signature,
+            randomized_commitment,
+            disclosed,
+            schnorr_commitment: schnorr_commitment.commited_blindings,
+            responses: responses.0,
+            range_proofs,
+            nonce: self.nonce,
+        })
+    }
+}
+
+impl<E: Pairing> RichPresentation<E> {
+    /// Attribute indices revealed in the clear, ascending
+    pub fn disclosed_indices(&self) -> Vec<usize> {
+        self.disclosed.keys().copied().collect()
+    }
+
+    /// Attribute indices proven in zero knowledge, ascending; excludes the randomness r
+    pub fn hidden_indices(&self) -> Vec<usize> {
+        let n = self.disclosed.len() + self.responses.len().saturating_sub(1);
+        hidden_indices(n, &self.disclosed)
+    }
+
+    /// Check the signature, the opening of the hidden attributes, every range proof and the nonce binding
+    /// Returns the validated disclosed attributes; the caller still compares `nonce` against the one it issued
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+    ) -> Result<BTreeMap<usize, E::ScalarField>, Error> {
+        if let Some(index) = self.disclosed.keys().find(|&&i| i >= pp.n) {
+            return Err(Error::Other(format!(
+                "Disclosed index {} out of range",
+                index
+            )));
+        }
+        let hidden = hidden_indices(pp.n, &self.disclosed);
+        if self.responses.len() != hidden.len() + 1 {
+            return Err(Error::InvalidProof);
+        }
+
+        // Hidden part of the commitment: the proof below only opens it if every
+        // disclosed value is the committed one
+        let statement = self
+            .randomized_commitment
+            .subtract_disclosed(pp, &self.disclosed);
+
+        let mut transcript = transcript(
+            &self.randomized_signature,
+            &self.randomized_commitment,
+            &self.disclosed,
+            self.nonce.as_deref(),
+            &self.schnorr_commitment,
+        );
+        for range_proof in &self.range_proofs {
+            range_proof.absorb(&mut transcript);
+        }
+        let challenge: E::ScalarField = transcript.challenge_scalar(b"c");
+
+        if !SchnorrProtocol::verify_schnorr(
+            &hidden_bases(pp, &hidden),
+            &statement,
+            &self.schnorr_commitment,
+            &self.responses,
+            &challenge,
+        ) {
+            return Err(Error::ProofVerificationFailed);
+        }
+
+        for range_proof in &self.range_proofs {
+            let position = hidden
+                .iter()
+                .position(|&i| i == range_proof.index)
+                .ok_or(Error::InvalidProof)?;
+            if !range_proof.verify(pp, &challenge, &self.responses[position]) {
+                return Err(Error::ProofVerificationFailed);
+            }
+        }
+
+        // A zero sigma1 would satisfy the pairing equation for any commitment
+        if self.randomized_signature.sigma1.is_zero()
+            || !vk.verify_with_pairing_checker(
+                &self.randomized_signature,
+                &self.randomized_commitment,
+                pp,
+            )
+        {
+            return Err(Error::SignatureVerificationFailed);
+        }
+        Ok(self.disclosed.clone())
+    }
+}
+
+/// Attributes a verifier requires revealed, and a fresh nonce for the session
+/// The holder answers with `Credential::respond_to_disclosure_request`
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisclosureRequest {
+    pub indices: Vec<usize>,
+    pub nonce: Vec<u8>,
+}
+
+impl DisclosureRequest {
+    /// Check `show` answers this request: signed under `vk`, bound to the nonce, and revealing
+    /// exactly the requested attributes
+    pub fn verify<E: Pairing>(
+        &self,
+        show: &ShowCredential<E>,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+    ) -> bool {
+        let requested: BTreeSet<usize> = self.indices.iter().copied().collect();
+        if !show.disclosed.keys().copied().eq(requested) || show.presented_at.is_some() {
+            return false;
+        }
+        show.verify_bound_proof_with_context(pp, &self.context())
+            && vk.verify(&show.randomized_signature, &show.randomized_commitment, pp)
+    }
+
+    // Proof context: the requested indices and the nonce
+    pub(crate) fn context(&self) -> Vec<u8> {
+        let mut context = b"mimc-abc-disclosure-request".to_vec();
+        context.extend_from_slice(&(self.indices.len() as u64).to_le_bytes());
+        for index in &self.indices {
+            context.extend_from_slice(&(*index as u64).to_le_bytes());
+        }
+        context.extend_from_slice(&self.nonce);
+        context
+    }
+}
+
+// Indices in [0, n) that aren't disclosed, in ascending order
+fn hidden_indices<F>(n: usize, disclosed: &BTreeMap<usize, F>) -> Vec<usize> {
+    (0..n).filter(|i| !disclosed.contains_key(i)).collect()
+}
+
+// g_i for each hidden index, then g for r
+fn hidden_bases<E: Pairing>(pp: &PublicParams<E>, hidden: &[usize]) -> Vec<E::G1Affine> {
+    hidden
+        .iter()
+        .map(|&i| pp.ck[i])
+        .chain(std::iter::once(pp.g))
+        .collect()
+}
+
+fn transcript<E: Pairing>(
+    signature: &Signature<E>,
+    commitment: &Commitment<E>,
+    disclosed: &BTreeMap<usize, E::ScalarField>,
+    nonce: Option<&[u8]>,
+    schnorr_commitment: &E::G1Affine,
+) -> Transcript {
+    let mut transcript = Transcript::new(b"mimc-abc-rich-presentation");
+    transcript.append_serializable(b"sigma1", &signature.sigma1);
+    transcript.append_serializable(b"sigma2", &signature.sigma2);
+    transcript.append_serializable(b"cm", &commitment.cm);
+    transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+    for (index, value) in disclosed {
+        transcript.append_message(b"disclosed_index", &(*index as u64).to_le_bytes());
+        transcript.append_serializable(b"disclosed_value", value);
+    }
+    if let Some(nonce) = nonce {
+        transcript.append_message(b"nonce", nonce);
+    }
+    transcript.append_serializable(b"schnorr_commitment", schnorr_commitment);
+    transcript
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::CommitmentProof;
+    use crate::protocol::MimcAbc;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_rich_presentation() {
+        let mut rng = test_rng();
+        let n = 5;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        // Attribute 2 is an age
+        let mut messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        messages[2] = Fr::from(42u64);
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+
+        let presentation = PresentationBuilder::new(&credential, &protocol.pp)
+            .disclose(&[1, 3])
+            .range(2, 18, 120)
+            .nonce(b"session-1")
+            .build(&mut rng)
+            .expect("Presentation should build");
+        let disclosed = presentation
+            .verify(&protocol.pp, &vk)
+            .expect("Rich presentation should verify");
+        assert_eq!(disclosed.len(), 2);
+        assert_eq!(disclosed.get(&1), Some(&messages[1]));
+        assert_eq!(disclosed.get(&3), Some(&messages[3]));
+
+        // Disclosed value
+        let mut tampered = presentation.clone();
+        tampered.disclosed.insert(1, Fr::from(7u64));
+        assert!(
+            tampered.verify(&protocol.pp, &vk).is_err(),
+            "Altered disclosure should fail"
+        );
+
+        // Range proof
+        let mut tampered = presentation.clone();
+        tampered.range_proofs[0].max = 40;
+        assert!(
+            tampered.verify(&protocol.pp, &vk).is_err(),
+            "Altered range should fail"
+        );
+        let mut tampered = presentation.clone();
+        tampered.range_proofs[0].lower.link_response += Fr::from(1u64);
+        assert!(
+            tampered.verify(&protocol.pp, &vk).is_err(),
+            "Altered range proof should fail"
+        );
+
+        // Nonce
+        let mut tampered = presentation.clone();
+        tampered.nonce = Some(b"session-2".to_vec());
+        assert!(
+            tampered.verify(&protocol.pp, &vk).is_err(),
+            "Altered nonce should fail"
+        );
+
+        // Hidden-attribute responses
+        let mut tampered = presentation.clone();
+        tampered.responses[0] += Fr::from(1u64);
+        assert!(
+            tampered.verify(&protocol.pp, &vk).is_err(),
+            "Altered response should fail"
+        );
+
+        // Signature
+        let mut tampered = presentation;
+        tampered.randomized_signature.sigma2 = tampered.randomized_signature.sigma1;
+        assert!(
+            tampered.verify(&protocol.pp, &vk).is_err(),
+            "Altered signature should fail"
+        );
+
+        // An out-of-range attribute can't be proven
+        let result = PresentationBuilder::new(&credential, &protocol.pp)
+            .range(2, 50, 120)
+            .build(&mut rng);
+        assert!(
+            result.is_err(),
+            "Out-of-range value shouldn't produce a proof"
+        );
+    }
+
+    #[test]
+    fn test_verify_returns_disclosed_attributes() {
+        let mut rng = test_rng();
+        let n = 5;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+        credential
+            .add_signature(protocol.issue(&proof, &sk, &mut rng).unwrap())
+            .unwrap();
+
+        let presentation = PresentationBuilder::new(&credential, &protocol.pp)
+            .disclose(&[3, 1])
+            .build(&mut rng)
+            .unwrap();
+        let disclosed = presentation.verify(&protocol.pp, &vk).unwrap();
+
+        let expected: BTreeMap<usize, Fr> =
+            [(1, messages[1]), (3, messages[3])].into_iter().collect();
+        assert_eq!(disclosed, expected);
+        assert_eq!(presentation.disclosed_indices(), vec![1, 3]);
+        assert_eq!(presentation.hidden_indices(), vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn test_inconsistent_disclosure_rejected() {
+        let mut rng = test_rng();
+        let n = 5;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+
+        // What's left after subtracting the true values commits to the hidden ones only
+        let disclosed: BTreeMap<usize, Fr> =
+            [(1, messages[1]), (3, messages[3])].into_iter().collect();
+        let mut zeroed = messages.clone();
+        zeroed[1] = Fr::from(0u64);
+        zeroed[3] = Fr::from(0u64);
+        assert_eq!(
+            credential
+                .commitment
+                .subtract_disclosed(&protocol.pp, &disclosed),
+            protocol.ck.commit(&protocol.pp, &zeroed, &r).cm
+        );
+
+        let presentation = PresentationBuilder::new(&credential, &protocol.pp)
+            .disclose(&[1, 3])
+            .build(&mut rng)
+            .unwrap();
+        assert!(presentation.verify(&protocol.pp, &vk).is_ok());
+
+        // The real values, attached to each other's slots
+        let mut swapped = presentation.clone();
+        swapped.disclosed.insert(1, messages[3]);
+        swapped.disclosed.insert(3, messages[1]);
+        assert!(swapped.verify(&protocol.pp, &vk).is_err());
+
+        // Shifting one value and compensating in the other keeps the sum, not the commitment
+        let mut shifted = presentation;
+        let shift = Fr::from(5u64);
+        shifted.disclosed.insert(1, messages[1] + shift);
+        shifted.disclosed.insert(3, messages[3] - shift);
+        assert!(shifted.verify(&protocol.pp, &vk).is_err());
+    }
+
+    #[test]
+    fn test_disclosure_request() {
+        let mut rng = test_rng();
+        let n = 5;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+
+        let request = DisclosureRequest {
+            indices: vec![1, 3],
+            nonce: b"session-1".to_vec(),
+        };
+        let show = credential
+            .respond_to_disclosure_request(&protocol.pp, &request, &mut rng)
+            .unwrap();
+        assert!(request.verify(&show, &protocol.pp, &vk));
+        let expected: BTreeMap<usize, Fr> =
+            [(1, messages[1]), (3, messages[3])].into_iter().collect();
+        assert_eq!(show.disclosed, expected);
+
+        // Replayed in another session
+        let replayed = DisclosureRequest {
+            nonce: b"session-2".to_vec(),
+            ..request.clone()
+        };
+        assert!(!replayed.verify(&show, &protocol.pp, &vk));
+
+        // A lie about a disclosed value
+        let mut tampered = show.clone();
+        tampered.disclosed.insert(1, Fr::from(7u64));
+        assert!(!request.verify(&tampered, &protocol.pp, &vk));
+
+        // A captured signature and commitment next to a proof of the forger's own commitment,
+        // disclosing values of its choice under this request
+        let mut own = messages.clone();
+        own[1] = Fr::from(7u64);
+        let own_r = Fr::rand(&mut rng);
+        let own_commitment = protocol.ck.commit(&protocol.pp, &own, &own_r);
+        let mut forged = show.clone();
+        forged.proof = CommitmentProof::prove_disclosing_with_context(
+            &protocol.pp,
+            &own_commitment,
+            &own,
+            &own_r,
+            None,
+            &request.indices,
+            &request.context(),
+            &mut rng,
+        );
+        forged.disclosed.insert(1, own[1]);
+        assert!(!request.verify(&forged, &protocol.pp, &vk));
+
+        // Fewer attributes than requested
+        let partial = DisclosureRequest {
+            indices: vec![1],
+            ..request.clone()
+        };
+        let show = credential
+            .respond_to_disclosure_request(&protocol.pp, &partial, &mut rng)
+            .unwrap();
+        assert!(partial.verify(&show, &protocol.pp, &vk));
+        assert!(!request.verify(&show, &protocol.pp, &vk));
+        let mut padded = show;
+        padded.disclosed.insert(3, messages[3]);
+        assert!(!request.verify(&padded, &protocol.pp, &vk));
+    }
+}
@@ -0,0 +1,189 @@
+// mimc_abc/src/projection.rs
+use crate::commitment::{Commitment, CommitmentKey};
+use crate::credential::Credential;
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::schnorr::SchnorrProtocol;
+use crate::signature::{Signature, VerificationKey};
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::AffineRepr;
+use ark_ff::UniformRand;
+use ark_std::rand::Rng;
+use std::iter;
+
+/// Proof that a projected commitment holds a subset of a signed credential's attributes
+///
+/// The original credential is shown (randomized signature and commitment) and both commitments
+/// are opened in one Schnorr proof. Slot j of the projection reuses the response of original
+/// attribute `indices[j]`, which forces the two values to be equal. Slot 0 is always the user ID,
+/// copied from the original's `id_index`.
+///
+/// What the link proves: the projected commitment opens to the attributes at `indices` of
+/// some credential signed under `vk`. The projection itself is unsigned, so it's only
+/// meaningful next to its link. It doesn't save presentation size either: the link carries
+/// a show of the original, with a response for each of its n attributes.
+#[derive(Clone)]
+pub struct LinkProof<E: Pairing> {
+    pub indices: Vec<usize>, // original index of each projected slot
+    pub randomized_signature: Signature<E>,
+    pub randomized_commitment: Commitment<E>,
+    pub original_announcement: E::G1Affine,
+    pub projected_announcement: E::G1Affine,
+    pub responses: Vec<E::ScalarField>, // original attributes, then r of the original
+    pub projected_r_response: E::ScalarField,
+}
+
+// Bases for a projection of k attributes: g_1..g_k, g
+fn projected_bases<E: Pairing>(pp: &PublicParams<E>, k: usize) -> Vec<E::G1Affine> {
+    pp.ck[..k].iter().cloned().chain(iter::once(pp.g)).collect()
+}
+
+/// Commitment key for a projection of k attributes, a prefix of the issuer's key
+pub(crate) fn projected_key<E: Pairing>(pp: &PublicParams<E>, k: usize) -> CommitmentKey<E> {
+    CommitmentKey {
+        ck: pp.ck[..k].to_vec(),
+        ck_tilde: pp.ck_tilde[..k].to_vec(),
+    }
+}
+
+impl<E: Pairing> LinkProof<E> {
+    pub fn prove(
+        pp: &PublicParams<E>,
+        original: &Credential<E>,
+        projected: &Credential<E>,
+        indices: &[usize],
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let delta_r = E::ScalarField::rand(rng);
+        let delta_u = E::ScalarField::rand(rng);
+        let (randomized_signature, randomized_commitment, r_new) =
+            original.randomize(pp, &delta_r, &delta_u)?;
+
+        // Projected slots share the blinding of the attribute they copy
+        let original_bases = pp.get_g1_bases();
+        let original_commitment = SchnorrProtocol::commit(original_bases, rng);
+        let mut projected_blindings: Vec<E::ScalarField> = indices
+            .iter()
+            .map(|&i| original_commitment.random_blindings[i])
+            .collect();
+        projected_blindings.push(E::ScalarField::rand(rng));
+        let projected_commitment = SchnorrProtocol::commit_with_prepared_blindings(
+            &projected_bases(pp, indices.len()),
+            &projected_blindings,
+        );
+
+        let challenge = Self::challenge(
+            indices,
+            &randomized_signature,
+            &randomized_commitment,
+            &projected.commitment,
+            &original_commitment.commited_blindings,
+            &projected_commitment.commited_blindings,
+        );
+
+        let mut exponents = original.get_messages().clone();
+        exponents.push(r_new);
+        let responses = SchnorrProtocol::prove(&original_commitment, &exponents, &challenge);
+        let projected_r_response =
+            projected_blindings[indices.len()] + challenge * projected.get_r();
+
+        Ok(Self {
+            indices: indices.to_vec(),
+            randomized_signature,
+            randomized_commitment,
+            original_announcement: original_commitment.commited_blindings,
+            projected_announcement: projected_commitment.commited_blindings,
+            responses: responses.0,
+            projected_r_response,
+        })
+    }
+
+    /// Verify `projected` was derived from a credential signed under `vk` whose schema keeps
+    /// the user ID at attribute `id_index`
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        projected: &Commitment<E>,
+        id_index: usize,
+    ) -> bool {
+        if self.indices.first() != Some(&id_index)
+            || self.indices.len() > pp.n
+            || self.indices.iter().any(|&i| i >= pp.n)
+            || self.responses.len() != pp.n + 1
+        {
+            return false;
+        }
+
+        let challenge = Self::challenge(
+            &self.indices,
+            &self.randomized_signature,
+            &self.randomized_commitment,
+            projected,
+            &self.original_announcement,
+            &self.projected_announcement,
+        );
+
+        if !SchnorrProtocol::verify_schnorr(
+            pp.get_g1_bases(),
+            &self.randomized_commitment.cm,
+            &self.original_announcement,
+            &self.responses,
+            &challenge,
+        ) {
+            return false;
+        }
+
+        let projected_responses: Vec<E::ScalarField> = self
+            .indices
+            .iter()
+            .map(|&i| self.responses[i])
+            .chain(iter::once(self.projected_r_response))
+            .collect();
+        if !SchnorrProtocol::verify_schnorr(
+            &projected_bases(pp, self.indices.len()),
+            &projected.cm,
+            &self.projected_announcement,
+            &projected_responses,
+            &challenge,
+        ) {
+            return false;
+        }
+
+        // The projection's G2 side must match its G1 side
+        if E::pairing(projected.cm, pp.g_tilde) != E::pairing(pp.g, projected.cm_tilde) {
+            return false;
+        }
+
+        !self.randomized_signature.sigma1.is_zero()
+            && vk.verify_with_pairing_checker(
+                &self.randomized_signature,
+                &self.randomized_commitment,
+                pp,
+            )
+    }
+
+    fn challenge(
+        indices: &[usize],
+        signature: &Signature<E>,
+        commitment: &Commitment<E>,
+        projected: &Commitment<E>,
+        original_announcement: &E::G1Affine,
+        projected_announcement: &E::G1Affine,
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-projection-link");
+        for index in indices {
+            transcript.append_message(b"index", &(*index as u64).to_le_bytes());
+        }
+        transcript.append_serializable(b"sigma1", &signature.sigma1);
+        transcript.append_serializable(b"sigma2", &signature.sigma2);
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+        transcript.append_serializable(b"projected_cm", &projected.cm);
+        transcript.append_serializable(b"projected_cm_tilde", &projected.cm_tilde);
+        transcript.append_serializable(b"original_announcement", original_announcement);
+        transcript.append_serializable(b"projected_announcement", projected_announcement);
+        transcript.challenge_scalar(b"c")
+    }
+}
@@ -1,18 +1,35 @@
 // use crate::commitment::Commitment;
 use crate::commitment::Commitment;
-use crate::public_params::PublicParams;
-use crate::schnorr::SchnorrProtocol;
+use crate::error::Error;
+use crate::public_params::{BaseTable, PublicParams};
+use crate::schnorr::{SchnorrCommitment, SchnorrProtocol};
+use crate::serialization::{read_version, write_version};
+use crate::transcript::Transcript;
 use ark_ec::pairing::Pairing;
-use ark_ff::UniformRand;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
 use ark_std::rand::Rng;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone)]
 pub struct CommitmentProof<E: Pairing> {
     pub commitment: Commitment<E>,
     pub schnorr_commitment: E::G1Affine,
     pub bases: Vec<E::G1Affine>,
-    pub challenge: E::ScalarField,
     pub responses: Vec<E::ScalarField>,
+    pub tag: Option<Vec<u8>>, // Cleartext metadata bound into the challenge
+}
+
+/// Blindings and exponents behind a proof, see `prove_with_witness`; test builds only
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct DebugWitness<E: Pairing> {
+    pub blindings: Vec<E::ScalarField>,
+    pub exponents: Vec<E::ScalarField>,
 }
 
 impl<E: Pairing> CommitmentProof<E> {
@@ -22,19 +39,205 @@ impl<E: Pairing> CommitmentProof<E> {
         messages: &[E::ScalarField],
         r: &E::ScalarField,
         rng: &mut impl Rng,
+    ) -> Self {
+        Self::prove_with_tag(pp, commitment, messages, r, None, rng)
+    }
+
+    /// Prove knowledge of the commitment opening, binding an optional cleartext tag into the challenge
+    pub fn prove_with_tag(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        tag: Option<&[u8]>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        Self::prove_with_context(pp, commitment, messages, r, tag, &[], rng)
+    }
+
+    /// Like `prove_with_tag`, also binding presentation context (e.g. a timestamp) into the challenge
+    /// The context isn't stored; the verifier supplies it to `verify_with_context`
+    pub fn prove_with_context(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        tag: Option<&[u8]>,
+        context: &[u8],
+        rng: &mut impl Rng,
+    ) -> Self {
+        Self::prove_padded(pp, commitment, messages, r, tag, context, pp.n, rng)
+    }
+
+    /// Like `prove_with_context`, with the attributes padded by zeros up to `pad_to` over
+    /// `PublicParams::padded_g1_bases`, so credentials with up to `pad_to` attributes give
+    /// proofs of the same size. The zeros are committed to nothing, so `commitment` is unchanged.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_padded(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        tag: Option<&[u8]>,
+        context: &[u8],
+        pad_to: usize,
+        rng: &mut impl Rng,
     ) -> Self {
         // Get bases and exponents for the proof
-        let bases = pp.get_g1_bases();
+        let bases = pp.padded_g1_bases(pad_to);
+        let mut padded = messages.to_vec();
+        padded.resize(bases.len() - 1, E::ScalarField::zero());
+
+        // Generate Schnorr commitment
+        let schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
+
+        Self::respond(
+            bases,
+            schnorr_commitment,
+            commitment,
+            &padded,
+            r,
+            tag,
+            context,
+        )
+    }
+
+    /// Same as `prove_with_tag`, computing the Schnorr commitment from precomputed
+    /// fixed-base tables instead of a fresh MSM; the proof is identical
+    pub fn prove_with_table(
+        table: &BaseTable<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        tag: Option<&[u8]>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let random_blindings: Vec<E::ScalarField> = (0..table.bases.len())
+            .map(|_| E::ScalarField::rand(rng))
+            .collect();
+        let schnorr_commitment = SchnorrCommitment {
+            commited_blindings: table.msm(&random_blindings).into_affine(),
+            random_blindings,
+        };
+        Self::respond(
+            table.bases.clone(),
+            schnorr_commitment,
+            commitment,
+            messages,
+            r,
+            tag,
+            &[],
+        )
+    }
+
+    /// Prove the opening while disclosing the attributes at `disclosed`: their blindings
+    /// are zero, so their responses are c * m_i and the issuer can read them off, see
+    /// `verify_disclosed`. The remaining attributes and r stay hidden.
+    pub fn prove_disclosing(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        tag: Option<&[u8]>,
+        disclosed: &[usize],
+        rng: &mut impl Rng,
+    ) -> Self {
+        Self::prove_disclosing_with_context(pp, commitment, messages, r, tag, disclosed, &[], rng)
+    }
 
+    /// Like `prove_disclosing`, also binding presentation context into the challenge
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_disclosing_with_context(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        tag: Option<&[u8]>,
+        disclosed: &[usize],
+        context: &[u8],
+        rng: &mut impl Rng,
+    ) -> Self {
+        let bases = pp.get_g1_bases().to_vec();
+        let random_blindings: Vec<E::ScalarField> = (0..bases.len())
+            .map(|i| {
+                if disclosed.contains(&i) && i < messages.len() {
+                    E::ScalarField::zero()
+                } else {
+                    E::ScalarField::rand(rng)
+                }
+            })
+            .collect();
+        let schnorr_commitment =
+            SchnorrProtocol::commit_with_prepared_blindings(&bases, &random_blindings);
+        Self::respond(
+            bases,
+            schnorr_commitment,
+            commitment,
+            messages,
+            r,
+            tag,
+            context,
+        )
+    }
+
+    /// Verify the proof and that it opens attribute i to `disclosed[i]`
+    pub fn verify_disclosed(&self, disclosed: &BTreeMap<usize, E::ScalarField>) -> bool {
+        self.verify() && self.opens_disclosed(&self.bases, &[], disclosed)
+    }
+
+    /// `verify_disclosed` against pp's bases, for a proof made with a context
+    pub fn verify_disclosed_against_with_context(
+        &self,
+        pp: &PublicParams<E>,
+        disclosed: &BTreeMap<usize, E::ScalarField>,
+        context: &[u8],
+    ) -> bool {
+        self.verify_against_with_context(pp, context)
+            && disclosed.keys().all(|&i| i < pp.n)
+            && self.opens_disclosed(
+                &pp.padded_g1_bases(self.bases.len() - 1),
+                context,
+                disclosed,
+            )
+    }
+
+    // A disclosed attribute's blinding is zero, so its response is c * m
+    fn opens_disclosed(
+        &self,
+        bases: &[E::G1Affine],
+        context: &[u8],
+        disclosed: &BTreeMap<usize, E::ScalarField>,
+    ) -> bool {
+        // The last response is r's, which can't be disclosed
+        let attribute_count = self.responses.len().saturating_sub(1);
+        let challenge = self.challenge_for(bases, context);
+        disclosed
+            .iter()
+            .all(|(&i, m)| i < attribute_count && self.responses[i] == challenge * m)
+    }
+
+    // Challenge and responses for a Schnorr commitment over `bases`
+    fn respond(
+        bases: Vec<E::G1Affine>,
+        schnorr_commitment: SchnorrCommitment<E::G1Affine>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        tag: Option<&[u8]>,
+        context: &[u8],
+    ) -> Self {
         // Create a new vector with copies of messages and add r at the end
         let mut exponents = messages.to_vec();
         exponents.push(*r);
 
-        // Generate Schnorr commitment
-        let schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
-
-        // Generate challenge
-        let challenge = E::ScalarField::rand(rng);
+        // Generate challenge from the statement, announcement and tag
+        let challenge = Self::challenge(
+            &bases,
+            commitment,
+            &schnorr_commitment.commited_blindings,
+            tag,
+            context,
+        );
 
         // Generate responses - use exponents which includes r, not just messages
         let responses = SchnorrProtocol::prove(&schnorr_commitment, &exponents, &challenge);
@@ -44,23 +247,605 @@ impl<E: Pairing> CommitmentProof<E> {
             commitment: commitment.clone(),
             schnorr_commitment: schnorr_commitment.commited_blindings,
             bases,
-            challenge,
             responses: responses.0,
+            tag: tag.map(|t| t.to_vec()),
         };
 
         proof
     }
 
     pub fn verify(&self) -> bool {
-        // Verify using Schnorr protocol
-        let is_valid = SchnorrProtocol::verify_schnorr(
+        self.verify_with_context(&[])
+    }
+
+    /// Exponent positions the proof binds: the attributes, then n for the randomness r
+    pub fn covered_indices(&self) -> Vec<usize> {
+        (0..self.bases.len()).collect()
+    }
+
+    /// Verify a proof made with `prove_with_context`
+    pub fn verify_with_context(&self, context: &[u8]) -> bool {
+        self.verify_with_bases(&self.bases, context)
+    }
+
+    /// Verify against the verifier's own bases from `pp`, ignoring the ones carried in the
+    /// proof, which the prover picks. Proofs padded with `prove_padded` are accepted.
+    pub fn verify_against(&self, pp: &PublicParams<E>) -> bool {
+        self.verify_against_with_context(pp, &[])
+    }
+
+    /// `verify_against` for a proof made with a context
+    pub fn verify_against_with_context(&self, pp: &PublicParams<E>, context: &[u8]) -> bool {
+        if self.bases.len() < pp.n + 1 {
+            return false;
+        }
+        self.verify_with_bases(&pp.padded_g1_bases(self.bases.len() - 1), context)
+    }
+
+    /// The commitment the responses open over pp's bases, (sum_i z_i * B_i - T) / c, with
+    /// c * m_i in place of z_i for every disclosed attribute. It's `commitment.cm` exactly
+    /// when the proof verifies under `context` and opens the disclosed values.
+    pub fn recompute_commitment(
+        &self,
+        pp: &PublicParams<E>,
+        disclosed: &BTreeMap<usize, E::ScalarField>,
+        context: &[u8],
+    ) -> E::G1Affine {
+        if self.bases.len() < pp.n + 1 || self.responses.len() != self.bases.len() {
+            return E::G1Affine::zero();
+        }
+        let bases = pp.padded_g1_bases(self.bases.len() - 1);
+        let challenge = self.challenge_for(&bases, context);
+        let mut responses = self.responses.clone();
+        for (&i, m) in disclosed.range(..pp.n) {
+            responses[i] = challenge * m;
+        }
+        let Some(inverse) = challenge.inverse() else {
+            return E::G1Affine::zero();
+        };
+        ((E::G1::msm_unchecked(&bases, &responses) - self.schnorr_commitment) * inverse)
+            .into_affine()
+    }
+
+    fn verify_with_bases(&self, bases: &[E::G1Affine], context: &[u8]) -> bool {
+        // The challenge is derived, never taken from the prover, so a modified tag,
+        // context or statement gives another challenge and the responses stop matching
+        let challenge = self.challenge_for(bases, context);
+        SchnorrProtocol::verify_schnorr(
+            bases,
+            &self.commitment.cm,
+            &self.schnorr_commitment,
+            &self.responses,
+            &challenge,
+        )
+    }
+
+    // Fiat-Shamir challenge of this proof over `bases`
+    fn challenge_for(&self, bases: &[E::G1Affine], context: &[u8]) -> E::ScalarField {
+        Self::challenge(
+            bases,
+            &self.commitment,
+            &self.schnorr_commitment,
+            self.tag.as_deref(),
+            context,
+        )
+    }
+
+    /// Verify many proofs at once with a random linear combination of their Schnorr relations
+    ///     sum_j w_j * (sum_i z_ji * B_ji - T_j - c_j * cm_j) == 0
+    /// evaluated as one multi-scalar multiplication. Weights are hashed from every proof,
+    /// so a failure anywhere rejects the whole batch.
+    pub fn batch_verify(proofs: &[&CommitmentProof<E>]) -> bool {
+        Self::batch_verify_over(proofs, |proof| Some(proof.bases.clone()))
+    }
+
+    /// `batch_verify` against the verifier's own bases from `pp`, like `verify_against`
+    pub fn batch_verify_against(pp: &PublicParams<E>, proofs: &[&CommitmentProof<E>]) -> bool {
+        Self::batch_verify_over(proofs, |proof| {
+            (proof.bases.len() > pp.n).then(|| pp.padded_g1_bases(proof.bases.len() - 1))
+        })
+    }
+
+    // `batch_verify` with each proof checked over `bases_of(proof)`, None rejecting the batch
+    fn batch_verify_over(
+        proofs: &[&CommitmentProof<E>],
+        bases_of: impl Fn(&CommitmentProof<E>) -> Option<Vec<E::G1Affine>>,
+    ) -> bool {
+        let mut transcript = Transcript::new(b"mimc-abc-commitment-proof-batch");
+        let mut all_bases = Vec::with_capacity(proofs.len());
+        for proof in proofs {
+            match bases_of(proof) {
+                Some(bases) if bases.len() == proof.responses.len() => all_bases.push(bases),
+                _ => return false,
+            }
+            transcript.append_serializable(b"cm", &proof.commitment.cm);
+            transcript.append_serializable(b"schnorr_commitment", &proof.schnorr_commitment);
+            transcript.append_serializable(b"responses", &proof.responses);
+        }
+
+        let mut points = Vec::new();
+        let mut scalars = Vec::new();
+        for (proof, bases) in proofs.iter().zip(&all_bases) {
+            let weight: E::ScalarField = transcript.challenge_scalar(b"weight");
+            let challenge = proof.challenge_for(bases, &[]);
+            points.extend_from_slice(bases);
+            scalars.extend(proof.responses.iter().map(|z| weight * z));
+            points.push(proof.schnorr_commitment);
+            scalars.push(-weight);
+            points.push(proof.commitment.cm);
+            scalars.push(-(weight * challenge));
+        }
+        E::G1::msm_unchecked(&points, &scalars).is_zero()
+    }
+
+    /// `prove`, also handing back the prover's secrets for `debug_check`
+    #[cfg(test)]
+    pub fn prove_with_witness(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> (Self, DebugWitness<E>) {
+        let bases = pp.get_g1_bases().to_vec();
+        let schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
+        let mut exponents = messages.to_vec();
+        exponents.push(*r);
+        let witness = DebugWitness {
+            blindings: schnorr_commitment.random_blindings.clone(),
+            exponents,
+        };
+        let proof = Self::respond(
+            bases,
+            schnorr_commitment,
+            commitment,
+            messages,
+            r,
+            None,
+            &[],
+        );
+        (proof, witness)
+    }
+
+    /// Debugging aid: recompute every piece of the proof and report the first inconsistency
+    #[cfg(test)]
+    pub fn debug_check(&self, witness: &DebugWitness<E>) -> Result<(), String> {
+        let challenge = self.challenge_for(&self.bases, &[]);
+        if self.bases.len() != self.responses.len() {
+            return Err(format!(
+                "{} bases but {} responses",
+                self.bases.len(),
+                self.responses.len()
+            ));
+        }
+
+        // Check each response z_i = rho_i + c * w_i against the prover's witness
+        for (i, response) in self.responses.iter().enumerate() {
+            let expected = witness.blindings[i] + challenge * witness.exponents[i];
+            if *response != expected {
+                return Err(format!(
+                    "response at index {} is inconsistent with base {}",
+                    i, i
+                ));
+            }
+        }
+
+        if !SchnorrProtocol::verify_schnorr(
             &self.bases,
             &self.commitment.cm,
             &self.schnorr_commitment,
             &self.responses,
-            &self.challenge,
+            &challenge,
+        ) {
+            return Err("aggregate Schnorr relation doesn't hold".to_string());
+        }
+        Ok(())
+    }
+
+    fn challenge(
+        bases: &[E::G1Affine],
+        commitment: &Commitment<E>,
+        schnorr_commitment: &E::G1Affine,
+        tag: Option<&[u8]>,
+        context: &[u8],
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-commitment-proof");
+        transcript.append_serializable(b"bases", bases);
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_serializable(b"schnorr_commitment", schnorr_commitment);
+        if let Some(tag) = tag {
+            transcript.append_message(b"tag", tag);
+        }
+        if !context.is_empty() {
+            transcript.append_message(b"context", context);
+        }
+        transcript.challenge_scalar(b"c")
+    }
+}
+
+// Format version of the serialized proof
+const COMMITMENT_PROOF_VERSION: u8 = 2;
+
+// The test-only debug witness isn't part of the encoding, nor is the challenge: the
+// verifier derives it from the statement, announcement, tag and context
+impl<E: Pairing> CanonicalSerialize for CommitmentProof<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        write_version(&mut writer, COMMITMENT_PROOF_VERSION)?;
+        self.commitment.serialize_with_mode(&mut writer, compress)?;
+        self.schnorr_commitment
+            .serialize_with_mode(&mut writer, compress)?;
+        self.bases.serialize_with_mode(&mut writer, compress)?;
+        self.responses.serialize_with_mode(&mut writer, compress)?;
+        self.tag.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + self.commitment.serialized_size(compress)
+            + self.schnorr_commitment.serialized_size(compress)
+            + self.bases.serialized_size(compress)
+            + self.responses.serialized_size(compress)
+            + self.tag.serialized_size(compress)
+    }
+}
+
+impl<E: Pairing> Valid for CommitmentProof<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.commitment.check()?;
+        self.schnorr_commitment.check()?;
+        self.bases.check()
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for CommitmentProof<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        read_version(&mut reader, COMMITMENT_PROOF_VERSION)?;
+        Ok(Self {
+            commitment: Commitment::deserialize_with_mode(&mut reader, compress, validate)?,
+            schnorr_commitment: E::G1Affine::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            bases: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            responses: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            tag: Option::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+/// `CommitmentProof` without its bases, which are pp's (padded) bases and known to the
+/// verifier: n + 1 fewer group elements on the wire. `expand` rebuilds them from pp.
+#[derive(Debug, Clone)]
+pub struct CompactCommitmentProof<E: Pairing> {
+    pub commitment: Commitment<E>,
+    pub schnorr_commitment: E::G1Affine,
+    pub responses: Vec<E::ScalarField>,
+    pub tag: Option<Vec<u8>>,
+}
+
+impl<E: Pairing> CommitmentProof<E> {
+    /// Drop the bases, see `CompactCommitmentProof`
+    pub fn compact(&self) -> CompactCommitmentProof<E> {
+        CompactCommitmentProof {
+            commitment: self.commitment.clone(),
+            schnorr_commitment: self.schnorr_commitment,
+            responses: self.responses.clone(),
+            tag: self.tag.clone(),
+        }
+    }
+}
+
+impl<E: Pairing> CompactCommitmentProof<E> {
+    /// The full proof over pp's bases, padded to the number of responses
+    /// A proof made over other bases expands to one that fails to verify
+    pub fn expand(&self, pp: &PublicParams<E>) -> Result<CommitmentProof<E>, Error> {
+        if self.responses.len() < pp.n + 1 {
+            return Err(Error::Other(format!(
+                "Expected at least {} responses, got {}",
+                pp.n + 1,
+                self.responses.len()
+            )));
+        }
+        Ok(CommitmentProof {
+            commitment: self.commitment.clone(),
+            schnorr_commitment: self.schnorr_commitment,
+            bases: pp.padded_g1_bases(self.responses.len() - 1),
+            responses: self.responses.clone(),
+            tag: self.tag.clone(),
+        })
+    }
+}
+
+const COMPACT_COMMITMENT_PROOF_VERSION: u8 = 1;
+
+impl<E: Pairing> CanonicalSerialize for CompactCommitmentProof<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        write_version(&mut writer, COMPACT_COMMITMENT_PROOF_VERSION)?;
+        self.commitment.serialize_with_mode(&mut writer, compress)?;
+        self.schnorr_commitment
+            .serialize_with_mode(&mut writer, compress)?;
+        self.responses.serialize_with_mode(&mut writer, compress)?;
+        self.tag.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + self.commitment.serialized_size(compress)
+            + self.schnorr_commitment.serialized_size(compress)
+            + self.responses.serialized_size(compress)
+            + self.tag.serialized_size(compress)
+    }
+}
+
+impl<E: Pairing> Valid for CompactCommitmentProof<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.commitment.check()?;
+        self.schnorr_commitment.check()
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for CompactCommitmentProof<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        read_version(&mut reader, COMPACT_COMMITMENT_PROOF_VERSION)?;
+        Ok(Self {
+            commitment: Commitment::deserialize_with_mode(&mut reader, compress, validate)?,
+            schnorr_commitment: E::G1Affine::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            responses: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            tag: Option::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commitment::CommitmentKey;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::ops::Mul;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_debug_check_names_bad_index() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let commitment = ck.commit(&pp, &messages, &r);
+
+        let (proof, witness) =
+            CommitmentProof::prove_with_witness(&pp, &commitment, &messages, &r, &mut rng);
+        assert_eq!(proof.debug_check(&witness), Ok(()));
+        assert_eq!(proof.covered_indices(), (0..=n).collect::<Vec<_>>());
+
+        // Corrupt one response
+        let mut corrupted = proof.clone();
+        corrupted.responses[2] += Fr::from(1u64);
+        assert!(!corrupted.verify());
+        let message = corrupted.debug_check(&witness).unwrap_err();
+        assert!(
+            message.contains("index 2"),
+            "Debug message should name the bad index: {}",
+            message
+        );
+
+        // Corrupt the tag, which moves the challenge under every response
+        let mut corrupted = proof;
+        corrupted.tag = Some(b"other".to_vec());
+        assert!(!corrupted.verify());
+        let message = corrupted.debug_check(&witness).unwrap_err();
+        assert!(message.contains("index 0"), "{}", message);
+    }
+
+    #[test]
+    fn test_prove_with_table_matches_msm() {
+        let mut rng = test_rng();
+        let n = 5;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let table = pp.schnorr_base_table();
+
+        for _ in 0..3 {
+            let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+            let r = Fr::rand(&mut rng);
+            let commitment = ck.commit(&pp, &messages, &r);
+
+            // Same randomness in, same proof out
+            let mut rng_msm = test_rng();
+            let mut rng_table = test_rng();
+            let plain = CommitmentProof::prove_with_tag(
+                &pp,
+                &commitment,
+                &messages,
+                &r,
+                Some(b"t"),
+                &mut rng_msm,
+            );
+            let tabled = CommitmentProof::prove_with_table(
+                &table,
+                &commitment,
+                &messages,
+                &r,
+                Some(b"t"),
+                &mut rng_table,
+            );
+            assert!(plain.verify());
+            assert!(tabled.verify());
+            assert_eq!(plain.schnorr_commitment, tabled.schnorr_commitment);
+            assert_eq!(
+                plain.challenge_for(&plain.bases, &[]),
+                tabled.challenge_for(&tabled.bases, &[])
+            );
+            assert_eq!(plain.responses, tabled.responses);
+        }
+    }
+
+    #[test]
+    fn test_verify_against_rejects_prover_bases() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let commitment = ck.commit(&pp, &messages, &r);
+
+        let honest = CommitmentProof::prove(&pp, &commitment, &messages, &r, &mut rng);
+        assert!(honest.verify_against(&pp));
+        let padded =
+            CommitmentProof::prove_padded(&pp, &commitment, &messages, &r, None, &[], 8, &mut rng);
+        assert!(padded.verify_against(&pp));
+
+        // A point the prover can't open under pp, "opened" over bases of its choosing
+        let bases = vec![pp.g; n + 1];
+        let exponents: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let forged_r = Fr::rand(&mut rng);
+        let forged_commitment = Commitment {
+            cm: pp
+                .g
+                .mul(exponents.iter().sum::<Fr>() + forged_r)
+                .into_affine(),
+            cm_tilde: commitment.cm_tilde,
+        };
+        let schnorr_commitment = SchnorrProtocol::commit(&bases, &mut rng);
+        let forged = CommitmentProof::respond(
+            bases,
+            schnorr_commitment,
+            &forged_commitment,
+            &exponents,
+            &forged_r,
+            None,
+            &[],
+        );
+        assert!(forged.verify());
+        assert!(!forged.verify_against(&pp));
+
+        // Likewise in a batch next to honest proofs
+        assert!(CommitmentProof::batch_verify(&[&honest, &forged]));
+        assert!(CommitmentProof::batch_verify_against(
+            &pp,
+            &[&honest, &padded]
+        ));
+        assert!(!CommitmentProof::batch_verify_against(
+            &pp,
+            &[&honest, &forged]
+        ));
+    }
+
+    #[test]
+    fn test_compact_proof() {
+        use crate::serialization::{from_bytes, to_bytes};
+        let mut rng = test_rng();
+        let n = 32;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let commitment = ck.commit(&pp, &messages, &r);
+        let proof = CommitmentProof::prove_with_tag(
+            &pp,
+            &commitment,
+            &messages,
+            &r,
+            Some(b"passport"),
+            &mut rng,
+        );
+
+        // n + 1 compressed G1 points and their length prefix smaller
+        let compact = proof.compact();
+        let full_size = to_bytes(&proof).len();
+        let compact_bytes = to_bytes(&compact);
+        assert_eq!(full_size - compact_bytes.len(), 8 + (n + 1) * 48);
+
+        let decoded: CompactCommitmentProof<Bls12_381> = from_bytes(&compact_bytes).unwrap();
+        let expanded = decoded.expand(&pp).unwrap();
+        assert_eq!(to_bytes(&expanded), to_bytes(&proof));
+        assert!(expanded.verify() && expanded.verify_against(&pp));
+
+        // Padded proofs expand to the same padding
+        let padded =
+            CommitmentProof::prove_padded(&pp, &commitment, &messages, &r, None, &[], 40, &mut rng);
+        assert!(padded.compact().expand(&pp).unwrap().verify_against(&pp));
+
+        // Under other parameters the expanded proof fails
+        let other = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        assert!(!compact.expand(&other).unwrap().verify());
+        let small = PublicParams::<Bls12_381>::new(&(n + 1), &mut rng);
+        assert!(compact.expand(&small).is_err());
+    }
+
+    #[test]
+    fn test_challenge_is_derived() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let commitment = ck.commit(&pp, &messages, &r);
+        let proof = CommitmentProof::prove_with_context(
+            &pp,
+            &commitment,
+            &messages,
+            &r,
+            None,
+            b"session",
+            &mut rng,
+        );
+
+        // The encoding carries no challenge, the verifier rederives it with its context
+        let bytes = crate::serialization::to_bytes(&proof);
+        assert_eq!(
+            bytes.len(),
+            1 + proof.commitment.compressed_size()
+                + proof.schnorr_commitment.compressed_size()
+                + proof.bases.compressed_size()
+                + proof.responses.compressed_size()
+                + proof.tag.compressed_size()
         );
+        let decoded: CommitmentProof<Bls12_381> = crate::serialization::from_bytes(&bytes).unwrap();
+        assert!(decoded.verify_with_context(b"session"));
+        assert!(decoded.verify_against_with_context(&pp, b"session"));
+        assert!(!decoded.verify_with_context(b"other session"));
 
-        is_valid
+        // Responses for one statement don't carry over to another
+        let mut moved = decoded;
+        moved.commitment = ck.commit(&pp, &messages, &Fr::rand(&mut rng));
+        assert!(!moved.verify_with_context(b"session"));
     }
 }
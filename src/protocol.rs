@@ -1,37 +1,48 @@
 use crate::commitment::CommitmentKey;
+use crate::credential::Credential;
 use crate::credential::ShowCredential;
-use crate::credential::{self, Credential};
 use crate::error::Error;
 use crate::proof::CommitmentProof;
 use crate::public_params::PublicParams;
+use crate::reissue::{valid_changes, UnchangedProof};
 use crate::signature::{generate_keys, SecretKey, Signature, VerificationKey};
 use crate::verkey::{VerKey, VerKeyProof};
 use ark_ec::pairing::Pairing;
-use ark_ff::UniformRand;
+use ark_ec::CurveGroup;
+use ark_ff::Zero;
+use ark_std::ops::Mul;
 use ark_std::rand::Rng;
+use std::collections::BTreeMap;
+use std::sync::mpsc::{Receiver, Sender};
 // We can speedup multi credential verification by batching the signature pairings into a pairing checker.
 // Then implement the schnorr efficiency improvement from the threshold variant I made
 
+// Most times one value may appear among the attributes disclosed to `issue_reject_trivial`
+pub const MAX_REPEATED_ATTRIBUTES: usize = 3;
+
 // main protocol struct
+#[derive(Clone)]
 pub struct MimcAbc<E: Pairing> {
     pub pp: PublicParams<E>,
     pub ck: CommitmentKey<E>,
 }
 
 impl<E: Pairing> MimcAbc<E> {
-    // Initialize with existing parameters
-    pub fn new(pp: PublicParams<E>) -> Self {
+    // Initialize with existing parameters, rejecting malformed ones and those that can't
+    // sign, such as `PublicParams::new_nothing_up_my_sleeve`
+    pub fn new(pp: PublicParams<E>) -> Result<Self, Error> {
+        pp.validate_for_issuance()?;
         let ck = CommitmentKey {
             ck: pp.ck.clone(),
             ck_tilde: pp.ck_tilde.clone(),
         };
-        Self { pp, ck }
+        Ok(Self { pp, ck })
     }
 
     // Generate fresh parameters and keys
     pub fn setup(n: usize, rng: &mut impl Rng) -> (Self, SecretKey<E>, VerificationKey<E>) {
         let pp = PublicParams::<E>::new(&n, rng);
-        let protocol = Self::new(pp);
+        let protocol = Self::new(pp).expect("freshly sampled parameters are valid");
         let (sk, vk) = generate_keys(&protocol.pp, rng);
         (protocol, sk, vk)
     }
@@ -47,16 +58,178 @@ impl<E: Pairing> MimcAbc<E> {
         sk: &SecretKey<E>,
         rng: &mut impl Rng,
     ) -> Result<Signature<E>, Error> {
-        if !proof.verify() {
+        // Over the issuer's own bases, never the ones the prover sent
+        if !proof.verify_against(&self.pp) {
+            return Err(Error::InvalidProof);
+        }
+        // The proof only covers cm, a cm_tilde that doesn't match would fail every show
+        if !proof.commitment.verify_consistency(&self.pp) {
+            return Err(Error::InvalidCommitment);
+        }
+        Ok(sk.sign(&proof.commitment, &self.pp, rng))
+    }
+
+    // Issuer worker loop: verify and sign each request from `rx`, sending the outcome to `tx`
+    // in request order. A bad proof yields an Err for that request and the loop moves on.
+    // Returns once `rx` is closed or nobody is listening on `tx`.
+    pub fn issue_stream(
+        &self,
+        sk: &SecretKey<E>,
+        rx: Receiver<CommitmentProof<E>>,
+        tx: Sender<Result<Signature<E>, Error>>,
+        rng: &mut impl Rng,
+    ) {
+        for proof in rx {
+            if tx.send(self.issue(&proof, sk, rng)).is_err() {
+                return;
+            }
+        }
+    }
+
+    // Prove, issue and attach the signature in one go, for a holder and issuer in one process
+    pub fn obtain_and_issue(
+        &self,
+        credential: &mut Credential<E>,
+        sk: &SecretKey<E>,
+        rng: &mut impl Rng,
+    ) -> Result<(), Error> {
+        let proof = self.obtain(credential, rng);
+        let signature = self.issue(&proof, sk, rng)?;
+        credential.add_signature(signature)?;
+        Ok(())
+    }
+
+    // Issue against a proof that discloses some attributes, refusing degenerate requests:
+    // every disclosed attribute besides the user ID (slot `id_index`) being zero, or one value
+    // repeated more than MAX_REPEATED_ATTRIBUTES times. Hidden attributes can't be inspected.
+    pub fn issue_reject_trivial(
+        &self,
+        proof: &CommitmentProof<E>,
+        disclosed: &BTreeMap<usize, E::ScalarField>,
+        id_index: usize,
+        sk: &SecretKey<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Signature<E>, Error> {
+        if !proof.verify_disclosed_against_with_context(&self.pp, disclosed, &[]) {
             return Err(Error::InvalidProof);
         }
+        if !proof.commitment.verify_consistency(&self.pp) {
+            return Err(Error::InvalidCommitment);
+        }
+        let attributes: Vec<&E::ScalarField> = disclosed
+            .iter()
+            .filter(|(&i, _)| i != id_index)
+            .map(|(_, m)| m)
+            .collect();
+        if attributes.is_empty() {
+            return Err(Error::Other(
+                "No attributes besides the user ID are disclosed, nothing to check".to_string(),
+            ));
+        }
+        if attributes.iter().all(|m| m.is_zero()) {
+            return Err(Error::Other(format!(
+                "All {} disclosed attributes are zero",
+                attributes.len()
+            )));
+        }
+        for value in &attributes {
+            let repeats = attributes.iter().filter(|m| *m == value).count();
+            if repeats > MAX_REPEATED_ATTRIBUTES {
+                return Err(Error::Other(format!(
+                    "One value is repeated across {} disclosed attributes, at most {} allowed",
+                    repeats, MAX_REPEATED_ATTRIBUTES
+                )));
+            }
+        }
         Ok(sk.sign(&proof.commitment, &self.pp, rng))
     }
 
-    pub fn show(&self, credential: &Credential<E>, rng: &mut impl Rng) -> ShowCredential<E> {
-        let delta_r = E::ScalarField::rand(rng);
-        let delta_u = E::ScalarField::rand(rng);
-        credential.show(&self.pp, &delta_r, &delta_u, rng)
+    // Blind issuance: the holder commits with the slots in `issuer_attrs` set to zero and
+    // proves it, disclosing only those zeros. The issuer writes its values into the slots
+    // and signs the result without learning the holder's attributes, who then attaches the
+    // signature with `Credential::complete_credential`.
+    pub fn issue_blind(
+        &self,
+        user_proof: &CommitmentProof<E>,
+        issuer_attrs: &[(usize, E::ScalarField)],
+        sk: &SecretKey<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Signature<E>, Error> {
+        if issuer_attrs.is_empty() || !valid_changes(self.pp.n, issuer_attrs) {
+            return Err(Error::Other(
+                "Issuer attributes must name distinct attributes in range".to_string(),
+            ));
+        }
+        let zeros: BTreeMap<usize, E::ScalarField> = issuer_attrs
+            .iter()
+            .map(|(i, _)| (*i, E::ScalarField::zero()))
+            .collect();
+        if !user_proof.verify_disclosed_against_with_context(&self.pp, &zeros, &[]) {
+            return Err(Error::InvalidProof);
+        }
+        if !user_proof.commitment.verify_consistency(&self.pp) {
+            return Err(Error::InvalidCommitment);
+        }
+        let commitment = user_proof.commitment.add_attributes(&self.pp, issuer_attrs);
+        Ok(sk.sign(&commitment, &self.pp, rng))
+    }
+
+    // Issue a batch of requests, all or nothing: every proof is checked before anything is signed
+    pub fn issue_batch(
+        &self,
+        proofs: &[&CommitmentProof<E>],
+        sk: &SecretKey<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<Signature<E>>, Error> {
+        if !CommitmentProof::batch_verify_against(&self.pp, proofs) {
+            return Err(Error::InvalidProof);
+        }
+        if !proofs
+            .iter()
+            .all(|proof| proof.commitment.verify_consistency(&self.pp))
+        {
+            return Err(Error::InvalidCommitment);
+        }
+        Ok(proofs
+            .iter()
+            .map(|proof| sk.sign(&proof.commitment, &self.pp, rng))
+            .collect())
+    }
+
+    // Re-sign a credential with some attributes changed: `old_credential` must be a valid
+    // presentation under this issuer, and the proof must show the new commitment holds its
+    // attributes except for the public `changes`. The holder attaches the signature to the
+    // new credential.
+    pub fn reissue_with_changes(
+        &self,
+        old_credential: &ShowCredential<E>,
+        changes: &[(usize, E::ScalarField)],
+        proof_of_unchanged: &UnchangedProof<E>,
+        sk: &SecretKey<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Signature<E>, Error> {
+        let vk = VerificationKey {
+            vk_tilde: self.pp.g_tilde.mul(sk.get_x()).into_affine(),
+        };
+        if !old_credential.verify(&self.pp, &vk) {
+            return Err(Error::SignatureVerificationFailed);
+        }
+        if !proof_of_unchanged.verify(&self.pp, old_credential, changes) {
+            return Err(Error::InvalidProof);
+        }
+        let new_commitment = &proof_of_unchanged.new_commitment;
+        if !new_commitment.verify_consistency(&self.pp) {
+            return Err(Error::InvalidCommitment);
+        }
+        Ok(sk.sign(new_commitment, &self.pp, rng))
+    }
+
+    pub fn show(
+        &self,
+        credential: &Credential<E>,
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        credential.show_auto(&self.pp, rng)
     }
 
     // Verifier checks a credential
@@ -69,17 +242,34 @@ impl<E: Pairing> MimcAbc<E> {
     }
 
     /// This corresponds to RS.VerKey in the protocol specification
-    pub fn prove_key_correctness(&self, sk: &SecretKey<E>, rng: &mut impl Rng) -> VerKeyProof<E> {
-        VerKey::prove(&self.pp, &sk.get_x(), &self.pp.get_y_values(), rng)
+    /// Needs the y values, so it errors on parameters from `PublicParams::from_public_bytes`
+    pub fn prove_key_correctness(
+        &self,
+        sk: &SecretKey<E>,
+        rng: &mut impl Rng,
+    ) -> Result<VerKeyProof<E>, Error> {
+        if !self.pp.has_y_values() {
+            return Err(Error::InvalidPublicParams(
+                "y values are required to prove key correctness".to_string(),
+            ));
+        }
+        Ok(VerKey::prove(
+            &self.pp,
+            &sk.get_x(),
+            &self.pp.get_y_values(),
+            rng,
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::commitment::Commitment;
     use crate::credential::Credential;
+    use crate::multi_credential::AggregatePresentation;
     use ark_bls12_381::{Bls12_381, Fr};
-    use ark_ff::UniformRand;
+    use ark_ff::{Field, UniformRand};
 
     #[test]
     fn test_mimc_abc_credential_lifecycle() {
@@ -103,7 +293,9 @@ mod tests {
         let proof = protocol.obtain(&credential, &mut rng);
 
         // Generate proof of key correctness
-        let key_proof = protocol.prove_key_correctness(&issuer_sk, &mut rng);
+        let key_proof = protocol
+            .prove_key_correctness(&issuer_sk, &mut rng)
+            .unwrap();
 
         // Verify the key proof
         let is_key_valid = protocol.verify_key_correctness(&key_proof, &issuer_vk);
@@ -115,7 +307,7 @@ mod tests {
             .expect("Issuance failed");
 
         // Add signature to credential
-        credential.add_signature(signature);
+        credential.add_signature(signature).unwrap();
 
         // Verify the original credential
         assert!(
@@ -124,7 +316,7 @@ mod tests {
         );
 
         // User shows credential
-        let presentation = protocol.show(&credential, &mut rng);
+        let presentation = protocol.show(&credential, &mut rng).unwrap();
 
         // Verifier checks presentation
         assert!(
@@ -133,6 +325,382 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_obtain_and_issue() {
+        let mut rng = ark_std::test_rng();
+        let n = 4;
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        assert!(credential.signature.is_none());
+
+        protocol
+            .obtain_and_issue(&mut credential, &issuer_sk, &mut rng)
+            .expect("Issuance failed");
+        assert!(credential.verify(&protocol.pp, &issuer_vk));
+        assert!(protocol.verify(protocol.show(&credential, &mut rng).unwrap(), &issuer_vk));
+    }
+
+    #[test]
+    fn test_issue_rejects_inconsistent_commitment() {
+        let mut rng = ark_std::test_rng();
+        let n = 4;
+        let (protocol, issuer_sk, _) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let commitment = protocol.ck.commit(&protocol.pp, &messages, &r);
+        let other = protocol
+            .ck
+            .commit(&protocol.pp, &messages, &Fr::rand(&mut rng));
+        assert!(commitment.verify_consistency(&protocol.pp));
+
+        // The opening proof of cm is valid, but cm_tilde commits to something else
+        let mismatched = Commitment {
+            cm: commitment.cm,
+            cm_tilde: other.cm_tilde,
+        };
+        assert!(!mismatched.verify_consistency(&protocol.pp));
+        let proof = CommitmentProof::prove(&protocol.pp, &mismatched, &messages, &r, &mut rng);
+        assert!(proof.verify());
+        assert!(matches!(
+            protocol.issue(&proof, &issuer_sk, &mut rng),
+            Err(Error::InvalidCommitment)
+        ));
+    }
+
+    #[test]
+    fn test_issue_stream() {
+        use std::sync::mpsc::channel;
+
+        let mut rng = ark_std::test_rng();
+        let n = 4;
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let credentials: Vec<Credential<Bls12_381>> = (0..5)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng))
+            })
+            .collect();
+        let mut proofs: Vec<CommitmentProof<Bls12_381>> = credentials
+            .iter()
+            .map(|credential| protocol.obtain(credential, &mut rng))
+            .collect();
+        // Requests 1 and 3 are bad
+        proofs[1].responses[0] += Fr::from(1u64);
+        proofs[3].schnorr_commitment = proofs[0].schnorr_commitment;
+
+        let (request_tx, request_rx) = channel();
+        let (result_tx, result_rx) = channel();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let mut worker_rng = ark_std::test_rng();
+                protocol.issue_stream(&issuer_sk, request_rx, result_tx, &mut worker_rng);
+            });
+            for proof in proofs {
+                request_tx.send(proof).unwrap();
+            }
+            drop(request_tx);
+        });
+
+        let results: Vec<Result<Signature<Bls12_381>, Error>> = result_rx.iter().collect();
+        assert_eq!(results.len(), 5);
+        for (i, (credential, result)) in credentials.iter().zip(results).enumerate() {
+            match result {
+                Ok(signature) => {
+                    assert!(i != 1 && i != 3, "Bad request {} was signed", i);
+                    assert!(issuer_vk.verify_with_pairing_checker(
+                        &signature,
+                        &credential.commitment,
+                        &protocol.pp
+                    ));
+                }
+                Err(err) => {
+                    assert!(i == 1 || i == 3, "Good request {} failed", i);
+                    assert!(matches!(err, Error::InvalidProof));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_issue_batch_all_or_nothing() {
+        let mut rng = ark_std::test_rng();
+        let n = 4;
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        let credentials: Vec<Credential<Bls12_381>> = (0..10)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng))
+            })
+            .collect();
+        let proofs: Vec<CommitmentProof<Bls12_381>> = credentials
+            .iter()
+            .map(|credential| protocol.obtain(credential, &mut rng))
+            .collect();
+        let refs: Vec<&CommitmentProof<Bls12_381>> = proofs.iter().collect();
+        assert!(CommitmentProof::batch_verify(&refs));
+
+        let signatures = protocol
+            .issue_batch(&refs, &issuer_sk, &mut rng)
+            .expect("Valid batch should be issued");
+        for (credential, signature) in credentials.iter().zip(signatures.iter()) {
+            assert!(issuer_vk.verify_with_pairing_checker(
+                signature,
+                &credential.commitment,
+                &protocol.pp
+            ));
+        }
+
+        // One bad proof among ten rejects the whole batch
+        let mut bad = proofs[6].clone();
+        bad.responses[1] += Fr::from(1u64);
+        assert!(!bad.verify());
+        let mut refs = refs;
+        refs[6] = &bad;
+        assert!(!CommitmentProof::batch_verify(&refs));
+        assert!(matches!(
+            protocol.issue_batch(&refs, &issuer_sk, &mut rng),
+            Err(Error::InvalidProof)
+        ));
+
+        // A proof over bases of the prover's choosing, ck[1] = g, opening a zero in slot 1
+        // as 5: valid over its bases, rejected alone and in a batch
+        let mut fake_pp = protocol.pp.clone();
+        fake_pp.ck[1] = fake_pp.g;
+        fake_pp.refresh_bases();
+        let mut messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        messages[1] = Fr::from(0u64);
+        let r = Fr::rand(&mut rng);
+        let zeroed = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+        let mut claimed = messages.clone();
+        claimed[1] = Fr::from(5u64);
+        let forged = CommitmentProof::prove(
+            &fake_pp,
+            &zeroed.commitment,
+            &claimed,
+            &(r - Fr::from(5u64)),
+            &mut rng,
+        );
+        assert!(forged.verify());
+        assert!(matches!(
+            protocol.issue(&forged, &issuer_sk, &mut rng),
+            Err(Error::InvalidProof)
+        ));
+        refs[6] = &forged;
+        assert!(CommitmentProof::batch_verify(&refs));
+        assert!(matches!(
+            protocol.issue_batch(&refs, &issuer_sk, &mut rng),
+            Err(Error::InvalidProof)
+        ));
+    }
+
+    #[test]
+    fn test_reissue_with_changes() {
+        let mut rng = ark_std::test_rng();
+        let n = 5;
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let mut old = Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        protocol
+            .obtain_and_issue(&mut old, &issuer_sk, &mut rng)
+            .unwrap();
+
+        // New address in attribute 3
+        let changes = [(3, Fr::rand(&mut rng))];
+        let mut new_messages = messages.clone();
+        new_messages[3] = changes[0].1;
+        let mut new = Credential::new(
+            &protocol.ck,
+            &protocol.pp,
+            &new_messages,
+            Fr::rand(&mut rng),
+        );
+        let shown = old.show_auto(&protocol.pp, &mut rng).unwrap();
+        let proof =
+            UnchangedProof::prove(&protocol.pp, &old, &shown, &new, &changes, &mut rng).unwrap();
+        let signature = protocol
+            .reissue_with_changes(&shown, &changes, &proof, &issuer_sk, &mut rng)
+            .unwrap();
+        new.add_signature(signature).unwrap();
+        assert!(new.verify(&protocol.pp, &issuer_vk));
+        assert!(protocol.verify(protocol.show(&new, &mut rng).unwrap(), &issuer_vk));
+
+        // Attribute 2 changed too, but only 3 is declared
+        new_messages[2] = Fr::rand(&mut rng);
+        let altered = Credential::new(
+            &protocol.ck,
+            &protocol.pp,
+            &new_messages,
+            Fr::rand(&mut rng),
+        );
+        assert!(
+            UnchangedProof::prove(&protocol.pp, &old, &shown, &altered, &changes, &mut rng)
+                .is_err()
+        );
+        let mut forged = proof;
+        forged.new_commitment = altered.commitment.clone();
+        assert!(matches!(
+            protocol.reissue_with_changes(&shown, &changes, &forged, &issuer_sk, &mut rng),
+            Err(Error::InvalidProof)
+        ));
+
+        // An old presentation forged without the secret key, over attributes of the forger's
+        // choosing: sigma1 = a*g, cm_tilde = c*g_tilde - a/(a+1)*vk, sigma2 = (a+1)c*g - cm
+        // make the signature and commitment equations fail by amounts that cancel
+        let chosen: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let chosen_r = Fr::rand(&mut rng);
+        let base = Credential::new(&protocol.ck, &protocol.pp, &chosen, chosen_r);
+        let (a, c) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let ratio = a * (a + Fr::from(1u64)).inverse().unwrap();
+        let forged_commitment = Commitment {
+            cm: base.commitment.cm,
+            cm_tilde: (protocol.pp.g_tilde.mul(c) - issuer_vk.vk_tilde.mul(ratio)).into_affine(),
+        };
+        let forged_signature = Signature {
+            sigma1: protocol.pp.g.mul(a).into_affine(),
+            sigma2: (protocol.pp.g.mul((a + Fr::from(1u64)) * c) - base.commitment.cm)
+                .into_affine(),
+        };
+        let forged_show = ShowCredential {
+            randomized_signature: forged_signature,
+            randomized_commitment: forged_commitment.clone(),
+            proof: CommitmentProof::prove(
+                &protocol.pp,
+                &forged_commitment,
+                &chosen,
+                &chosen_r,
+                &mut rng,
+            ),
+            r_new: chosen_r,
+            issuer_or_proof: None,
+            nullifier_proof: None,
+            presented_at: None,
+            disclosed: BTreeMap::new(),
+            range_proofs: BTreeMap::new(),
+        };
+        let mut wanted = chosen.clone();
+        wanted[3] = changes[0].1;
+        let minted = Credential::new(&protocol.ck, &protocol.pp, &wanted, Fr::rand(&mut rng));
+        let minted_proof = UnchangedProof::prove(
+            &protocol.pp,
+            &base,
+            &forged_show,
+            &minted,
+            &changes,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(minted_proof.verify(&protocol.pp, &forged_show, &changes));
+        assert!(matches!(
+            protocol.reissue_with_changes(
+                &forged_show,
+                &changes,
+                &minted_proof,
+                &issuer_sk,
+                &mut rng
+            ),
+            Err(Error::SignatureVerificationFailed)
+        ));
+    }
+
+    #[test]
+    fn test_issue_reject_trivial() {
+        let mut rng = ark_std::test_rng();
+        let n = 6;
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let user_id = Fr::rand(&mut rng);
+        let disclose: Vec<usize> = (1..n).collect();
+
+        let request = |messages: &[Fr], rng: &mut _| {
+            let credential = Credential::new(&protocol.ck, &protocol.pp, messages, Fr::rand(rng));
+            let proof = credential.prove_commitment_disclosing(&protocol.pp, &disclose, rng);
+            let disclosed: BTreeMap<usize, Fr> =
+                disclose.iter().map(|&i| (i, messages[i])).collect();
+            (credential, proof, disclosed)
+        };
+
+        // All-zero attributes behind a real user ID
+        let mut messages = vec![Fr::from(0u64); n];
+        messages[0] = user_id;
+        let (_, proof, disclosed) = request(&messages, &mut rng);
+        let Err(err) = protocol.issue_reject_trivial(&proof, &disclosed, 0, &issuer_sk, &mut rng)
+        else {
+            panic!("Trivial attributes should be rejected");
+        };
+        assert!(err.to_string().contains("disclosed attributes are zero"));
+
+        // One value in four slots
+        let mut messages = vec![Fr::from(7u64); n];
+        messages[0] = user_id;
+        messages[5] = Fr::from(8u64);
+        let (_, proof, disclosed) = request(&messages, &mut rng);
+        let Err(err) = protocol.issue_reject_trivial(&proof, &disclosed, 0, &issuer_sk, &mut rng)
+        else {
+            panic!("Trivial attributes should be rejected");
+        };
+        assert!(err.to_string().contains("repeated across 4"));
+
+        // Ordinary attributes are signed
+        let mut messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        messages[0] = user_id;
+        let (mut credential, proof, disclosed) = request(&messages, &mut rng);
+        let signature = protocol
+            .issue_reject_trivial(&proof, &disclosed, 0, &issuer_sk, &mut rng)
+            .unwrap();
+        credential.add_signature(signature).unwrap();
+        assert!(credential.verify(&protocol.pp, &issuer_vk));
+
+        // Claiming disclosed values other than the committed ones fails the proof
+        let mut lied = disclosed;
+        lied.insert(2, Fr::from(1u64));
+        assert!(matches!(
+            protocol.issue_reject_trivial(&proof, &lied, 0, &issuer_sk, &mut rng),
+            Err(Error::InvalidProof)
+        ));
+
+        // The user ID kept in slot 2 is skipped instead of slot 0, leaving only zeros
+        let mut messages = vec![Fr::from(0u64); n];
+        messages[2] = user_id;
+        let (_, proof, disclosed) = request(&messages, &mut rng);
+        let Err(err) = protocol.issue_reject_trivial(&proof, &disclosed, 2, &issuer_sk, &mut rng)
+        else {
+            panic!("Trivial attributes should be rejected");
+        };
+        assert!(err.to_string().contains("disclosed attributes are zero"));
+
+        // All-zero attributes opened over the prover's own bases with ck[1] = g: the
+        // commitment r*g also opens as 5 in slot 1 with randomness r - 5
+        let mut fake_pp = protocol.pp.clone();
+        fake_pp.ck[1] = fake_pp.g;
+        fake_pp.refresh_bases();
+        let mut zeros = vec![Fr::from(0u64); n];
+        zeros[0] = user_id;
+        let r = Fr::rand(&mut rng);
+        let credential = Credential::new(&protocol.ck, &protocol.pp, &zeros, r);
+        let mut claimed = zeros.clone();
+        claimed[1] = Fr::from(5u64);
+        let proof = CommitmentProof::prove_disclosing(
+            &fake_pp,
+            &credential.commitment,
+            &claimed,
+            &(r - Fr::from(5u64)),
+            None,
+            &[1],
+            &mut rng,
+        );
+        let disclosed: BTreeMap<usize, Fr> = [(1, Fr::from(5u64))].into_iter().collect();
+        assert!(proof.verify_disclosed(&disclosed));
+        assert!(matches!(
+            protocol.issue_reject_trivial(&proof, &disclosed, 0, &issuer_sk, &mut rng),
+            Err(Error::InvalidProof)
+        ));
+    }
+
     #[test]
     fn test_issuer_key_verification() {
         // Initialize random number generator
@@ -143,7 +711,9 @@ mod tests {
         let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
 
         // Generate proof of key correctness
-        let key_proof = protocol.prove_key_correctness(&issuer_sk, &mut rng);
+        let key_proof = protocol
+            .prove_key_correctness(&issuer_sk, &mut rng)
+            .unwrap();
 
         // Verify the key proof
         let is_key_valid = protocol.verify_key_correctness(&key_proof, &issuer_vk);
@@ -159,4 +729,82 @@ mod tests {
         //     "Invalid issuer key verification should fail"
         // );
     }
+
+    #[test]
+    fn test_verify_with_public_bytes_params() {
+        let mut rng = ark_std::test_rng();
+        let n = 4;
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        protocol
+            .obtain_and_issue(&mut credential, &issuer_sk, &mut rng)
+            .expect("Issuance failed");
+
+        // A verifier only ever sees the public encoding
+        let pp = PublicParams::<Bls12_381>::from_public_bytes(&protocol.pp.to_public_bytes())
+            .expect("Public parameters should decode");
+        assert!(!pp.has_y_values());
+        let verifier = MimcAbc::new(pp).unwrap();
+
+        let presentations: Vec<_> = (0..3)
+            .map(|_| protocol.show(&credential, &mut rng).unwrap())
+            .collect();
+        assert!(verifier.verify(presentations[0].clone(), &issuer_vk));
+        assert!(AggregatePresentation::new(presentations).batch_verify(
+            &verifier.pp,
+            &issuer_vk,
+            &mut rng
+        ));
+
+        assert!(matches!(
+            verifier.prove_key_correctness(&issuer_sk, &mut rng),
+            Err(Error::InvalidPublicParams(_))
+        ));
+    }
+
+    #[test]
+    fn test_issue_blind() {
+        let mut rng = ark_std::test_rng();
+        let n = 3;
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+
+        // The user hides attributes 0 and 2, the issuer sets attribute 1
+        let user_id = Fr::rand(&mut rng);
+        let secret = Fr::rand(&mut rng);
+        let messages = vec![user_id, Fr::zero(), secret];
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        let proof = credential.prove_commitment_disclosing(&protocol.pp, &[1], &mut rng);
+
+        let issuer_attrs = [(1, Fr::from(2030u64))];
+        let signature = protocol
+            .issue_blind(&proof, &issuer_attrs, &issuer_sk, &mut rng)
+            .unwrap();
+        credential
+            .complete_credential(&protocol.pp, &issuer_vk, &issuer_attrs, signature)
+            .unwrap();
+        assert_eq!(
+            credential.get_messages(),
+            &vec![user_id, Fr::from(2030u64), secret]
+        );
+        let shown = protocol.show(&credential, &mut rng).unwrap();
+        assert!(protocol.verify(shown, &issuer_vk));
+
+        // A slot the user already filled can't be issued into
+        let messages = vec![user_id, Fr::from(5u64), secret];
+        let credential = Credential::new(&protocol.ck, &protocol.pp, &messages, Fr::rand(&mut rng));
+        let proof = credential.prove_commitment_disclosing(&protocol.pp, &[1], &mut rng);
+        assert!(matches!(
+            protocol.issue_blind(&proof, &issuer_attrs, &issuer_sk, &mut rng),
+            Err(Error::InvalidProof)
+        ));
+
+        // Nor can a slot the proof keeps hidden
+        let proof = credential.prove_commitment(&protocol.pp, &mut rng);
+        assert!(protocol
+            .issue_blind(&proof, &issuer_attrs, &issuer_sk, &mut rng)
+            .is_err());
+    }
 }
@@ -1,8 +1,16 @@
+use crate::error::Error;
+use crate::transcript::Transcript;
 use ark_ec::pairing::Pairing;
-use ark_ec::CurveGroup;
-use ark_ff::UniformRand;
+use ark_ec::scalar_mul::fixed_base::FixedBase;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{PrimeField, UniformRand};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
 use ark_std::ops::Mul;
 use ark_std::rand::Rng;
+use sha2::{Digest, Sha256};
 use std::iter;
 
 #[derive(Clone, Debug)]
@@ -12,10 +20,175 @@ pub struct PublicParams<E: Pairing> {
     pub g_tilde: E::G2Affine,
     pub ck: Vec<E::G1Affine>,
     pub ck_tilde: Vec<E::G2Affine>,
-    y_values: Vec<E::ScalarField>, // Store the y values
+    y_values: Vec<E::ScalarField>,   // Store the y values
+    g1_bases: Vec<E::G1Affine>,      // ck then g, built once for the Schnorr proofs
+    g_tilde_bases: Vec<E::G2Affine>, // ck_tilde then g_tilde
+}
+
+// The cached bases aren't encoded, they're rebuilt on decode
+impl<E: Pairing> CanonicalSerialize for PublicParams<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.n.serialize_with_mode(&mut writer, compress)?;
+        self.g.serialize_with_mode(&mut writer, compress)?;
+        self.g_tilde.serialize_with_mode(&mut writer, compress)?;
+        self.ck.serialize_with_mode(&mut writer, compress)?;
+        self.ck_tilde.serialize_with_mode(&mut writer, compress)?;
+        self.y_values.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.n.serialized_size(compress)
+            + self.g.serialized_size(compress)
+            + self.g_tilde.serialized_size(compress)
+            + self.ck.serialized_size(compress)
+            + self.ck_tilde.serialized_size(compress)
+            + self.y_values.serialized_size(compress)
+    }
+}
+
+impl<E: Pairing> Valid for PublicParams<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.g.check()?;
+        self.g_tilde.check()?;
+        self.ck.check()?;
+        self.ck_tilde.check()
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for PublicParams<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self::from_parts(
+            usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            E::G1Affine::deserialize_with_mode(&mut reader, compress, validate)?,
+            E::G2Affine::deserialize_with_mode(&mut reader, compress, validate)?,
+            Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+        ))
+    }
+}
+
+// Window width for the fixed-base tables: 2^5 multiples per 5-bit digit of the scalar
+const TABLE_WINDOW: usize = 5;
+
+/// Fixed-base window tables for the Schnorr bases g_1, ..., g_n, g
+/// Built once and reused by holders that prove often, see `CommitmentProof::prove_with_table`
+#[derive(Clone, Debug)]
+pub struct BaseTable<E: Pairing> {
+    pub bases: Vec<E::G1Affine>,
+    tables: Vec<Vec<Vec<E::G1Affine>>>, // per base: multiples for every window position
+}
+
+impl<E: Pairing> BaseTable<E> {
+    /// sum_i scalars[i] * bases[i] using the tables
+    pub fn msm(&self, scalars: &[E::ScalarField]) -> E::G1 {
+        let scalar_size = E::ScalarField::MODULUS_BIT_SIZE as usize;
+        let outerc = scalar_size.div_ceil(TABLE_WINDOW);
+        self.tables
+            .iter()
+            .zip(scalars)
+            .map(|(table, scalar)| {
+                FixedBase::windowed_mul::<E::G1>(outerc, TABLE_WINDOW, table, scalar)
+            })
+            .sum()
+    }
+}
+
+/// Deterministic map from (domain, label, index) to a curve point of unknown discrete log
+/// Plugged into `PublicParams::new_hashed_with`
+pub trait HashToCurve {
+    fn hash_to_curve<G: AffineRepr>(&self, domain: &[u8], label: &[u8], index: u64) -> G;
+}
+
+/// Try-and-increment: hash to a candidate x coordinate until one is on the curve, then clear
+/// the cofactor. Works for any curve, but isn't constant time, which is fine for public bases.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TryAndIncrement;
+
+impl HashToCurve for TryAndIncrement {
+    fn hash_to_curve<G: AffineRepr>(&self, domain: &[u8], label: &[u8], index: u64) -> G {
+        let len = G::zero().compressed_size();
+        for counter in 0u64.. {
+            let bytes: Vec<u8> = (0u64..)
+                .flat_map(|block| {
+                    Sha256::new()
+                        .chain_update(b"mimc-abc-hash-to-curve")
+                        .chain_update((domain.len() as u64).to_le_bytes())
+                        .chain_update(domain)
+                        .chain_update((label.len() as u64).to_le_bytes())
+                        .chain_update(label)
+                        .chain_update(index.to_le_bytes())
+                        .chain_update(counter.to_le_bytes())
+                        .chain_update(block.to_le_bytes())
+                        .finalize()
+                })
+                .take(len)
+                .collect();
+            if let Some(point) = G::from_random_bytes(&bytes) {
+                let point = point.mul_by_cofactor();
+                if !point.is_zero() {
+                    return point;
+                }
+            }
+        }
+        unreachable!("about half of all candidates are on the curve")
+    }
 }
 
 impl<E: Pairing> PublicParams<E> {
+    /// Parameters with every base hashed to the curve from `domain` with `TryAndIncrement`
+    /// See `new_hashed_with`
+    pub fn new_nothing_up_my_sleeve(n: usize, domain: &[u8]) -> Self {
+        Self::new_hashed_with(n, domain, &TryAndIncrement)
+    }
+
+    /// Parameters with g, g_tilde and every ck[i], ck_tilde[i] hashed independently from
+    /// `domain`, so nobody knows a discrete log between any two bases. `new` samples
+    /// ck[i] = y_i * g, and whoever ran it can open Pedersen commitments to other values.
+    ///
+    /// The tradeoff: there are no y values, and ck[i], ck_tilde[i] no longer share one.
+    /// `CommitmentKey::commit` only takes the bases and works as before, and so do the
+    /// commitment proofs in G1. But a commitment's halves no longer satisfy
+    /// e(cm, g_tilde) == e(g, cm_tilde), which signing relies on. These parameters suit
+    /// commitments and proofs but not credentials: `validate_for_issuance` and with it
+    /// `MimcAbc::new` reject them.
+    pub fn new_hashed_with(n: usize, domain: &[u8], hasher: &impl HashToCurve) -> Self {
+        Self::from_parts(
+            n,
+            hasher.hash_to_curve(domain, b"g", 0),
+            hasher.hash_to_curve(domain, b"g_tilde", 0),
+            (0..n as u64)
+                .map(|i| hasher.hash_to_curve(domain, b"ck", i))
+                .collect(),
+            (0..n as u64)
+                .map(|i| hasher.hash_to_curve(domain, b"ck_tilde", i))
+                .collect(),
+            Vec::new(),
+        )
+    }
+
+    /// Precompute fixed-base tables for the Schnorr bases
+    pub fn schnorr_base_table(&self) -> BaseTable<E> {
+        let scalar_size = E::ScalarField::MODULUS_BIT_SIZE as usize;
+        let bases = self.get_g1_bases();
+        let tables = bases
+            .iter()
+            .map(|base| FixedBase::get_window_table(scalar_size, TABLE_WINDOW, base.into_group()))
+            .collect();
+        BaseTable {
+            bases: bases.to_vec(),
+            tables,
+        }
+    }
+
     pub fn new(n: &usize, rng: &mut impl Rng) -> Self {
         let g = E::G1Affine::rand(rng);
         let g_tilde = E::G2Affine::rand(rng);
@@ -31,39 +204,188 @@ impl<E: Pairing> PublicParams<E> {
             .map(|yi| g_tilde.mul(*yi))
             .collect::<Vec<_>>();
         let ck_tilde = E::G2::normalize_batch(&ck_tilde);
+        Self::from_parts(*n, g, g_tilde, ck, ck_tilde, y_values)
+    }
+
+    fn from_parts(
+        n: usize,
+        g: E::G1Affine,
+        g_tilde: E::G2Affine,
+        ck: Vec<E::G1Affine>,
+        ck_tilde: Vec<E::G2Affine>,
+        y_values: Vec<E::ScalarField>,
+    ) -> Self {
+        let g1_bases = ck.iter().cloned().chain(iter::once(g)).collect();
+        let g_tilde_bases = ck_tilde
+            .iter()
+            .cloned()
+            .chain(iter::once(g_tilde))
+            .collect();
         PublicParams {
-            n: *n,
+            n,
             g,
             g_tilde,
             ck,
             ck_tilde,
             y_values,
+            g1_bases,
+            g_tilde_bases,
         }
     }
 
-    /// returns g_1,...,g_n,g
-    pub fn get_g1_bases(&self) -> Vec<E::G1Affine> {
-        self.ck.iter().cloned().chain(iter::once(self.g)).collect()
+    /// Rebuild the cached bases after editing g, g_tilde, ck or ck_tilde in place
+    pub fn refresh_bases(&mut self) {
+        *self = Self::from_parts(
+            self.n,
+            self.g,
+            self.g_tilde,
+            std::mem::take(&mut self.ck),
+            std::mem::take(&mut self.ck_tilde),
+            std::mem::take(&mut self.y_values),
+        );
     }
 
-    pub fn get_g1_basesv2(&self) -> Vec<E::G1Affine> {
-        // add g1 to end of ckg1
-        let mut g1_bases = self.ck.clone();
-        g1_bases.push(self.g.clone());
-        g1_bases
+    /// returns g_1,...,g_n,g
+    pub fn get_g1_bases(&self) -> &[E::G1Affine] {
+        &self.g1_bases
     }
 
-    pub fn get_g_tilde_bases(&self) -> Vec<E::G2Affine> {
-        self.ck_tilde
+    /// g_1,...,g_n, then dummy bases for slots n..pad_to, then g
+    /// Slot j's dummy base is g scaled by a hash of g and j, so any verifier can rebuild it
+    pub fn padded_g1_bases(&self, pad_to: usize) -> Vec<E::G1Affine> {
+        let padding = (self.n..pad_to).map(|j| {
+            let mut transcript = Transcript::new(b"mimc-abc-padding-base");
+            transcript.append_serializable(b"g", &self.g);
+            transcript.append_message(b"slot", &(j as u64).to_le_bytes());
+            let scalar: E::ScalarField = transcript.challenge_scalar(b"scalar");
+            self.g.mul(scalar).into_affine()
+        });
+        self.ck
             .iter()
             .cloned()
-            .chain(iter::once(self.g_tilde))
+            .chain(padding)
+            .chain(iter::once(self.g))
             .collect()
     }
 
+    pub fn get_g1_basesv2(&self) -> &[E::G1Affine] {
+        &self.g1_bases
+    }
+
+    pub fn get_g_tilde_bases(&self) -> &[E::G2Affine] {
+        &self.g_tilde_bases
+    }
+
     pub fn get_y_values(&self) -> Vec<E::ScalarField> {
         self.y_values.clone()
     }
+
+    /// Whether the y values behind ck are known, they're needed only to prove key correctness
+    pub fn has_y_values(&self) -> bool {
+        !self.y_values.is_empty()
+    }
+
+    /// Encoding of everything but the y values, for publishing to holders and verifiers
+    pub fn to_public_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        (self.n as u64)
+            .serialize_compressed(&mut bytes)
+            .and_then(|_| self.g.serialize_compressed(&mut bytes))
+            .and_then(|_| self.g_tilde.serialize_compressed(&mut bytes))
+            .and_then(|_| self.ck.serialize_compressed(&mut bytes))
+            .and_then(|_| self.ck_tilde.serialize_compressed(&mut bytes))
+            .expect("serializing into a Vec cannot fail");
+        bytes
+    }
+
+    /// Decode `to_public_bytes`, the result has no y values
+    /// Showing and verifying work as usual, `MimcAbc::prove_key_correctness` errors
+    pub fn from_public_bytes(mut bytes: &[u8]) -> Result<Self, Error> {
+        let n = u64::deserialize_compressed(&mut bytes).map_err(Error::SerializationError)?;
+        let g =
+            E::G1Affine::deserialize_compressed(&mut bytes).map_err(Error::SerializationError)?;
+        let g_tilde =
+            E::G2Affine::deserialize_compressed(&mut bytes).map_err(Error::SerializationError)?;
+        let ck = Vec::<E::G1Affine>::deserialize_compressed(&mut bytes)
+            .map_err(Error::SerializationError)?;
+        let ck_tilde = Vec::<E::G2Affine>::deserialize_compressed(&mut bytes)
+            .map_err(Error::SerializationError)?;
+        let pp = Self::from_parts(n as usize, g, g_tilde, ck, ck_tilde, Vec::new());
+        pp.validate()?;
+        Ok(pp)
+    }
+
+    /// Reject parameters that would break soundness: identity generators, wrong lengths,
+    /// or commitment bases that don't match the stored y values
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.g.is_zero() {
+            return Err(Error::InvalidPublicParams("g is the identity".to_string()));
+        }
+        if self.g_tilde.is_zero() {
+            return Err(Error::InvalidPublicParams(
+                "g_tilde is the identity".to_string(),
+            ));
+        }
+        if self.ck.len() != self.n || self.ck_tilde.len() != self.n {
+            return Err(Error::InvalidPublicParams(format!(
+                "expected {} commitment bases, got {} in G1 and {} in G2",
+                self.n,
+                self.ck.len(),
+                self.ck_tilde.len()
+            )));
+        }
+        if self.g1_bases.split_last() != Some((&self.g, &self.ck[..]))
+            || self.g_tilde_bases.split_last() != Some((&self.g_tilde, &self.ck_tilde[..]))
+        {
+            return Err(Error::InvalidPublicParams(
+                "cached bases are stale, see refresh_bases".to_string(),
+            ));
+        }
+        if !self.y_values.is_empty() {
+            if self.y_values.len() != self.n {
+                return Err(Error::InvalidPublicParams(format!(
+                    "expected {} y values, got {}",
+                    self.n,
+                    self.y_values.len()
+                )));
+            }
+            for (i, y) in self.y_values.iter().enumerate() {
+                if self.ck[i] != self.g.mul(y).into_affine()
+                    || self.ck_tilde[i] != self.g_tilde.mul(y).into_affine()
+                {
+                    return Err(Error::InvalidPublicParams(format!(
+                        "commitment base {} doesn't match its y value",
+                        i
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `validate`, plus the relation signing needs between the two halves of the commitment
+    /// key, e(ck[i], g_tilde) == e(g, ck_tilde[i]), also when there are no y values to check
+    /// it with. All n relations are checked at once, weighted by a hash of the bases.
+    pub fn validate_for_issuance(&self) -> Result<(), Error> {
+        self.validate()?;
+        let mut transcript = Transcript::new(b"mimc-abc-pp-issuance");
+        transcript.append_serializable(b"g", &self.g);
+        transcript.append_serializable(b"g_tilde", &self.g_tilde);
+        transcript.append_serializable(b"ck", &self.ck);
+        transcript.append_serializable(b"ck_tilde", &self.ck_tilde);
+        let weights: Vec<E::ScalarField> = (0..self.n)
+            .map(|_| transcript.challenge_scalar(b"weight"))
+            .collect();
+        let ck = E::G1::msm_unchecked(&self.ck, &weights);
+        let ck_tilde = E::G2::msm_unchecked(&self.ck_tilde, &weights);
+        if E::pairing(ck, self.g_tilde) != E::pairing(self.g, ck_tilde) {
+            return Err(Error::InvalidPublicParams(
+                "commitment bases in G1 and G2 don't share exponents, credentials can't be issued"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -80,4 +402,115 @@ mod test {
         assert_eq!(pp.ck_tilde.len(), n, "ck_tilde length should match n");
         assert_eq!(pp.get_g1_bases().len(), n + 1, "g1 bases should include g");
     }
+
+    #[test]
+    fn test_validate_rejects_bad_params() {
+        let n = 4;
+        let mut rng = ark_std::test_rng();
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        assert!(pp.validate().is_ok());
+
+        let mut bad = pp.clone();
+        bad.g = <Bls12_381 as Pairing>::G1Affine::zero();
+        assert!(bad.validate().is_err(), "Identity g should be rejected");
+        assert!(crate::protocol::MimcAbc::new(bad).is_err());
+
+        let mut bad = pp.clone();
+        bad.g_tilde = <Bls12_381 as Pairing>::G2Affine::zero();
+        assert!(
+            bad.validate().is_err(),
+            "Identity g_tilde should be rejected"
+        );
+
+        let mut bad = pp.clone();
+        bad.ck.pop();
+        assert!(bad.validate().is_err(), "Short ck should be rejected");
+
+        let mut bad = pp.clone();
+        bad.ck.swap(0, 1);
+        assert!(
+            bad.validate().is_err(),
+            "Stale cached bases should be rejected"
+        );
+        bad.refresh_bases();
+        assert!(
+            bad.validate().is_err(),
+            "ck inconsistent with y should be rejected"
+        );
+    }
+
+    #[test]
+    fn test_nothing_up_my_sleeve() {
+        use crate::commitment::CommitmentKey;
+        use crate::proof::CommitmentProof;
+        use ark_bls12_381::Fr;
+
+        let n = 4;
+        let mut rng = ark_std::test_rng();
+        let pp = PublicParams::<Bls12_381>::new_nothing_up_my_sleeve(n, b"test-domain");
+        assert!(pp.validate().is_ok());
+        assert!(!pp.has_y_values());
+
+        // Deterministic per domain, and every base is a distinct point of the prime subgroup
+        let again = PublicParams::<Bls12_381>::new_nothing_up_my_sleeve(n, b"test-domain");
+        assert_eq!(pp.to_public_bytes(), again.to_public_bytes());
+        let other = PublicParams::<Bls12_381>::new_nothing_up_my_sleeve(n, b"other-domain");
+        assert_ne!(pp.g, other.g);
+        let bases = pp.get_g1_bases();
+        for (i, base) in bases.iter().enumerate() {
+            assert!(bases[..i].iter().all(|b| b != base));
+        }
+        // Decoding checks every point is on the curve and in the subgroup
+        assert!(PublicParams::<Bls12_381>::from_public_bytes(&pp.to_public_bytes()).is_ok());
+
+        // Commitments and their proofs work, signing's pairing relation doesn't hold
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let commitment = ck.commit(&pp, &messages, &r);
+        let proof = CommitmentProof::prove(&pp, &commitment, &messages, &r, &mut rng);
+        assert!(proof.verify_against(&pp));
+        assert!(!commitment.verify_consistency(&pp));
+        assert!(pp.validate_for_issuance().is_err());
+        assert!(crate::protocol::MimcAbc::new(pp).is_err());
+
+        // Public bytes of sampled parameters drop the y values but stay issuable
+        let sampled = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let decoded =
+            PublicParams::<Bls12_381>::from_public_bytes(&sampled.to_public_bytes()).unwrap();
+        assert!(!decoded.has_y_values());
+        assert!(crate::protocol::MimcAbc::new(decoded).is_ok());
+    }
+
+    #[test]
+    fn test_cached_bases() {
+        let n = 32;
+        let mut rng = ark_std::test_rng();
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let g1_bases: Vec<_> = pp.ck.iter().cloned().chain(iter::once(pp.g)).collect();
+        let g_tilde_bases: Vec<_> = pp
+            .ck_tilde
+            .iter()
+            .cloned()
+            .chain(iter::once(pp.g_tilde))
+            .collect();
+        assert_eq!(pp.get_g1_bases(), &g1_bases[..]);
+        assert_eq!(pp.get_g1_basesv2(), &g1_bases[..]);
+        assert_eq!(pp.get_g_tilde_bases(), &g_tilde_bases[..]);
+        assert_eq!(pp.padded_g1_bases(n), g1_bases);
+
+        // Rebuilt on decode, and by every constructor
+        let decoded = PublicParams::<Bls12_381>::deserialize_compressed(
+            &crate::serialization::to_bytes(&pp)[..],
+        )
+        .unwrap();
+        assert_eq!(decoded.get_g1_bases(), &g1_bases[..]);
+        assert_eq!(decoded.get_g_tilde_bases(), &g_tilde_bases[..]);
+        let public = PublicParams::<Bls12_381>::from_public_bytes(&pp.to_public_bytes()).unwrap();
+        assert_eq!(public.get_g1_bases(), &g1_bases[..]);
+        assert!(decoded.validate().is_ok() && public.validate().is_ok());
+    }
 }
@@ -0,0 +1,446 @@
+// mimc_abc/src/range_proof.rs
+use crate::attributes::decode_u128;
+use crate::commitment::Commitment;
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::schnorr::SchnorrProtocol;
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::ops::Mul;
+use ark_std::rand::Rng;
+
+/// Number of bits needed to represent every value in [0, span]
+pub fn bits_for_span(span: u64) -> usize {
+    (64 - span.leading_zeros() as usize).max(1)
+}
+
+// Pedersen bases for the bit commitments: values on g, blindings on ck[0]
+pub(crate) fn pedersen_bases<E: Pairing>(pp: &PublicParams<E>) -> (E::G1Affine, E::G1Affine) {
+    (pp.g, pp.ck[0])
+}
+
+/// Proof that a committed value v lies in [0, 2^L), linked to an attribute m by
+///     target = m * G + s * H
+/// where target is built from the bit commitments (see `RangeProof`).
+/// Each bit commitment C_j = b_j * G + s_j * H carries an OR-proof that b_j is 0 or 1.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct BoundProof<E: Pairing> {
+    pub bit_commitments: Vec<E::G1Affine>,
+    pub bit_announcements: Vec<(E::G1Affine, E::G1Affine)>,
+    pub bit_challenges: Vec<E::ScalarField>, // challenge of the 0-branch, the 1-branch gets c - c0
+    pub bit_responses: Vec<(E::ScalarField, E::ScalarField)>,
+    pub link_announcement: E::G1Affine,
+    pub link_response: E::ScalarField,
+}
+
+// Prover secrets for one bound, kept between announcement and response
+struct BoundSecrets<E: Pairing> {
+    bits: Vec<bool>,
+    blindings: Vec<E::ScalarField>,
+    nonces: Vec<E::ScalarField>,
+    link_nonce: E::ScalarField,
+    link_secret: E::ScalarField,
+}
+
+impl<E: Pairing> BoundProof<E> {
+    // Commit to the bits of v; the link secret is +sum(2^j s_j), negated for an upper bound
+    fn commit(
+        pp: &PublicParams<E>,
+        v: u64,
+        bit_length: usize,
+        negate_link: bool,
+        value_nonce: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> (Self, BoundSecrets<E>) {
+        let (g, h) = pedersen_bases(pp);
+        let mut proof = BoundProof {
+            bit_commitments: Vec::with_capacity(bit_length),
+            bit_announcements: Vec::with_capacity(bit_length),
+            bit_challenges: Vec::with_capacity(bit_length),
+            bit_responses: Vec::with_capacity(bit_length),
+            link_announcement: E::G1Affine::zero(),
+            link_response: E::ScalarField::zero(),
+        };
+        let mut secrets: BoundSecrets<E> = BoundSecrets {
+            bits: Vec::with_capacity(bit_length),
+            blindings: Vec::with_capacity(bit_length),
+            nonces: Vec::with_capacity(bit_length),
+            link_nonce: E::ScalarField::rand(rng),
+            link_secret: E::ScalarField::zero(),
+        };
+
+        let mut power = E::ScalarField::from(1u64);
+        for j in 0..bit_length {
+            let bit = (v >> j) & 1 == 1;
+            let s_j = E::ScalarField::rand(rng);
+            let c_j = if bit { g + h.mul(s_j) } else { h.mul(s_j) }.into_affine();
+
+            // Real branch is announced honestly, the other one is simulated
+            let nonce = E::ScalarField::rand(rng);
+            let sim_challenge = E::ScalarField::rand(rng);
+            let sim_response = E::ScalarField::rand(rng);
+            let real = h.mul(nonce).into_affine();
+            let (announcements, challenge0, responses) = if bit {
+                // simulate the 0-branch: A0 = z0*H - c0*C
+                let a0 = (h.mul(sim_response) - c_j.mul(sim_challenge)).into_affine();
+                (
+                    (a0, real),
+                    sim_challenge,
+                    (sim_response, E::ScalarField::zero()),
+                )
+            } else {
+                // simulate the 1-branch: A1 = z1*H - c1*(C - G), c0 is fixed once c is known
+                let a1 =
+                    (h.mul(sim_response) - (c_j.into_group() - g).mul(sim_challenge)).into_affine();
+                (
+                    (real, a1),
+                    sim_challenge,
+                    (E::ScalarField::zero(), sim_response),
+                )
+            };
+
+            proof.bit_commitments.push(c_j);
+            proof.bit_announcements.push(announcements);
+            proof.bit_challenges.push(challenge0);
+            proof.bit_responses.push(responses);
+            secrets.bits.push(bit);
+            secrets.blindings.push(s_j);
+            secrets.nonces.push(nonce);
+            secrets.link_secret += power * s_j;
+            power.double_in_place();
+        }
+
+        if negate_link {
+            secrets.link_secret = -secrets.link_secret;
+        }
+        proof.link_announcement = (g.mul(*value_nonce) + h.mul(secrets.link_nonce)).into_affine();
+        (proof, secrets)
+    }
+
+    fn respond(&mut self, secrets: &BoundSecrets<E>, challenge: &E::ScalarField) {
+        for j in 0..secrets.bits.len() {
+            // bit_challenges holds the simulated branch's challenge until now
+            let simulated = self.bit_challenges[j];
+            let real_challenge = *challenge - simulated;
+            let real_response = secrets.nonces[j] + real_challenge * secrets.blindings[j];
+            if secrets.bits[j] {
+                // real branch is 1, c0 = simulated
+                self.bit_responses[j].1 = real_response;
+            } else {
+                // real branch is 0, c0 = c - c1
+                self.bit_challenges[j] = real_challenge;
+                self.bit_responses[j].0 = real_response;
+            }
+        }
+        self.link_response = secrets.link_nonce + *challenge * secrets.link_secret;
+    }
+
+    fn absorb(&self, transcript: &mut Transcript) {
+        transcript.append_serializable(b"bit_commitments", &self.bit_commitments);
+        for (a0, a1) in &self.bit_announcements {
+            transcript.append_serializable(b"bit_announcement_0", a0);
+            transcript.append_serializable(b"bit_announcement_1", a1);
+        }
+        transcript.append_serializable(b"link_announcement", &self.link_announcement);
+    }
+
+    // sum_j 2^j C_j
+    fn decomposed(&self) -> E::G1 {
+        let mut power = E::ScalarField::from(1u64);
+        let powers: Vec<E::ScalarField> = (0..self.bit_commitments.len())
+            .map(|_| {
+                let current = power;
+                power.double_in_place();
+                current
+            })
+            .collect();
+        E::G1::msm_unchecked(&self.bit_commitments, &powers)
+    }
+
+    fn verify_bits(&self, pp: &PublicParams<E>, challenge: &E::ScalarField) -> bool {
+        let (g, h) = pedersen_bases(pp);
+        let len = self.bit_commitments.len();
+        if self.bit_announcements.len() != len
+            || self.bit_challenges.len() != len
+            || self.bit_responses.len() != len
+        {
+            return false;
+        }
+        for j in 0..len {
+            let c_j = self.bit_commitments[j];
+            let (a0, a1) = self.bit_announcements[j];
+            let (z0, z1) = self.bit_responses[j];
+            let c0 = self.bit_challenges[j];
+            let c1 = *challenge - c0;
+            if h.mul(z0) != a0 + c_j.mul(c0) {
+                return false;
+            }
+            if h.mul(z1) != a1.into_group() + (c_j.into_group() - g).mul(c1) {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Replace the link proof by one simulated for `challenge`, valid for any target
+    fn simulate_link(
+        &mut self,
+        pp: &PublicParams<E>,
+        challenge: &E::ScalarField,
+        value_response: &E::ScalarField,
+        target: E::G1,
+        rng: &mut impl Rng,
+    ) {
+        let (g, h) = pedersen_bases(pp);
+        self.link_response = E::ScalarField::rand(rng);
+        self.link_announcement = (g.mul(*value_response) + h.mul(self.link_response)
+            - target.mul(*challenge))
+        .into_affine();
+    }
+
+    fn verify_link(
+        &self,
+        pp: &PublicParams<E>,
+        challenge: &E::ScalarField,
+        value_response: &E::ScalarField,
+        target: E::G1,
+    ) -> bool {
+        let (g, h) = pedersen_bases(pp);
+        g.mul(*value_response) + h.mul(self.link_response)
+            == self.link_announcement + target.mul(*challenge)
+    }
+}
+
+/// Proof that a hidden attribute lies in [min, max]
+/// Decomposes m - min and max - m into `bits_for_span(max - min)` bits each; both being
+/// non-negative and short rules out wrap-around. The attribute's Schnorr response is shared
+/// with the proof of the credential's commitment opening, which ties the range to the attribute.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RangeProof<E: Pairing> {
+    pub index: usize,
+    pub min: u64,
+    pub max: u64,
+    pub lower: BoundProof<E>,
+    pub upper: BoundProof<E>,
+}
+
+/// Prover side of a range proof between the announcement and the challenge
+pub struct RangeProver<E: Pairing> {
+    proof: RangeProof<E>,
+    lower: BoundSecrets<E>,
+    upper: BoundSecrets<E>,
+}
+
+impl<E: Pairing> RangeProver<E> {
+    /// First move: commit to the bits of value - min and max - value
+    /// `value_nonce` must be the Schnorr blinding used for the attribute in the opening proof
+    pub fn commit(
+        pp: &PublicParams<E>,
+        index: usize,
+        value: u64,
+        min: u64,
+        max: u64,
+        value_nonce: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        if min > max {
+            return Err(Error::Other(format!(
+                "Invalid range: min {} is greater than max {}",
+                min, max
+            )));
+        }
+        if value < min || value > max {
+            return Err(Error::Other(format!(
+                "Attribute {} is outside the range [{}, {}]",
+                index, min, max
+            )));
+        }
+        let bit_length = bits_for_span(max - min);
+        let (lower_proof, lower) =
+            BoundProof::commit(pp, value - min, bit_length, false, value_nonce, rng);
+        let (upper_proof, upper) =
+            BoundProof::commit(pp, max - value, bit_length, true, value_nonce, rng);
+        Ok(Self {
+            proof: RangeProof {
+                index,
+                min,
+                max,
+                lower: lower_proof,
+                upper: upper_proof,
+            },
+            lower,
+            upper,
+        })
+    }
+
+    /// Absorb the announcements before the challenge is drawn
+    pub fn absorb(&self, transcript: &mut Transcript) {
+        self.proof.absorb(transcript);
+    }
+
+    /// Second move: respond to the shared challenge
+    pub fn respond(mut self, challenge: &E::ScalarField) -> RangeProof<E> {
+        self.proof.lower.respond(&self.lower, challenge);
+        self.proof.upper.respond(&self.upper, challenge);
+        self.proof
+    }
+}
+
+impl<E: Pairing> RangeProof<E> {
+    /// A proof for `challenge` and `value_response` fixed in advance, accepted by `verify`
+    /// whatever the attribute is. For OR-compositions, where the branches that don't hold
+    /// are simulated; the bit proofs are real, only the links to the attribute are not.
+    pub(crate) fn simulate(
+        pp: &PublicParams<E>,
+        index: usize,
+        min: u64,
+        max: u64,
+        challenge: &E::ScalarField,
+        value_response: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let mut prover = RangeProver::commit(pp, index, min, min, max, value_response, rng)?;
+        prover.proof.lower.respond(&prover.lower, challenge);
+        prover.proof.upper.respond(&prover.upper, challenge);
+        let mut proof = prover.proof;
+        let (lower_target, upper_target) = proof.targets(pp);
+        proof
+            .lower
+            .simulate_link(pp, challenge, value_response, lower_target, rng);
+        proof
+            .upper
+            .simulate_link(pp, challenge, value_response, upper_target, rng);
+        Ok(proof)
+    }
+
+    // What m * G + S * H and m * G - S' * H must equal for the lower and upper bounds
+    fn targets(&self, pp: &PublicParams<E>) -> (E::G1, E::G1) {
+        let g = pp.g;
+        // m * G + S * H = D + min * G
+        let lower_target = self.lower.decomposed() + g.mul(E::ScalarField::from(self.min));
+        // m * G - S' * H = max * G - D'
+        let upper_target = g.mul(E::ScalarField::from(self.max)) - self.upper.decomposed();
+        (lower_target, upper_target)
+    }
+
+    /// Absorb the public range and announcements into the transcript
+    pub fn absorb(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"range_index", &(self.index as u64).to_le_bytes());
+        transcript.append_message(b"range_min", &self.min.to_le_bytes());
+        transcript.append_message(b"range_max", &self.max.to_le_bytes());
+        self.lower.absorb(transcript);
+        self.upper.absorb(transcript);
+    }
+
+    /// Check the range given the shared challenge and the attribute's Schnorr response
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        challenge: &E::ScalarField,
+        value_response: &E::ScalarField,
+    ) -> bool {
+        if self.min > self.max {
+            return false;
+        }
+        let bit_length = bits_for_span(self.max - self.min);
+        if self.lower.bit_commitments.len() != bit_length
+            || self.upper.bit_commitments.len() != bit_length
+        {
+            return false;
+        }
+        if !self.lower.verify_bits(pp, challenge) || !self.upper.verify_bits(pp, challenge) {
+            return false;
+        }
+
+        let (lower_target, upper_target) = self.targets(pp);
+        self.lower
+            .verify_link(pp, challenge, value_response, lower_target)
+            && self
+                .upper
+                .verify_link(pp, challenge, value_response, upper_target)
+    }
+}
+
+/// A `RangeProof` with its own opening proof of a commitment, for attaching to a presentation
+/// The opening shares the attribute's blinding with the range proof, as `RichPresentation`
+/// does for its single opening
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AttributeRangeProof<E: Pairing> {
+    pub opening_announcement: E::G1Affine,
+    pub opening_responses: Vec<E::ScalarField>, // attributes, then r
+    pub range: RangeProof<E>,
+}
+
+impl<E: Pairing> AttributeRangeProof<E> {
+    /// Prove attribute `index` of `commitment`, opened by `messages` and `r`, lies in [min, max]
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        index: usize,
+        min: u64,
+        max: u64,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        if messages.len() != pp.n || index >= pp.n {
+            return Err(Error::Other("Attribute index out of range".to_string()));
+        }
+        let value = u64::try_from(decode_u128(&messages[index])?)
+            .map_err(|_| Error::Other(format!("Attribute {} doesn't fit in u64", index)))?;
+
+        let opening = SchnorrProtocol::commit(pp.get_g1_bases(), rng);
+        let prover = RangeProver::commit(
+            pp,
+            index,
+            value,
+            min,
+            max,
+            &opening.random_blindings[index],
+            rng,
+        )?;
+        let mut transcript = Self::transcript(commitment, &opening.commited_blindings);
+        prover.absorb(&mut transcript);
+        let challenge: E::ScalarField = transcript.challenge_scalar(b"c");
+
+        let mut exponents = messages.to_vec();
+        exponents.push(*r);
+        Ok(Self {
+            opening_announcement: opening.commited_blindings,
+            opening_responses: SchnorrProtocol::prove(&opening, &exponents, &challenge).0,
+            range: prover.respond(&challenge),
+        })
+    }
+
+    /// Check the attribute at `self.range.index` of `commitment` is in the proof's range
+    pub fn verify(&self, pp: &PublicParams<E>, commitment: &Commitment<E>) -> bool {
+        if self.range.index >= pp.n || self.opening_responses.len() != pp.n + 1 {
+            return false;
+        }
+        let mut transcript = Self::transcript(commitment, &self.opening_announcement);
+        self.range.absorb(&mut transcript);
+        let challenge: E::ScalarField = transcript.challenge_scalar(b"c");
+
+        SchnorrProtocol::verify_schnorr(
+            pp.get_g1_bases(),
+            &commitment.cm,
+            &self.opening_announcement,
+            &self.opening_responses,
+            &challenge,
+        ) && self
+            .range
+            .verify(pp, &challenge, &self.opening_responses[self.range.index])
+    }
+
+    fn transcript(commitment: &Commitment<E>, opening_announcement: &E::G1Affine) -> Transcript {
+        let mut transcript = Transcript::new(b"mimc-abc-attribute-range");
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+        transcript.append_serializable(b"opening_announcement", opening_announcement);
+        transcript
+    }
+}
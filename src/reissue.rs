@@ -0,0 +1,183 @@
+// mimc_abc/src/reissue.rs
+use crate::commitment::Commitment;
+use crate::credential::{Credential, ShowCredential};
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::schnorr::SchnorrProtocol;
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::UniformRand;
+use ark_std::ops::Mul;
+use ark_std::rand::Rng;
+use std::iter;
+
+/// Proof that a new commitment holds the attributes of a shown credential, except for the
+/// public `changes` (index, new value)
+///
+/// The shown credential's randomized commitment and the new commitment, with the changed
+/// values taken out, are opened in one Schnorr proof. Every unchanged slot of the new
+/// commitment reuses the response of the same slot of the old one, which forces the two
+/// values to be equal. See `MimcAbc::reissue_with_changes`.
+#[derive(Clone, Debug)]
+pub struct UnchangedProof<E: Pairing> {
+    pub new_commitment: Commitment<E>,
+    pub old_announcement: E::G1Affine,
+    pub new_announcement: E::G1Affine,
+    pub responses: Vec<E::ScalarField>, // old attributes, then r of the shown commitment
+    pub new_r_response: E::ScalarField,
+}
+
+// Bases of the unchanged attributes, then g
+fn unchanged_bases<E: Pairing>(
+    pp: &PublicParams<E>,
+    changes: &[(usize, E::ScalarField)],
+) -> Vec<E::G1Affine> {
+    unchanged(pp.n, changes)
+        .map(|j| pp.ck[j])
+        .chain(iter::once(pp.g))
+        .collect()
+}
+
+fn unchanged<F>(n: usize, changes: &[(usize, F)]) -> impl Iterator<Item = usize> + '_ {
+    (0..n).filter(move |j| changes.iter().all(|(i, _)| i != j))
+}
+
+// Changes must name distinct attributes
+pub(crate) fn valid_changes<F>(n: usize, changes: &[(usize, F)]) -> bool {
+    changes
+        .iter()
+        .enumerate()
+        .all(|(k, (i, _))| *i < n && changes[..k].iter().all(|(j, _)| j != i))
+}
+
+impl<E: Pairing> UnchangedProof<E> {
+    /// `shown` must be a presentation of `old`, `new` must differ from `old` only at `changes`
+    pub fn prove(
+        pp: &PublicParams<E>,
+        old: &Credential<E>,
+        shown: &ShowCredential<E>,
+        new: &Credential<E>,
+        changes: &[(usize, E::ScalarField)],
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        if !valid_changes(pp.n, changes) {
+            return Err(Error::Other(
+                "Changes must name distinct attributes in range".to_string(),
+            ));
+        }
+        let old_messages = old.get_messages();
+        let new_messages = new.get_messages();
+        if changes.iter().any(|(i, v)| new_messages[*i] != *v)
+            || unchanged(pp.n, changes).any(|j| new_messages[j] != old_messages[j])
+        {
+            return Err(Error::Other(
+                "New credential doesn't match the old one with the changes applied".to_string(),
+            ));
+        }
+
+        // Unchanged slots of the new commitment share the blinding of the old slot
+        let old_commitment = SchnorrProtocol::commit(pp.get_g1_bases(), rng);
+        let new_blindings: Vec<E::ScalarField> = unchanged(pp.n, changes)
+            .map(|j| old_commitment.random_blindings[j])
+            .chain(iter::once(E::ScalarField::rand(rng)))
+            .collect();
+        let new_commitment = SchnorrProtocol::commit_with_prepared_blindings(
+            &unchanged_bases(pp, changes),
+            &new_blindings,
+        );
+
+        let challenge = Self::challenge(
+            changes,
+            shown,
+            &new.commitment,
+            &old_commitment.commited_blindings,
+            &new_commitment.commited_blindings,
+        );
+
+        let mut exponents = old_messages.clone();
+        exponents.push(shown.r_new);
+        let responses = SchnorrProtocol::prove(&old_commitment, &exponents, &challenge);
+        let new_r_response = new_blindings[new_blindings.len() - 1] + challenge * new.get_r();
+
+        Ok(Self {
+            new_commitment: new.commitment.clone(),
+            old_announcement: old_commitment.commited_blindings,
+            new_announcement: new_commitment.commited_blindings,
+            responses: responses.0,
+            new_r_response,
+        })
+    }
+
+    /// Check `new_commitment` is `shown`'s commitment with `changes` applied
+    /// Doesn't check `shown` itself, see `ShowCredential::verify`
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        shown: &ShowCredential<E>,
+        changes: &[(usize, E::ScalarField)],
+    ) -> bool {
+        if !valid_changes(pp.n, changes) || self.responses.len() != pp.n + 1 {
+            return false;
+        }
+
+        let challenge = Self::challenge(
+            changes,
+            shown,
+            &self.new_commitment,
+            &self.old_announcement,
+            &self.new_announcement,
+        );
+
+        if !SchnorrProtocol::verify_schnorr(
+            pp.get_g1_bases(),
+            &shown.randomized_commitment.cm,
+            &self.old_announcement,
+            &self.responses,
+            &challenge,
+        ) {
+            return false;
+        }
+
+        // new cm - sum_i v_i * g_i opens to the old unchanged attributes
+        let statement = changes
+            .iter()
+            .fold(self.new_commitment.cm.into_group(), |acc, (i, v)| {
+                acc - pp.ck[*i].mul(*v)
+            })
+            .into_affine();
+        let new_responses: Vec<E::ScalarField> = unchanged(pp.n, changes)
+            .map(|j| self.responses[j])
+            .chain(iter::once(self.new_r_response))
+            .collect();
+        SchnorrProtocol::verify_schnorr(
+            &unchanged_bases(pp, changes),
+            &statement,
+            &self.new_announcement,
+            &new_responses,
+            &challenge,
+        )
+    }
+
+    fn challenge(
+        changes: &[(usize, E::ScalarField)],
+        shown: &ShowCredential<E>,
+        new_commitment: &Commitment<E>,
+        old_announcement: &E::G1Affine,
+        new_announcement: &E::G1Affine,
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-reissue");
+        for (i, v) in changes {
+            transcript.append_message(b"changed_index", &(*i as u64).to_le_bytes());
+            transcript.append_serializable(b"changed_value", v);
+        }
+        transcript.append_serializable(b"sigma1", &shown.randomized_signature.sigma1);
+        transcript.append_serializable(b"sigma2", &shown.randomized_signature.sigma2);
+        transcript.append_serializable(b"old_cm", &shown.randomized_commitment.cm);
+        transcript.append_serializable(b"new_cm", &new_commitment.cm);
+        transcript.append_serializable(b"new_cm_tilde", &new_commitment.cm_tilde);
+        transcript.append_serializable(b"old_announcement", old_announcement);
+        transcript.append_serializable(b"new_announcement", new_announcement);
+        transcript.challenge_scalar(b"c")
+    }
+}
@@ -0,0 +1,487 @@
+// mimc_abc/src/revocation.rs
+// Revocation by user ID with a bilinear accumulator of the revoked IDs
+use crate::commitment::Commitment;
+use crate::credential::{Credential, ShowCredential};
+use crate::error::Error;
+use crate::public_params::PublicParams;
+use crate::schnorr::SchnorrProtocol;
+use crate::signature::VerificationKey;
+use crate::transcript::Transcript;
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, One, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::ops::{Mul, Neg};
+use ark_std::rand::Rng;
+
+/// What holders and verifiers need of the accumulator: the base P, the key alpha * g_tilde
+/// and the current value V = prod_y (y + alpha) * P over the revoked IDs y
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AccumulatorValue<E: Pairing> {
+    pub p: E::G1Affine,
+    pub q_tilde: E::G2Affine,
+    pub value: E::G1Affine,
+}
+
+/// The revocation authority's accumulator, holding the secret alpha
+///
+/// Revoking or reinstating an ID changes the value, and with it every holder's witness:
+/// holders fetch a fresh `witness` after each change.
+pub struct RevocationAccumulator<E: Pairing> {
+    alpha: E::ScalarField,
+    revoked: Vec<E::ScalarField>,
+    product: E::ScalarField, // prod_y (y + alpha)
+    p: E::G1Affine,
+    q_tilde: E::G2Affine,
+}
+
+/// Witness that a user ID x is not accumulated: C and d != 0 with
+/// (x + alpha) * C + d * P = V, i.e. d = prod_y (y - x) is the remainder of the accumulated
+/// polynomial divided by (x + alpha)
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MembershipWitness<E: Pairing> {
+    pub c: E::G1Affine,
+    pub d: E::ScalarField,
+}
+
+impl<E: Pairing> RevocationAccumulator<E> {
+    pub fn new(pp: &PublicParams<E>, rng: &mut impl Rng) -> Self {
+        let alpha = E::ScalarField::rand(rng);
+        Self {
+            alpha,
+            revoked: Vec::new(),
+            product: E::ScalarField::one(),
+            p: pp.g,
+            q_tilde: pp.g_tilde.mul(alpha).into_affine(),
+        }
+    }
+
+    /// Revoke `user_id`; revoking it again does nothing
+    pub fn add(&mut self, user_id: E::ScalarField) {
+        if !self.revoked.contains(&user_id) {
+            self.product *= user_id + self.alpha;
+            self.revoked.push(user_id);
+        }
+    }
+
+    /// Reinstate `user_id`; returns false if it wasn't revoked
+    pub fn remove(&mut self, user_id: &E::ScalarField) -> bool {
+        match self.revoked.iter().position(|y| y == user_id) {
+            Some(position) => {
+                let factor = self.revoked.swap_remove(position) + self.alpha;
+                self.product *= factor.inverse().expect("revoked IDs are never -alpha");
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_revoked(&self, user_id: &E::ScalarField) -> bool {
+        self.revoked.contains(user_id)
+    }
+
+    pub fn value(&self) -> AccumulatorValue<E> {
+        AccumulatorValue {
+            p: self.p,
+            q_tilde: self.q_tilde,
+            value: self.p.mul(self.product).into_affine(),
+        }
+    }
+
+    /// Non-revocation witness for `user_id` against the current value
+    /// Fails for a revoked ID, which has none
+    pub fn witness(&self, user_id: &E::ScalarField) -> Result<MembershipWitness<E>, Error> {
+        let d: E::ScalarField = self.revoked.iter().map(|y| *y - user_id).product();
+        if d.is_zero() {
+            return Err(Error::Other("User ID is revoked".to_string()));
+        }
+        let quotient = (self.product - d)
+            * (*user_id + self.alpha)
+                .inverse()
+                .ok_or_else(|| Error::Other("User ID has no witness".to_string()))?;
+        Ok(MembershipWitness {
+            c: self.p.mul(quotient).into_affine(),
+            d,
+        })
+    }
+}
+
+impl<E: Pairing> MembershipWitness<E> {
+    /// e(C, alpha * g_tilde + x * g_tilde) == e(V - d * P, g_tilde) with d != 0
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        accumulator: &AccumulatorValue<E>,
+        user_id: &E::ScalarField,
+    ) -> bool {
+        !self.d.is_zero()
+            && E::pairing(
+                self.c,
+                accumulator.q_tilde.into_group() + pp.g_tilde.mul(*user_id),
+            ) == E::pairing(
+                accumulator.value.into_group() - accumulator.p.mul(self.d),
+                pp.g_tilde,
+            )
+    }
+}
+
+/// Proof that attribute `id_index` of a presentation's commitment, the user ID, is not revoked
+///
+/// The witness is blinded by a fresh rho: C' = rho * C, D = rho * d * P and
+/// W = alpha * C' = rho * V - x * C' - rho * d * P, which the verifier checks with
+/// e(C', alpha * g_tilde) == e(W, g_tilde). W and D are opened in one Schnorr proof with the
+/// commitment, sharing x's response with attribute `id_index`. D != 0 shows d != 0, so x is not
+/// accumulated. Fresh rho makes proofs from the same witness unlinkable.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct NonRevocationProof<E: Pairing> {
+    pub blinded_witness: E::G1Affine,   // C'
+    pub blinded_remainder: E::G1Affine, // D
+    pub alpha_witness: E::G1Affine,     // W
+    pub opening_announcement: E::G1Affine,
+    pub accumulator_announcement: E::G1Affine,
+    pub remainder_announcement: E::G1Affine,
+    pub opening_responses: Vec<E::ScalarField>, // attributes, then r
+    pub rho_response: E::ScalarField,
+    pub remainder_response: E::ScalarField,
+}
+
+impl<E: Pairing> NonRevocationProof<E> {
+    /// Prove attribute `id_index` of `messages`, opened by `r` in `commitment`, isn't revoked
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        id_index: usize,
+        accumulator: &AccumulatorValue<E>,
+        witness: &MembershipWitness<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        if id_index >= messages.len() || messages.len() != pp.n {
+            return Err(Error::Other(
+                "Messages must include the user ID".to_string(),
+            ));
+        }
+        let user_id = messages[id_index];
+        if !witness.verify(pp, accumulator, &user_id) {
+            return Err(Error::Other(
+                "Witness doesn't match the accumulator value".to_string(),
+            ));
+        }
+
+        let mut rho = E::ScalarField::rand(rng);
+        while rho.is_zero() {
+            rho = E::ScalarField::rand(rng);
+        }
+        let delta = rho * witness.d;
+        let blinded_witness = witness.c.mul(rho).into_affine();
+        let blinded_remainder = accumulator.p.mul(delta).into_affine();
+        let alpha_witness = (accumulator.value.mul(rho)
+            - blinded_witness.mul(user_id)
+            - blinded_remainder.into_group())
+        .into_affine();
+
+        // The user ID's blinding is shared between the opening and the accumulator relation
+        let opening = SchnorrProtocol::commit(pp.get_g1_bases(), rng);
+        let rho_blinding = E::ScalarField::rand(rng);
+        let delta_blinding = E::ScalarField::rand(rng);
+        let accumulator_commitment = SchnorrProtocol::commit_with_prepared_blindings(
+            &accumulator_bases(accumulator, &blinded_witness),
+            &[
+                rho_blinding,
+                opening.random_blindings[id_index],
+                delta_blinding,
+            ],
+        );
+        let remainder_announcement = accumulator.p.mul(delta_blinding).into_affine();
+
+        let challenge = Self::challenge(
+            commitment,
+            accumulator,
+            id_index,
+            &blinded_witness,
+            &blinded_remainder,
+            &alpha_witness,
+            &opening.commited_blindings,
+            &accumulator_commitment.commited_blindings,
+            &remainder_announcement,
+        );
+
+        let mut exponents = messages.to_vec();
+        exponents.push(*r);
+        Ok(Self {
+            blinded_witness,
+            blinded_remainder,
+            alpha_witness,
+            opening_announcement: opening.commited_blindings,
+            accumulator_announcement: accumulator_commitment.commited_blindings,
+            remainder_announcement,
+            opening_responses: SchnorrProtocol::prove(&opening, &exponents, &challenge).0,
+            rho_response: rho_blinding + challenge * rho,
+            remainder_response: delta_blinding + challenge * delta,
+        })
+    }
+
+    /// Check the user ID in `commitment`, attribute `id_index` of the verifier's schema, isn't
+    /// accumulated in `accumulator`
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        accumulator: &AccumulatorValue<E>,
+        id_index: usize,
+    ) -> bool {
+        if self.opening_responses.len() != pp.n + 1
+            || id_index >= pp.n
+            || self.blinded_witness.is_zero()
+            || self.blinded_remainder.is_zero()
+        {
+            return false;
+        }
+        // W = alpha * C'
+        if E::pairing(self.blinded_witness, accumulator.q_tilde)
+            != E::pairing(self.alpha_witness, pp.g_tilde)
+        {
+            return false;
+        }
+
+        let challenge = Self::challenge(
+            commitment,
+            accumulator,
+            id_index,
+            &self.blinded_witness,
+            &self.blinded_remainder,
+            &self.alpha_witness,
+            &self.opening_announcement,
+            &self.accumulator_announcement,
+            &self.remainder_announcement,
+        );
+
+        SchnorrProtocol::verify_schnorr(
+            pp.get_g1_bases(),
+            &commitment.cm,
+            &self.opening_announcement,
+            &self.opening_responses,
+            &challenge,
+        ) && SchnorrProtocol::verify_schnorr(
+            &accumulator_bases(accumulator, &self.blinded_witness),
+            &self.alpha_witness,
+            &self.accumulator_announcement,
+            &[
+                self.rho_response,
+                self.opening_responses[id_index],
+                self.remainder_response,
+            ],
+            &challenge,
+        ) && SchnorrProtocol::verify_schnorr(
+            &[accumulator.p],
+            &self.blinded_remainder,
+            &self.remainder_announcement,
+            &[self.remainder_response],
+            &challenge,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn challenge(
+        commitment: &Commitment<E>,
+        accumulator: &AccumulatorValue<E>,
+        id_index: usize,
+        blinded_witness: &E::G1Affine,
+        blinded_remainder: &E::G1Affine,
+        alpha_witness: &E::G1Affine,
+        opening_announcement: &E::G1Affine,
+        accumulator_announcement: &E::G1Affine,
+        remainder_announcement: &E::G1Affine,
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-non-revocation");
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_serializable(b"p", &accumulator.p);
+        transcript.append_serializable(b"q_tilde", &accumulator.q_tilde);
+        transcript.append_serializable(b"value", &accumulator.value);
+        transcript.append_message(b"id_index", &(id_index as u64).to_le_bytes());
+        transcript.append_serializable(b"blinded_witness", blinded_witness);
+        transcript.append_serializable(b"blinded_remainder", blinded_remainder);
+        transcript.append_serializable(b"alpha_witness", alpha_witness);
+        transcript.append_serializable(b"opening_announcement", opening_announcement);
+        transcript.append_serializable(b"accumulator_announcement", accumulator_announcement);
+        transcript.append_serializable(b"remainder_announcement", remainder_announcement);
+        transcript.challenge_scalar(b"c")
+    }
+}
+
+// Bases of W = rho * V + x * (-C') + delta * (-P)
+fn accumulator_bases<E: Pairing>(
+    accumulator: &AccumulatorValue<E>,
+    blinded_witness: &E::G1Affine,
+) -> [E::G1Affine; 3] {
+    [
+        accumulator.value,
+        blinded_witness.into_group().neg().into_affine(),
+        accumulator.p.into_group().neg().into_affine(),
+    ]
+}
+
+impl<E: Pairing> ShowCredential<E> {
+    /// Prove this presentation of `credential` carries a user ID that isn't revoked
+    /// Only the holder can, it needs the presentation's `r_new`
+    pub fn prove_non_revocation(
+        &self,
+        pp: &PublicParams<E>,
+        credential: &Credential<E>,
+        accumulator: &AccumulatorValue<E>,
+        witness: &MembershipWitness<E>,
+        rng: &mut impl Rng,
+    ) -> Result<NonRevocationProof<E>, Error> {
+        let mut exponents = credential.get_messages().clone();
+        exponents.push(self.r_new);
+        if exponents.len() != pp.n + 1
+            || E::G1::msm_unchecked(pp.get_g1_bases(), &exponents).into_affine()
+                != self.randomized_commitment.cm
+        {
+            return Err(Error::Other(
+                "Presentation isn't a show of this credential".to_string(),
+            ));
+        }
+        NonRevocationProof::prove(
+            pp,
+            &self.randomized_commitment,
+            credential.get_messages(),
+            &self.r_new,
+            credential.id_index(),
+            accumulator,
+            witness,
+            rng,
+        )
+    }
+
+    /// Verify the presentation and that its user ID, attribute `id_index`, isn't revoked in
+    /// `accumulator`. The verifier picks the slot from the schema, not from the holder
+    pub fn verify_non_revocation(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        accumulator: &AccumulatorValue<E>,
+        proof: &NonRevocationProof<E>,
+        id_index: usize,
+    ) -> bool {
+        proof.verify(pp, &self.randomized_commitment, accumulator, id_index) && self.verify(pp, vk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::MimcAbc;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_revocation() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let pp = &protocol.pp;
+        let mut accumulator = RevocationAccumulator::new(pp, &mut rng);
+
+        // Three users, each with the user ID as attribute 0
+        let credentials: Vec<Credential<Bls12_381>> = (0..3)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let mut credential =
+                    Credential::new(&protocol.ck, pp, &messages, Fr::rand(&mut rng));
+                protocol
+                    .obtain_and_issue(&mut credential, &sk, &mut rng)
+                    .unwrap();
+                credential
+            })
+            .collect();
+        let user_id = |i: usize| credentials[i].get_messages()[0];
+
+        // Someone else's revocation, so the value isn't the empty one
+        accumulator.add(Fr::rand(&mut rng));
+        let stale = accumulator.witness(&user_id(1)).unwrap();
+
+        accumulator.add(user_id(1));
+        assert!(accumulator.is_revoked(&user_id(1)));
+        let value = accumulator.value();
+
+        for i in [0, 2] {
+            let witness = accumulator.witness(&user_id(i)).unwrap();
+            let show = credentials[i].show_auto(pp, &mut rng).unwrap();
+            let proof = show
+                .prove_non_revocation(pp, &credentials[i], &value, &witness, &mut rng)
+                .unwrap();
+            assert!(show.verify_non_revocation(pp, &vk, &value, &proof, 0));
+
+            // Not against another presentation, or another accumulator value
+            let other = credentials[i].show_auto(pp, &mut rng).unwrap();
+            assert!(!other.verify_non_revocation(pp, &vk, &value, &proof, 0));
+            let mut later = accumulator.value();
+            later.value = (later.value + pp.g).into_affine();
+            assert!(!show.verify_non_revocation(pp, &vk, &later, &proof, 0));
+        }
+
+        // The revoked user gets no witness, and the one from before doesn't prove
+        assert!(accumulator.witness(&user_id(1)).is_err());
+        let show = credentials[1].show_auto(pp, &mut rng).unwrap();
+        assert!(show
+            .prove_non_revocation(pp, &credentials[1], &value, &stale, &mut rng)
+            .is_err());
+
+        // A revoked ID would need d = 0, a proof claiming it is rejected
+        let witness = accumulator.witness(&user_id(0)).unwrap();
+        let show_0 = credentials[0].show_auto(pp, &mut rng).unwrap();
+        let mut proof = show_0
+            .prove_non_revocation(pp, &credentials[0], &value, &witness, &mut rng)
+            .unwrap();
+        proof.blinded_remainder = <Bls12_381 as Pairing>::G1Affine::zero();
+        assert!(!show_0.verify_non_revocation(pp, &vk, &value, &proof, 0));
+
+        // Reinstated, the user proves again
+        assert!(accumulator.remove(&user_id(1)));
+        let value = accumulator.value();
+        let witness = accumulator.witness(&user_id(1)).unwrap();
+        let proof = show
+            .prove_non_revocation(pp, &credentials[1], &value, &witness, &mut rng)
+            .unwrap();
+        assert!(show.verify_non_revocation(pp, &vk, &value, &proof, 0));
+    }
+
+    #[test]
+    fn test_revocation_with_id_index() {
+        let mut rng = test_rng();
+        let n = 4;
+        let id_index = 2;
+        let (protocol, sk, vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let pp = &protocol.pp;
+        let mut accumulator = RevocationAccumulator::new(pp, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential = Credential::new(&protocol.ck, pp, &messages, Fr::rand(&mut rng))
+            .with_id_index(id_index)
+            .unwrap();
+        protocol
+            .obtain_and_issue(&mut credential, &sk, &mut rng)
+            .unwrap();
+
+        // Revoking attribute 0 doesn't touch the user, who proves over slot 2
+        accumulator.add(messages[0]);
+        let value = accumulator.value();
+        let witness = accumulator.witness(&messages[id_index]).unwrap();
+        let show = credential.show_auto(pp, &mut rng).unwrap();
+        let proof = show
+            .prove_non_revocation(pp, &credential, &value, &witness, &mut rng)
+            .unwrap();
+        assert!(show.verify_non_revocation(pp, &vk, &value, &proof, id_index));
+
+        // The verifier's slot is bound, the proof doesn't pass for slot 0, the revoked value
+        assert!(!show.verify_non_revocation(pp, &vk, &value, &proof, 0));
+        assert!(!show.verify_non_revocation(pp, &vk, &value, &proof, n));
+
+        // Revoking the user ID itself leaves no witness
+        accumulator.add(messages[id_index]);
+        assert!(accumulator.witness(&messages[id_index]).is_err());
+    }
+}
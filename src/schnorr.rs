@@ -41,7 +41,7 @@ impl SchnorrProtocol {
         random_blindings: &[G::ScalarField],
     ) -> SchnorrCommitment<G> {
         let commited_blindings: G =
-            G::Group::msm_unchecked(public_generators, &random_blindings).into_affine();
+            G::Group::msm_unchecked(public_generators, random_blindings).into_affine();
         SchnorrCommitment {
             random_blindings: random_blindings.to_vec(),
             commited_blindings,
@@ -53,7 +53,7 @@ impl SchnorrProtocol {
         public_generators: &[G],
         rng: &mut R,
         equal_blindness: &G::ScalarField,
-        index: usize, //hard code index to 0
+        _index: usize, //hard code index to 0
     ) -> SchnorrCommitment<G> {
         // random_blindings hide the exponent like a pedersen commitment e.g. g^m h^r
 
@@ -113,7 +113,7 @@ impl SchnorrProtocol {
         challenge: &G::ScalarField,
     ) -> bool {
         //e.g.  LHS = g1^(t1 + e*m1) * g2^(t2 + e*m2) * h^(t3 + e*r)
-        let lhs = G::Group::msm_unchecked(public_generators, &schnorr_responses).into_affine();
+        let lhs = G::Group::msm_unchecked(public_generators, schnorr_responses).into_affine();
         // com^e + com
         let rhs = (schnorr_commitment.into_group() + statement.mul(*challenge)).into_affine();
         lhs == rhs
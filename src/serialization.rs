@@ -0,0 +1,62 @@
+// mimc_abc/src/serialization.rs
+// Versioned encodings: proofs and presentations start with a one-byte format version,
+// so a buffer from another version is rejected instead of misparsed
+use crate::error::Error;
+#[cfg(feature = "serde")]
+use ark_ec::pairing::Pairing;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Write,
+};
+
+pub(crate) fn write_version<W: Write>(writer: W, version: u8) -> Result<(), SerializationError> {
+    version.serialize_compressed(writer)
+}
+
+pub(crate) fn read_version<R: Read>(reader: R, version: u8) -> Result<(), SerializationError> {
+    if u8::deserialize_compressed(reader)? != version {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(())
+}
+
+/// Compressed encoding of a proof or presentation
+pub fn to_bytes<T: CanonicalSerialize>(item: &T) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(item.serialized_size(Compress::Yes));
+    item.serialize_compressed(&mut bytes)
+        .expect("serializing into a Vec cannot fail");
+    bytes
+}
+
+/// Decode a compressed encoding, checking the format version and every point
+pub fn from_bytes<T: CanonicalDeserialize>(bytes: &[u8]) -> Result<T, Error> {
+    T::deserialize_compressed(bytes).map_err(Error::SerializationError)
+}
+
+// serde support through the canonical encoding: a value serializes as its compressed bytes,
+// an array of numbers in JSON and a length-prefixed byte string in bincode, and decoding
+// runs the same checks as `from_bytes`
+#[cfg(feature = "serde")]
+macro_rules! impl_serde_via_canonical {
+    ($($ty:path),*) => {$(
+        impl<E: Pairing> serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(&to_bytes(self))
+            }
+        }
+
+        impl<'de, E: Pairing> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let bytes = <Vec<u8> as serde::Deserialize>::deserialize(deserializer)?;
+                from_bytes(&bytes).map_err(serde::de::Error::custom)
+            }
+        }
+    )*};
+}
+
+#[cfg(feature = "serde")]
+impl_serde_via_canonical!(
+    crate::credential::ShowCredential<E>,
+    crate::signature::Signature<E>,
+    crate::commitment::Commitment<E>,
+    crate::verkey::VerKeyProof<E>
+);
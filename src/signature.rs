@@ -1,15 +1,19 @@
-use crate::commitment::Commitment;
-use crate::pairing::{create_check, PairingCheck};
+use crate::commitment::{Commitment, CommitmentKey};
+use crate::pairing::{nonzero_scalar, PairingCheck};
 use crate::public_params::PublicParams;
+use crate::transcript::Transcript;
 use ark_ec::pairing::Pairing;
 use ark_ec::AffineRepr;
 use ark_ec::CurveGroup;
 use ark_ec::VariableBaseMSM;
-use ark_ff::UniformRand;
+use ark_ff::{One, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::{Add, Mul, Neg};
 use ark_std::rand::Rng;
+use sha2::{Digest, Sha256};
 
 // Secret and verification keys
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct SecretKey<E: Pairing> {
     pub sk: E::G1Affine,
     x: E::ScalarField,
@@ -34,63 +38,272 @@ impl<E: Pairing> SecretKey<E> {
     pub fn new(sk: E::G1Affine, x: E::ScalarField) -> Self {
         Self { sk, x }
     }
+
+    /// Sum of secret keys, the key behind `VerificationKey::aggregate` of their public keys
+    /// For tests and single-process setups; real issuers never pool their secrets
+    pub fn combine(shares: &[&SecretKey<E>]) -> Self {
+        let x = shares.iter().map(|share| share.x).sum();
+        let sk = shares
+            .iter()
+            .map(|share| share.sk.into_group())
+            .sum::<E::G1>()
+            .into_affine();
+        Self { sk, x }
+    }
 }
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct VerificationKey<E: Pairing> {
     pub vk_tilde: E::G2Affine,
 }
 
 impl<E: Pairing> VerificationKey<E> {
+    /// Joint key of several issuers, the sum of their vk_tilde
+    /// A signature verifies under it when it was made with the sum of their secret keys.
+    /// Summing is open to rogue keys, an issuer picking its key to cancel the others', so
+    /// each key should come with a `VerKeyProof` before it's aggregated.
+    pub fn aggregate(keys: &[&VerificationKey<E>]) -> Self {
+        Self {
+            vk_tilde: keys
+                .iter()
+                .map(|key| key.vk_tilde.into_group())
+                .sum::<E::G2>()
+                .into_affine(),
+        }
+    }
+
     pub fn verify(
         &self,
         signature: &Signature<E>,
         commitment: &Commitment<E>,
         pp: &PublicParams<E>,
     ) -> bool {
+        // An identity sigma1 (with sigma2 = 0) satisfies the signature equation for any commitment
+        if signature.sigma1.is_zero() {
+            return false;
+        }
         let left1 = E::pairing(signature.sigma2, pp.g_tilde);
         let right1 = E::pairing(signature.sigma1, self.vk_tilde.add(commitment.cm_tilde));
-        assert!(left1 == right1, "Pairing check failed!");
+        if left1 != right1 {
+            return false;
+        }
 
         let left2 = E::pairing(commitment.cm, pp.g_tilde);
         let right2 = E::pairing(pp.g, commitment.cm_tilde);
-        assert!(left2 == right2, "Commitment Pairing check fail!");
+        if left2 != right2 {
+            return false;
+        }
         true
     }
 
+    /// `verify` without early return: both pairing equations are always evaluated and their
+    /// results combined with the identity-sigma1 check, so the time taken doesn't tell which
+    /// one failed. The comparisons themselves are ordinary field equality, not a hardened
+    /// constant-time primitive.
+    pub fn verify_constant_time(
+        &self,
+        signature: &Signature<E>,
+        commitment: &Commitment<E>,
+        pp: &PublicParams<E>,
+    ) -> bool {
+        let (signature_holds, commitment_holds) = self.evaluate_both(signature, commitment, pp);
+        !signature.sigma1.is_zero() & signature_holds & commitment_holds
+    }
+
+    // (signature equation holds, commitment consistency holds), both always computed
+    fn evaluate_both(
+        &self,
+        signature: &Signature<E>,
+        commitment: &Commitment<E>,
+        pp: &PublicParams<E>,
+    ) -> (bool, bool) {
+        let left1 = E::pairing(signature.sigma2, pp.g_tilde);
+        let right1 = E::pairing(signature.sigma1, self.vk_tilde.add(commitment.cm_tilde));
+        let left2 = E::pairing(commitment.cm, pp.g_tilde);
+        let right2 = E::pairing(pp.g, commitment.cm_tilde);
+        (left1 == right1, left2 == right2)
+    }
+
     pub fn verify_with_pairing_checker(
         &self,
         signature: &Signature<E>,
         commitment: &Commitment<E>,
         pp: &PublicParams<E>,
     ) -> bool {
+        self.verify_prepared(&signature.prepare(), commitment, pp)
+    }
+
+    /// `verify_with_pairing_checker` for a signature with its negated sigma1 already computed
+    pub fn verify_prepared(
+        &self,
+        signature: &PreparedSignature<E>,
+        commitment: &Commitment<E>,
+        pp: &PublicParams<E>,
+    ) -> bool {
+        self.batch_verify_prepared(pp, &[(signature, commitment)])
+    }
+
+    /// Verify many prepared signatures in one merged pairing check
+    /// Every equation of every credential is scaled by its own non-zero weight hashed from the
+    /// key and the whole batch, so errors in different equations or credentials can't cancel
+    pub fn batch_verify_prepared(
+        &self,
+        pp: &PublicParams<E>,
+        signatures: &[(&PreparedSignature<E>, &Commitment<E>)],
+    ) -> bool {
+        if signatures
+            .iter()
+            .any(|(signature, _)| signature.sigma1.is_zero())
+        {
+            return false;
+        }
+
+        let mut transcript = Transcript::new(b"mimc-abc-prepared-batch");
+        transcript.append_serializable(b"vk_tilde", &self.vk_tilde);
+        for (signature, commitment) in signatures {
+            transcript.append_serializable(b"sigma1", &signature.sigma1);
+            transcript.append_serializable(b"sigma2", &signature.sigma2);
+            transcript.append_serializable(b"cm", &commitment.cm);
+            transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+        }
+
+        let neg_g = pp.g.into_group().neg().into_affine();
+        let mut final_check = PairingCheck::<E>::new();
+        for (signature, commitment) in signatures {
+            let weights = (
+                nonzero_scalar(|| transcript.challenge_scalar(b"sig_weight")),
+                nonzero_scalar(|| transcript.challenge_scalar(b"cm_weight")),
+            );
+            final_check.merge(&self.prepared_check(signature, commitment, pp, &neg_g, weights));
+        }
+        final_check.verify()
+    }
+
+    // Signature and commitment consistency checks of one credential, each scaled by its
+    // own weight and merged
+    fn prepared_check(
+        &self,
+        signature: &PreparedSignature<E>,
+        commitment: &Commitment<E>,
+        pp: &PublicParams<E>,
+        neg_g: &E::G1Affine,
+        (sig_weight, cm_weight): (E::ScalarField, E::ScalarField),
+    ) -> PairingCheck<E> {
         // Calculate vk + commitment in G2
         let vk_plus_cm_tilde = self.vk_tilde.add(commitment.cm_tilde).into_affine();
 
         // Create signature verification check: e(sigma2, g_tilde) * e(-sigma1, vk+cm_tilde) = 1
-        let sig_check = create_check::<E>(
+        let mut check = PairingCheck::<E>::scaled(
+            sig_weight,
             &[
                 (&signature.sigma2, &pp.g_tilde),
-                (
-                    &signature.sigma1.into_group().neg().into_affine(),
-                    &vk_plus_cm_tilde,
-                ),
+                (&signature.neg_sigma1, &vk_plus_cm_tilde),
             ],
-            None, // defaults to 1
+            &E::TargetField::one(),
         );
 
         // Create commitment consistency check: e(cm, g_tilde) * e(-g, cm_tilde) = 1
-        let cm_check = create_check::<E>(
-            &[
-                (&commitment.cm, &pp.g_tilde),
-                (&pp.g.into_group().neg().into_affine(), &commitment.cm_tilde),
-            ],
-            None,
-        );
+        check.merge(&PairingCheck::<E>::scaled(
+            cm_weight,
+            &[(&commitment.cm, &pp.g_tilde), (neg_g, &commitment.cm_tilde)],
+            &E::TargetField::one(),
+        ));
+        check
+    }
+}
 
-        // Merge checks and verify
-        let mut final_check = PairingCheck::<E>::new();
-        final_check.merge(&sig_check);
-        final_check.merge(&cm_check);
-        final_check.verify()
+impl<E: Pairing> VerificationKey<E> {
+    /// Verify against a commitment recomputed from the cleartext opening
+    /// For fully-disclosed verifiers: nothing in a supplied `Commitment` is trusted
+    pub fn verify_recomputed(
+        &self,
+        signature: &Signature<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        pp: &PublicParams<E>,
+    ) -> bool {
+        if messages.len() != pp.n {
+            return false;
+        }
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let commitment = ck.commit(pp, messages, r);
+        self.verify_with_pairing_checker(signature, &commitment, pp)
+    }
+
+    /// Stable identifier of the key: SHA-256 of its compressed encoding
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut bytes = Vec::new();
+        self.vk_tilde
+            .serialize_compressed(&mut bytes)
+            .expect("serializing into a Vec cannot fail");
+        Sha256::digest(&bytes).into()
+    }
+
+    /// Both checks of `verify` in 3 Miller loops and one final exponentiation instead of
+    /// 4 full pairings, by folding the commitment equation in with a hash-derived weight
+    pub fn verify_optimized(
+        &self,
+        signature: &Signature<E>,
+        commitment: &Commitment<E>,
+        pp: &PublicParams<E>,
+    ) -> bool {
+        self.prepare(pp).verify(pp, signature, commitment)
+    }
+
+    /// Precompute the G2 line coefficients used by every verification under this key
+    pub fn prepare(&self, pp: &PublicParams<E>) -> PreparedVerificationKey<E> {
+        PreparedVerificationKey {
+            vk_tilde: self.vk_tilde,
+            vk_prepared: self.vk_tilde.into(),
+            g_tilde_prepared: pp.g_tilde.into(),
+        }
+    }
+}
+
+/// Verification key with its G2 elements prepared for the Miller loop
+#[derive(Clone, Debug)]
+pub struct PreparedVerificationKey<E: Pairing> {
+    pub vk_tilde: E::G2Affine,
+    vk_prepared: E::G2Prepared,
+    g_tilde_prepared: E::G2Prepared,
+}
+
+impl<E: Pairing> PreparedVerificationKey<E> {
+    /// Signature and commitment consistency checks in a single multi-Miller loop
+    /// The commitment equation is scaled by a hash-derived weight and folded in:
+    ///     e(sigma2 + w*cm, g_tilde) * e(-sigma1, vk) * e(-(sigma1 + w*g), cm_tilde) == 1
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        signature: &Signature<E>,
+        commitment: &Commitment<E>,
+    ) -> bool {
+        if signature.sigma1.is_zero() {
+            return false;
+        }
+
+        let mut transcript = Transcript::new(b"mimc-abc-prepared-verify");
+        transcript.append_serializable(b"vk_tilde", &self.vk_tilde);
+        transcript.append_serializable(b"sigma1", &signature.sigma1);
+        transcript.append_serializable(b"sigma2", &signature.sigma2);
+        transcript.append_serializable(b"cm", &commitment.cm);
+        transcript.append_serializable(b"cm_tilde", &commitment.cm_tilde);
+        let weight: E::ScalarField = transcript.challenge_scalar(b"weight");
+
+        let left = E::G1::normalize_batch(&[
+            signature.sigma2 + commitment.cm.mul(weight),
+            signature.sigma1.into_group().neg(),
+            (signature.sigma1 + pp.g.mul(weight)).neg(),
+        ]);
+        let right = [
+            self.g_tilde_prepared.clone(),
+            self.vk_prepared.clone(),
+            commitment.cm_tilde.into(),
+        ];
+        let result = E::multi_miller_loop(left, right);
+        E::final_exponentiation(result).is_some_and(|output| output.is_zero())
     }
 }
 
@@ -104,7 +317,70 @@ pub fn generate_keys<E: Pairing>(
     let vk_tilde = pp.g_tilde.mul(x).into_affine();
     (SecretKey { sk, x }, VerificationKey { vk_tilde })
 }
-#[derive(Clone)]
+/// Proof an issuer rotating from `old_vk` to `new_vk` holds both secret keys: Schnorr
+/// proofs of knowledge of x_old and x_new in g_tilde under one challenge hashed from both
+/// keys. Credentials signed under the old key keep verifying with it, and a verifier that
+/// trusts the old key can extend that trust to the new one, see `verify_rotation`
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct KeyRotationProof<E: Pairing> {
+    pub announcements: (E::G2Affine, E::G2Affine), // old, new
+    pub responses: (E::ScalarField, E::ScalarField),
+}
+
+impl<E: Pairing> KeyRotationProof<E> {
+    pub fn prove(
+        pp: &PublicParams<E>,
+        old_sk: &SecretKey<E>,
+        new_sk: &SecretKey<E>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let old_vk = pp.g_tilde.mul(old_sk.x).into_affine();
+        let new_vk = pp.g_tilde.mul(new_sk.x).into_affine();
+        let (b_old, b_new) = (E::ScalarField::rand(rng), E::ScalarField::rand(rng));
+        let announcements = (
+            pp.g_tilde.mul(b_old).into_affine(),
+            pp.g_tilde.mul(b_new).into_affine(),
+        );
+        let c = Self::challenge(pp, &old_vk, &new_vk, &announcements);
+        Self {
+            announcements,
+            responses: (b_old + c * old_sk.x, b_new + c * new_sk.x),
+        }
+    }
+
+    fn challenge(
+        pp: &PublicParams<E>,
+        old_vk: &E::G2Affine,
+        new_vk: &E::G2Affine,
+        announcements: &(E::G2Affine, E::G2Affine),
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-key-rotation");
+        transcript.append_serializable(b"g_tilde", &pp.g_tilde);
+        transcript.append_serializable(b"old_vk", old_vk);
+        transcript.append_serializable(b"new_vk", new_vk);
+        transcript.append_serializable(b"old_announcement", &announcements.0);
+        transcript.append_serializable(b"new_announcement", &announcements.1);
+        transcript.challenge_scalar(b"c")
+    }
+}
+
+/// Check `proof` links `old_vk` to `new_vk`
+pub fn verify_rotation<E: Pairing>(
+    pp: &PublicParams<E>,
+    old_vk: &VerificationKey<E>,
+    new_vk: &VerificationKey<E>,
+    proof: &KeyRotationProof<E>,
+) -> bool {
+    if old_vk.vk_tilde == new_vk.vk_tilde {
+        return false;
+    }
+    let c =
+        KeyRotationProof::challenge(pp, &old_vk.vk_tilde, &new_vk.vk_tilde, &proof.announcements);
+    pp.g_tilde.mul(proof.responses.0) == proof.announcements.0 + old_vk.vk_tilde.mul(c)
+        && pp.g_tilde.mul(proof.responses.1) == proof.announcements.1 + new_vk.vk_tilde.mul(c)
+}
+
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct Signature<E: Pairing> {
     // Signature fields based on your scheme
     pub sigma1: E::G1Affine,
@@ -112,6 +388,15 @@ pub struct Signature<E: Pairing> {
 }
 
 impl<E: Pairing> Signature<E> {
+    /// Cache -sigma1 so repeated or batched verifications skip the conversion
+    pub fn prepare(&self) -> PreparedSignature<E> {
+        PreparedSignature {
+            sigma1: self.sigma1,
+            sigma2: self.sigma2,
+            neg_sigma1: self.sigma1.into_group().neg().into_affine(),
+        }
+    }
+
     pub fn randomize(&self, delta_r: &E::ScalarField, delta_u: &E::ScalarField) -> Self {
         let sigma1_prime = self.sigma1.mul(delta_u).into_affine();
         let r_times_u = delta_r.mul(delta_u);
@@ -126,3 +411,411 @@ impl<E: Pairing> Signature<E> {
         }
     }
 }
+
+/// Signature with its negated sigma1 precomputed for the pairing checks
+#[derive(Clone)]
+pub struct PreparedSignature<E: Pairing> {
+    pub sigma1: E::G1Affine,
+    pub sigma2: E::G1Affine,
+    pub neg_sigma1: E::G1Affine,
+}
+
+/// Proof that a signature is a rerandomization of a known one: knowledge of a = delta_u
+/// and b = delta_u * delta_r with sigma1' = a sigma1 and sigma2' = a sigma2 + b sigma1.
+/// The two signatures alone can't be linked, that's what makes showings unlinkable,
+/// so the holder supplies this to an auditor who knows the original.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RerandomizationProof<E: Pairing> {
+    pub announcements: (E::G1Affine, E::G1Affine),
+    pub responses: (E::ScalarField, E::ScalarField),
+}
+
+impl<E: Pairing> Signature<E> {
+    /// Prove `self` is `original.randomize(delta_r, delta_u)`
+    pub fn prove_rerandomization(
+        &self,
+        original: &Signature<E>,
+        delta_r: &E::ScalarField,
+        delta_u: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> RerandomizationProof<E> {
+        let (a, b) = (*delta_u, *delta_u * delta_r);
+        let (nonce_a, nonce_b) = (E::ScalarField::rand(rng), E::ScalarField::rand(rng));
+        let announcements = (
+            original.sigma1.mul(nonce_a).into_affine(),
+            E::G1::msm_unchecked(&[original.sigma2, original.sigma1], &[nonce_a, nonce_b])
+                .into_affine(),
+        );
+        let c = self.rerandomization_challenge(original, &announcements);
+        RerandomizationProof {
+            announcements,
+            responses: (nonce_a + c * a, nonce_b + c * b),
+        }
+    }
+
+    /// Check `self` verifies against the randomized `commitment` under `vk` and was derived
+    /// from `original`; together these mean `commitment` rerandomizes the original's commitment
+    pub fn verify_rerandomization_of(
+        &self,
+        original: &Signature<E>,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        commitment: &Commitment<E>,
+        proof: &RerandomizationProof<E>,
+    ) -> bool {
+        if self.sigma1.is_zero()
+            || original.sigma1.is_zero()
+            || !vk.verify_with_pairing_checker(self, commitment, pp)
+        {
+            return false;
+        }
+        let c = self.rerandomization_challenge(original, &proof.announcements);
+        let (z_a, z_b) = proof.responses;
+        original.sigma1.mul(z_a) == proof.announcements.0 + self.sigma1.mul(c)
+            && E::G1::msm_unchecked(&[original.sigma2, original.sigma1], &[z_a, z_b])
+                == proof.announcements.1 + self.sigma2.mul(c)
+    }
+
+    fn rerandomization_challenge(
+        &self,
+        original: &Signature<E>,
+        announcements: &(E::G1Affine, E::G1Affine),
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-rerandomization");
+        transcript.append_serializable(b"original_sigma1", &original.sigma1);
+        transcript.append_serializable(b"original_sigma2", &original.sigma2);
+        transcript.append_serializable(b"sigma1", &self.sigma1);
+        transcript.append_serializable(b"sigma2", &self.sigma2);
+        transcript.append_serializable(b"announcement_1", &announcements.0);
+        transcript.append_serializable(b"announcement_2", &announcements.1);
+        transcript.challenge_scalar(b"c")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr, G1Affine};
+    use ark_ff::Field;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_verify_recomputed() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let (sk, vk) = generate_keys(&pp, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let commitment = ck.commit(&pp, &messages, &r);
+        let signature = sk.sign(&commitment, &pp, &mut rng);
+        assert!(vk.verify_recomputed(&signature, &messages, &r, &pp));
+
+        // Claimed messages don't match the supplied commitment: the commitment-based
+        // check still passes, the recomputed one doesn't
+        let mut claimed = messages.clone();
+        claimed[1] += Fr::from(1u64);
+        assert!(vk.verify_with_pairing_checker(&signature, &commitment, &pp));
+        assert!(!vk.verify_recomputed(&signature, &claimed, &r, &pp));
+        assert!(!vk.verify_recomputed(&signature, &messages, &(r + Fr::from(1u64)), &pp));
+    }
+
+    #[test]
+    fn test_verify_rejects_corrupted_signature() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let (sk, vk) = generate_keys(&pp, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let commitment = ck.commit(&pp, &messages, &Fr::rand(&mut rng));
+        let mut signature = sk.sign(&commitment, &pp, &mut rng);
+        assert!(vk.verify(&signature, &commitment, &pp));
+
+        // A bad signature is a false, not a panic
+        signature.sigma2 = (signature.sigma2 + pp.g).into_affine();
+        assert!(!vk.verify(&signature, &commitment, &pp));
+
+        // The all-identity signature satisfies the equation for any commitment
+        let identity = Signature::<Bls12_381> {
+            sigma1: G1Affine::zero(),
+            sigma2: G1Affine::zero(),
+        };
+        assert!(!vk.verify(&identity, &commitment, &pp));
+    }
+
+    #[test]
+    fn test_verify_constant_time() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let (sk, vk) = generate_keys(&pp, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let commitment = ck.commit(&pp, &messages, &Fr::rand(&mut rng));
+        let signature = sk.sign(&commitment, &pp, &mut rng);
+        assert_eq!(vk.evaluate_both(&signature, &commitment, &pp), (true, true));
+        assert!(vk.verify_constant_time(&signature, &commitment, &pp));
+
+        // A failing signature equation still has the commitment equation evaluated
+        let mut forged = signature.clone();
+        forged.sigma2 = (forged.sigma2 + pp.g).into_affine();
+        assert_eq!(vk.evaluate_both(&forged, &commitment, &pp), (false, true));
+        assert!(!vk.verify_constant_time(&forged, &commitment, &pp));
+
+        // Both fail together
+        let mut inconsistent = commitment.clone();
+        inconsistent.cm = (inconsistent.cm + pp.g).into_affine();
+        assert_eq!(
+            vk.evaluate_both(&forged, &inconsistent, &pp),
+            (false, false)
+        );
+        assert!(!vk.verify_constant_time(&signature, &inconsistent, &pp));
+
+        // The identity signature satisfies both equations but is still rejected
+        let identity = Signature::<Bls12_381> {
+            sigma1: G1Affine::zero(),
+            sigma2: G1Affine::zero(),
+        };
+        assert_eq!(vk.evaluate_both(&identity, &commitment, &pp), (true, true));
+        assert!(!vk.verify_constant_time(&identity, &commitment, &pp));
+    }
+
+    #[test]
+    fn test_verify_rerandomization_of() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let (sk, vk) = generate_keys(&pp, &mut rng);
+
+        let sign = |rng: &mut _| {
+            let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(rng)).collect();
+            let commitment = ck.commit(&pp, &messages, &Fr::rand(rng));
+            let signature = sk.sign(&commitment, &pp, rng);
+            (commitment, signature)
+        };
+        let (commitment, original) = sign(&mut rng);
+        let (other_commitment, other) = sign(&mut rng);
+
+        let (delta_r, delta_u) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let randomized = original.randomize(&delta_r, &delta_u);
+        let randomized_commitment = commitment.randomize(&pp, &delta_r);
+        let proof = randomized.prove_rerandomization(&original, &delta_r, &delta_u, &mut rng);
+        assert!(randomized.verify_rerandomization_of(
+            &original,
+            &pp,
+            &vk,
+            &randomized_commitment,
+            &proof
+        ));
+
+        // A valid signature from the same issuer, rerandomized from another original
+        let unrelated = other.randomize(&delta_r, &delta_u);
+        let unrelated_commitment = other_commitment.randomize(&pp, &delta_r);
+        let unrelated_proof = unrelated.prove_rerandomization(&other, &delta_r, &delta_u, &mut rng);
+        assert!(vk.verify_with_pairing_checker(&unrelated, &unrelated_commitment, &pp));
+        assert!(!unrelated.verify_rerandomization_of(
+            &original,
+            &pp,
+            &vk,
+            &unrelated_commitment,
+            &unrelated_proof
+        ));
+
+        // Nor does a legitimate rerandomization pass against the wrong commitment
+        assert!(!randomized.verify_rerandomization_of(
+            &original,
+            &pp,
+            &vk,
+            &unrelated_commitment,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn test_verify_optimized_matches_verify() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let (sk, vk) = generate_keys(&pp, &mut rng);
+        let (_, other_vk) = generate_keys(&pp, &mut rng);
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let commitment = ck.commit(&pp, &messages, &Fr::rand(&mut rng));
+        let signature = sk.sign(&commitment, &pp, &mut rng);
+        assert!(vk.verify(&signature, &commitment, &pp));
+        assert!(vk.verify_optimized(&signature, &commitment, &pp));
+
+        // Failures agree with the pairing-checker verification
+        let other = ck.commit(&pp, &messages, &Fr::rand(&mut rng));
+        let inconsistent = Commitment {
+            cm: commitment.cm,
+            cm_tilde: other.cm_tilde,
+        };
+        let cases = [
+            (&other_vk, &signature, &commitment),
+            (&vk, &signature, &other),
+            (&vk, &signature, &inconsistent),
+        ];
+        for (key, sig, cm) in cases {
+            assert_eq!(
+                key.verify_optimized(sig, cm, &pp),
+                key.verify_with_pairing_checker(sig, cm, &pp)
+            );
+            assert!(!key.verify_optimized(sig, cm, &pp));
+        }
+    }
+
+    #[test]
+    fn test_verify_prepared_matches_verify() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let (sk, vk) = generate_keys(&pp, &mut rng);
+        let (_, other_vk) = generate_keys(&pp, &mut rng);
+
+        let signed: Vec<(Signature<Bls12_381>, Commitment<Bls12_381>)> = (0..3)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let commitment = ck.commit(&pp, &messages, &Fr::rand(&mut rng));
+                (sk.sign(&commitment, &pp, &mut rng), commitment)
+            })
+            .collect();
+        let prepared: Vec<_> = signed.iter().map(|(sig, _)| sig.prepare()).collect();
+
+        for ((signature, commitment), prepared) in signed.iter().zip(&prepared) {
+            assert!(vk.verify_prepared(prepared, commitment, &pp));
+            for key in [&vk, &other_vk] {
+                assert_eq!(
+                    key.verify_prepared(prepared, &signed[0].1, &pp),
+                    key.verify_with_pairing_checker(signature, &signed[0].1, &pp)
+                );
+            }
+        }
+        assert!(!other_vk.verify_prepared(&prepared[0], &signed[0].1, &pp));
+
+        let batch: Vec<_> = prepared
+            .iter()
+            .zip(signed.iter().map(|(_, cm)| cm))
+            .collect();
+        assert!(vk.batch_verify_prepared(&pp, &batch));
+        assert!(!other_vk.batch_verify_prepared(&pp, &batch));
+
+        // One mismatched pair fails the whole batch
+        let mut mismatched = batch.clone();
+        mismatched[2].1 = &signed[1].1;
+        assert!(!vk.batch_verify_prepared(&pp, &mismatched));
+    }
+
+    #[test]
+    fn test_pairing_checker_rejects_cancelling_forgeries() {
+        let mut rng = test_rng();
+        let n = 4;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let (sk, vk) = generate_keys(&pp, &mut rng);
+
+        // Equal weights on both equations let their errors cancel: with sigma1 = a*g,
+        // cm_tilde = c*g_tilde - a/(a+1)*vk and sigma2 = (a+1)c*g - cm, neither equation holds
+        // but their product does, for any cm and without the secret key
+        let (a, c) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let cm = G1Affine::rand(&mut rng);
+        let ratio = a * (a + Fr::from(1u64)).inverse().unwrap();
+        let forged_commitment = Commitment::<Bls12_381> {
+            cm,
+            cm_tilde: (pp.g_tilde.mul(c) - vk.vk_tilde.mul(ratio)).into_affine(),
+        };
+        let forged = Signature::<Bls12_381> {
+            sigma1: pp.g.mul(a).into_affine(),
+            sigma2: (pp.g.mul((a + Fr::from(1u64)) * c) - cm).into_affine(),
+        };
+        assert!(!vk.verify(&forged, &forged_commitment, &pp));
+        assert!(!vk.verify_with_pairing_checker(&forged, &forged_commitment, &pp));
+        assert!(!vk.verify_prepared(&forged.prepare(), &forged_commitment, &pp));
+
+        // Two valid signatures shifted by +D and -D: each fails, and the batch must too
+        let signed: Vec<(Signature<Bls12_381>, Commitment<Bls12_381>)> = (0..2)
+            .map(|_| {
+                let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+                let commitment = ck.commit(&pp, &messages, &Fr::rand(&mut rng));
+                (sk.sign(&commitment, &pp, &mut rng), commitment)
+            })
+            .collect();
+        let shift = G1Affine::rand(&mut rng);
+        let mut shifted: Vec<Signature<Bls12_381>> = signed
+            .iter()
+            .map(|(signature, _)| signature.clone())
+            .collect();
+        shifted[0].sigma2 = (shifted[0].sigma2 + shift).into_affine();
+        shifted[1].sigma2 = (shifted[1].sigma2 - shift).into_affine();
+        for (signature, (_, commitment)) in shifted.iter().zip(&signed) {
+            assert!(!vk.verify_with_pairing_checker(signature, commitment, &pp));
+        }
+        let prepared: Vec<_> = shifted.iter().map(|sig| sig.prepare()).collect();
+        let batch: Vec<_> = prepared
+            .iter()
+            .zip(signed.iter().map(|(_, cm)| cm))
+            .collect();
+        assert!(!vk.batch_verify_prepared(&pp, &batch));
+
+        // Nor does the identity signature pass the merged check
+        let identity = Signature::<Bls12_381> {
+            sigma1: G1Affine::zero(),
+            sigma2: G1Affine::zero(),
+        };
+        assert!(!vk.verify_with_pairing_checker(&identity, &signed[0].1, &pp));
+    }
+
+    #[test]
+    fn test_aggregate_keys() {
+        let mut rng = test_rng();
+        let n = 3;
+        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let ck = CommitmentKey {
+            ck: pp.ck.clone(),
+            ck_tilde: pp.ck_tilde.clone(),
+        };
+        let (sk_a, vk_a) = generate_keys(&pp, &mut rng);
+        let (sk_b, vk_b) = generate_keys(&pp, &mut rng);
+        let sk = SecretKey::combine(&[&sk_a, &sk_b]);
+        let vk = VerificationKey::aggregate(&[&vk_a, &vk_b]);
+        assert_eq!(vk.vk_tilde, pp.g_tilde.mul(sk.get_x()).into_affine());
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let commitment = ck.commit(&pp, &messages, &Fr::rand(&mut rng));
+        let signature = sk.sign(&commitment, &pp, &mut rng);
+        assert!(vk.verify(&signature, &commitment, &pp));
+
+        // Neither issuer's key alone accepts it
+        assert!(!vk_a.verify(&signature, &commitment, &pp));
+        assert!(!vk_b.verify(&signature, &commitment, &pp));
+    }
+}
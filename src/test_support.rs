@@ -0,0 +1,56 @@
+// Curve-generic helpers for tests and benches, built with `cfg(test)` or the `test-util` feature
+use crate::credential::{Credential, ShowCredential};
+use crate::protocol::MimcAbc;
+use crate::serialization::{from_bytes, to_bytes};
+use ark_ec::pairing::Pairing;
+use ark_ff::UniformRand;
+use ark_std::test_rng;
+
+/// Setup, issue, show and verify a credential of `n` random attributes over `E`, panicking
+/// at the first step that fails. Also checks the presentation survives an encoding round
+/// trip at the size `ShowCredential::size_for` predicts, which depends on the curve.
+pub fn run_lifecycle<E: Pairing>(n: usize) {
+    let mut rng = test_rng();
+    let (protocol, sk, vk) = MimcAbc::<E>::setup(n, &mut rng);
+
+    let messages: Vec<E::ScalarField> = (0..n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+    let mut credential = Credential::new(
+        &protocol.ck,
+        &protocol.pp,
+        &messages,
+        E::ScalarField::rand(&mut rng),
+    );
+    let proof = protocol.obtain(&credential, &mut rng);
+    let signature = protocol
+        .issue(&proof, &sk, &mut rng)
+        .expect("issuance proof should verify");
+    credential.add_signature(signature).unwrap();
+    assert!(
+        credential.verify(&protocol.pp, &vk),
+        "signature should verify"
+    );
+
+    let shown = protocol
+        .show(&credential, &mut rng)
+        .expect("signed credential should show");
+    let bytes = to_bytes(&shown);
+    assert_eq!(bytes.len(), ShowCredential::<E>::size_for(n));
+    let decoded: ShowCredential<E> = from_bytes(&bytes).expect("presentation should decode");
+    assert!(protocol.verify(decoded, &vk), "presentation should verify");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_bls12_381() {
+        run_lifecycle::<ark_bls12_381::Bls12_381>(5);
+    }
+
+    #[cfg(feature = "bn254")]
+    #[test]
+    fn test_lifecycle_bn254() {
+        run_lifecycle::<ark_bn254::Bn254>(5);
+    }
+}
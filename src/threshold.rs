@@ -0,0 +1,532 @@
+use crate::commitment::Commitment;
+use crate::error::Error;
+use crate::proof::CommitmentProof;
+use crate::public_params::PublicParams;
+use crate::signature::{Signature, VerificationKey};
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_std::ops::{Mul, Neg};
+use ark_std::rand::Rng;
+
+/// Threshold issuance: the issuer key x and each signing nonce u are Shamir-shared among
+/// `n` issuers with threshold `t`, so fewer than `t` colluding issuers learn neither.
+///
+/// Issuer i's partial is a signature under its key share with its nonce share,
+/// (u_i g, u_i (cm + x_i g) + z_i g), where z is a sharing of zero that hides the product
+/// shares u_i x_i. The product has degree 2t - 2, so combining takes 2t - 1 partials.
+/// Each partial is checked against the dealer's public shares before it is used, which
+/// pins down the issuer that sent a bad one.
+pub struct ThresholdKey<E: Pairing> {
+    pub t: usize,
+    pub vk: VerificationKey<E>,
+    pub vk_shares: Vec<E::G2Affine>, // g_tilde^{x_i}, issuer i at position i - 1
+}
+
+/// Issuer i's share of the signing key
+pub struct KeyShare<E: Pairing> {
+    pub index: usize, // 1-based evaluation point
+    sk: E::G1Affine,  // g^{x_i}
+}
+
+/// Issuer i's share of one signing nonce; consumed by `partial_sign`
+pub struct NonceShare<E: Pairing> {
+    pub index: usize,
+    u: E::ScalarField,
+    z: E::ScalarField,
+}
+
+/// Public side of a nonce dealing, g_tilde^{u_i} and g_tilde^{z_i} per issuer
+pub struct NonceCommitments<E: Pairing> {
+    pub u_tilde: Vec<E::G2Affine>,
+    pub z_tilde: Vec<E::G2Affine>,
+}
+
+/// A partial signature; it is its own correctness proof, checked with pairings
+/// against the issuer's public key share and nonce commitments
+#[derive(Clone)]
+pub struct PartialSignature<E: Pairing> {
+    pub index: usize,
+    pub signature: Signature<E>,
+}
+
+// Evaluations at 1..=n of a random polynomial of the given degree and constant term
+fn shamir_shares<F: Field>(secret: F, degree: usize, n: usize, rng: &mut impl Rng) -> Vec<F> {
+    let coefficients: Vec<F> = ark_std::iter::once(secret)
+        .chain((0..degree).map(|_| F::rand(rng)))
+        .collect();
+    (1..=n as u64)
+        .map(|i| {
+            let x = F::from(i);
+            coefficients
+                .iter()
+                .rev()
+                .fold(F::zero(), |acc, c| acc * x + c)
+        })
+        .collect()
+}
+
+// Lagrange coefficients at zero for the 1-based evaluation points `indices`
+fn lagrange_at_zero<F: Field>(indices: &[usize]) -> Vec<F> {
+    indices
+        .iter()
+        .map(|&i| {
+            let xi = F::from(i as u64);
+            let (num, den) =
+                indices
+                    .iter()
+                    .filter(|&&j| j != i)
+                    .fold((F::one(), F::one()), |(num, den), &j| {
+                        let xj = F::from(j as u64);
+                        (num * xj, den * (xj - xi))
+                    });
+            num * den.inverse().expect("evaluation points are distinct")
+        })
+        .collect()
+}
+
+/// Trusted dealer: share a fresh issuer key among `n` issuers with threshold `t`
+pub fn deal_keys<E: Pairing>(
+    pp: &PublicParams<E>,
+    t: usize,
+    n: usize,
+    rng: &mut impl Rng,
+) -> Result<(ThresholdKey<E>, Vec<KeyShare<E>>), Error> {
+    if t == 0 || n < 2 * t - 1 {
+        return Err(Error::Other(format!(
+            "Threshold {} needs at least {} issuers, got {}",
+            t,
+            2 * t - 1,
+            n
+        )));
+    }
+    let x = E::ScalarField::rand(rng);
+    let shares = shamir_shares(x, t - 1, n, rng);
+
+    let key = ThresholdKey {
+        t,
+        vk: VerificationKey {
+            vk_tilde: pp.g_tilde.mul(x).into_affine(),
+        },
+        vk_shares: shares
+            .iter()
+            .map(|xi| pp.g_tilde.mul(xi).into_affine())
+            .collect(),
+    };
+    let key_shares = shares
+        .iter()
+        .enumerate()
+        .map(|(i, xi)| KeyShare {
+            index: i + 1,
+            sk: pp.g.mul(xi).into_affine(),
+        })
+        .collect();
+    Ok((key, key_shares))
+}
+
+/// Trusted dealer: share a single-use signing nonce for one issuance
+pub fn deal_nonce<E: Pairing>(
+    pp: &PublicParams<E>,
+    t: usize,
+    n: usize,
+    rng: &mut impl Rng,
+) -> (NonceCommitments<E>, Vec<NonceShare<E>>) {
+    let u = E::ScalarField::rand(rng);
+    let u_shares = shamir_shares(u, t.saturating_sub(1), n, rng);
+    let z_shares = shamir_shares(E::ScalarField::zero(), (2 * t).saturating_sub(2), n, rng);
+
+    let commitments = NonceCommitments {
+        u_tilde: u_shares
+            .iter()
+            .map(|ui| pp.g_tilde.mul(ui).into_affine())
+            .collect(),
+        z_tilde: z_shares
+            .iter()
+            .map(|zi| pp.g_tilde.mul(zi).into_affine())
+            .collect(),
+    };
+    let shares = u_shares
+        .into_iter()
+        .zip(z_shares)
+        .enumerate()
+        .map(|(i, (u, z))| NonceShare { index: i + 1, u, z })
+        .collect();
+    (commitments, shares)
+}
+
+impl<E: Pairing> KeyShare<E> {
+    /// Check the request and sign it with this key share and a fresh nonce share
+    pub fn partial_sign(
+        &self,
+        pp: &PublicParams<E>,
+        proof: &CommitmentProof<E>,
+        nonce: NonceShare<E>,
+    ) -> Result<PartialSignature<E>, Error> {
+        if nonce.index != self.index {
+            return Err(Error::Other(format!(
+                "Nonce share {} used by issuer {}",
+                nonce.index, self.index
+            )));
+        }
+        // Over pp's bases, never the ones the prover sent
+        if !proof.verify_against(pp) {
+            return Err(Error::InvalidProof);
+        }
+        let sigma1 = pp.g.mul(nonce.u).into_affine();
+        let sigma2 = E::G1::msm_unchecked(
+            &[proof.commitment.cm, self.sk, pp.g],
+            &[nonce.u, nonce.u, nonce.z],
+        )
+        .into_affine();
+        Ok(PartialSignature {
+            index: self.index,
+            signature: Signature { sigma1, sigma2 },
+        })
+    }
+}
+
+impl<E: Pairing> PartialSignature<E> {
+    /// Check the partial was made with the issuer's key share and dealt nonce share
+    /// Assumes `commitment` is consistent across G1 and G2, see `combine_partials`
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        key: &ThresholdKey<E>,
+        nonces: &NonceCommitments<E>,
+    ) -> bool {
+        let slot = match self.index.checked_sub(1) {
+            Some(slot)
+                if slot < key.vk_shares.len()
+                    && slot < nonces.u_tilde.len()
+                    && slot < nonces.z_tilde.len() =>
+            {
+                slot
+            }
+            _ => return false,
+        };
+        let sigma = &self.signature;
+        let neg_g = pp.g.into_group().neg();
+
+        // sigma1 = u_i g
+        let nonce_ok = E::multi_pairing(
+            [sigma.sigma1.into_group(), neg_g],
+            [pp.g_tilde, nonces.u_tilde[slot]],
+        )
+        .is_zero();
+
+        // sigma2 = u_i (cm + x_i g) + z_i g
+        let vk_plus_cm_tilde = (key.vk_shares[slot] + commitment.cm_tilde).into_affine();
+        let sign_ok = E::multi_pairing(
+            [
+                sigma.sigma2.into_group(),
+                sigma.sigma1.into_group().neg(),
+                neg_g,
+            ],
+            [pp.g_tilde, vk_plus_cm_tilde, nonces.z_tilde[slot]],
+        )
+        .is_zero();
+
+        nonce_ok && sign_ok
+    }
+}
+
+/// Verify every partial and interpolate them into a signature under `key.vk`
+/// Fails with `InvalidPartialSignature` naming the first issuer whose partial is wrong
+pub fn combine_partials<E: Pairing>(
+    pp: &PublicParams<E>,
+    commitment: &Commitment<E>,
+    key: &ThresholdKey<E>,
+    nonces: &NonceCommitments<E>,
+    partials: &[PartialSignature<E>],
+) -> Result<Signature<E>, Error> {
+    let needed = 2 * key.t - 1;
+    if partials.len() < needed {
+        return Err(Error::Other(format!(
+            "Need {} partial signatures, got {}",
+            needed,
+            partials.len()
+        )));
+    }
+    let mut indices: Vec<usize> = partials.iter().map(|p| p.index).collect();
+    indices.sort_unstable();
+    if indices.windows(2).any(|w| w[0] == w[1]) {
+        return Err(Error::Other("Duplicate partial signatures".to_string()));
+    }
+
+    // The per-partial checks read cm_tilde, so it has to match cm
+    if E::pairing(commitment.cm, pp.g_tilde) != E::pairing(pp.g, commitment.cm_tilde) {
+        return Err(Error::InvalidCommitment);
+    }
+    if let Some(bad) = partials
+        .iter()
+        .find(|partial| !partial.verify(pp, commitment, key, nonces))
+    {
+        return Err(Error::InvalidPartialSignature(bad.index));
+    }
+
+    Ok(interpolate(partials))
+}
+
+// Lagrange-combine verified partials at zero
+fn interpolate<'a, E: Pairing>(
+    partials: impl IntoIterator<Item = &'a PartialSignature<E>>,
+) -> Signature<E> {
+    let partials: Vec<&PartialSignature<E>> = partials.into_iter().collect();
+    let indices: Vec<usize> = partials.iter().map(|p| p.index).collect();
+    let lambdas = lagrange_at_zero::<E::ScalarField>(&indices);
+    let sigma1s: Vec<E::G1Affine> = partials.iter().map(|p| p.signature.sigma1).collect();
+    let sigma2s: Vec<E::G1Affine> = partials.iter().map(|p| p.signature.sigma2).collect();
+    Signature {
+        sigma1: E::G1::msm_unchecked(&sigma1s, &lambdas).into_affine(),
+        sigma2: E::G1::msm_unchecked(&sigma2s, &lambdas).into_affine(),
+    }
+}
+
+/// Weighted threshold issuance: issuer i holds `weights[i]` consecutive Shamir points and
+/// signs with all of them, and combining needs contributors whose weights sum to `threshold`.
+///
+/// The points are dealt with the unweighted scheme at t = (threshold + 1) / 2, so the
+/// `threshold` points of any qualifying set are enough to interpolate. As there, the
+/// product sharing means a coalition holding t points can recover the key, roughly half
+/// the threshold. Dealing rejects weights that give a single issuer t points, but the
+/// weights only gate signing through `combine_weighted_partials`; they don't stop issuers
+/// whose points together reach t from colluding to recover the key.
+pub struct WeightedThresholdKey<E: Pairing> {
+    pub threshold: usize,
+    pub weights: Vec<usize>,
+    pub key: ThresholdKey<E>,
+}
+
+impl<E: Pairing> WeightedThresholdKey<E> {
+    // 1-based Shamir points held by `issuer`
+    fn points(&self, issuer: usize) -> ark_std::ops::Range<usize> {
+        let start: usize = self.weights[..issuer].iter().sum();
+        start + 1..start + 1 + self.weights[issuer]
+    }
+
+    /// Deal a single-use nonce, grouped per issuer like the key shares
+    pub fn deal_nonce(
+        &self,
+        pp: &PublicParams<E>,
+        rng: &mut impl Rng,
+    ) -> (NonceCommitments<E>, Vec<Vec<NonceShare<E>>>) {
+        let total = self.weights.iter().sum();
+        let (commitments, shares) = deal_nonce(pp, self.key.t, total, rng);
+        (commitments, group_by_weight(shares, &self.weights))
+    }
+}
+
+fn group_by_weight<T>(items: Vec<T>, weights: &[usize]) -> Vec<Vec<T>> {
+    let mut items = items.into_iter();
+    weights
+        .iter()
+        .map(|&w| items.by_ref().take(w).collect())
+        .collect()
+}
+
+/// One weighted issuer's key shares, one per point it holds
+pub type IssuerKeyShares<E> = Vec<KeyShare<E>>;
+
+/// Trusted dealer: share a fresh issuer key among issuers with the given weights
+pub fn deal_weighted_keys<E: Pairing>(
+    pp: &PublicParams<E>,
+    weights: &[usize],
+    threshold: usize,
+    rng: &mut impl Rng,
+) -> Result<(WeightedThresholdKey<E>, Vec<IssuerKeyShares<E>>), Error> {
+    let total: usize = weights.iter().sum();
+    if threshold == 0 || threshold > total || weights.contains(&0) {
+        return Err(Error::Other(format!(
+            "Threshold {} must be reachable by positive weights summing to {}",
+            threshold, total
+        )));
+    }
+    let t = threshold.div_ceil(2);
+    if let Some(issuer) = weights.iter().position(|&w| w >= t) {
+        return Err(Error::Other(format!(
+            "Issuer {} holds {} points, enough to recover the key alone at threshold {}",
+            issuer, weights[issuer], threshold
+        )));
+    }
+    let (key, shares) = deal_keys(pp, t, total, rng)?;
+    Ok((
+        WeightedThresholdKey {
+            threshold,
+            weights: weights.to_vec(),
+            key,
+        },
+        group_by_weight(shares, weights),
+    ))
+}
+
+/// Verify and combine the partials of contributing issuers, given as
+/// (issuer position, partials over all of its points)
+/// Fails with `InvalidPartialSignature` naming the issuer position if any of its partials is wrong
+pub fn combine_weighted_partials<E: Pairing>(
+    pp: &PublicParams<E>,
+    commitment: &Commitment<E>,
+    key: &WeightedThresholdKey<E>,
+    nonces: &NonceCommitments<E>,
+    contributions: &[(usize, Vec<PartialSignature<E>>)],
+) -> Result<Signature<E>, Error> {
+    let mut issuers: Vec<usize> = contributions.iter().map(|(issuer, _)| *issuer).collect();
+    issuers.sort_unstable();
+    if issuers.windows(2).any(|w| w[0] == w[1]) {
+        return Err(Error::Other("Duplicate issuer contributions".to_string()));
+    }
+    if let Some(issuer) = issuers.iter().find(|&&i| i >= key.weights.len()) {
+        return Err(Error::Other(format!("Unknown issuer {}", issuer)));
+    }
+    let weight: usize = issuers.iter().map(|&i| key.weights[i]).sum();
+    if weight < key.threshold {
+        return Err(Error::Other(format!(
+            "Contributors carry weight {}, threshold is {}",
+            weight, key.threshold
+        )));
+    }
+
+    if E::pairing(commitment.cm, pp.g_tilde) != E::pairing(pp.g, commitment.cm_tilde) {
+        return Err(Error::InvalidCommitment);
+    }
+    for (issuer, partials) in contributions {
+        // Every point the issuer holds, each signed correctly
+        let signed_points = partials.iter().map(|p| p.index);
+        if !signed_points.eq(key.points(*issuer))
+            || partials
+                .iter()
+                .any(|partial| !partial.verify(pp, commitment, &key.key, nonces))
+        {
+            return Err(Error::InvalidPartialSignature(*issuer));
+        }
+    }
+
+    Ok(interpolate(
+        contributions.iter().flat_map(|(_, partials)| partials),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credential::Credential;
+    use crate::protocol::MimcAbc;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_combine_partials_names_faulty_issuer() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (t, issuers) = (3, 5);
+        let (protocol, _, _) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let pp = &protocol.pp;
+        let (key, key_shares) = deal_keys(pp, t, issuers, &mut rng).unwrap();
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = Fr::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, pp, &messages, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+
+        let (nonces, nonce_shares) = deal_nonce(pp, t, issuers, &mut rng);
+        let partials: Vec<PartialSignature<Bls12_381>> = key_shares
+            .iter()
+            .zip(nonce_shares)
+            .map(|(share, nonce)| share.partial_sign(pp, &proof, nonce).unwrap())
+            .collect();
+
+        let signature = combine_partials(pp, &proof.commitment, &key, &nonces, &partials).unwrap();
+        credential.add_signature(signature).unwrap();
+        assert!(credential.verify(pp, &key.vk));
+        assert!(protocol
+            .show(&credential, &mut rng)
+            .unwrap()
+            .verify(pp, &key.vk));
+
+        // Issuer 4 sends a bad partial
+        let mut corrupted = partials.clone();
+        corrupted[3].signature.sigma2 = (corrupted[3].signature.sigma2 + pp.g).into_affine();
+        match combine_partials(pp, &proof.commitment, &key, &nonces, &corrupted) {
+            Err(Error::InvalidPartialSignature(index)) => assert_eq!(index, 4),
+            _ => panic!("Corrupted partial should be attributed to issuer 4"),
+        }
+
+        // Too few partials can't be combined
+        assert!(combine_partials(pp, &proof.commitment, &key, &nonces, &partials[..4]).is_err());
+
+        // A proof over bases of the prover's choosing, ck[1] = g, opening a zero in slot 1
+        // as 5, isn't signed
+        let mut fake_pp = pp.clone();
+        fake_pp.ck[1] = fake_pp.g;
+        fake_pp.refresh_bases();
+        let mut zeroed = messages.clone();
+        zeroed[1] = Fr::from(0u64);
+        let commitment = protocol.ck.commit(pp, &zeroed, &r);
+        let mut claimed = zeroed;
+        claimed[1] = Fr::from(5u64);
+        let forged = CommitmentProof::prove(
+            &fake_pp,
+            &commitment,
+            &claimed,
+            &(r - Fr::from(5u64)),
+            &mut rng,
+        );
+        assert!(forged.verify());
+        let (_, mut nonce_shares) = deal_nonce(pp, t, issuers, &mut rng);
+        assert!(matches!(
+            key_shares[0].partial_sign(pp, &forged, nonce_shares.remove(0)),
+            Err(Error::InvalidProof)
+        ));
+    }
+
+    #[test]
+    fn test_weighted_threshold() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, _, _) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let pp = &protocol.pp;
+        // Issuer 0 would hold 3 of the 4 points, past t = 2, and could recover the key
+        assert!(deal_weighted_keys(pp, &[3, 2, 2], 4, &mut rng).is_err());
+        let (key, key_shares) = deal_weighted_keys(pp, &[2, 2, 1, 1, 1], 5, &mut rng).unwrap();
+
+        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential = Credential::new(&protocol.ck, pp, &messages, Fr::rand(&mut rng));
+        let proof = protocol.obtain(&credential, &mut rng);
+
+        let (nonces, nonce_shares) = key.deal_nonce(pp, &mut rng);
+        let contributions: Vec<(usize, Vec<PartialSignature<Bls12_381>>)> = key_shares
+            .iter()
+            .zip(nonce_shares)
+            .enumerate()
+            .map(|(issuer, (shares, nonces))| {
+                let partials = shares
+                    .iter()
+                    .zip(nonces)
+                    .map(|(share, nonce)| share.partial_sign(pp, &proof, nonce).unwrap())
+                    .collect();
+                (issuer, partials)
+            })
+            .collect();
+        let cm = &proof.commitment;
+
+        // Issuer 0 alone, through either combiner, and issuers 0 and 1 with weight 4
+        assert!(combine_weighted_partials(pp, cm, &key, &nonces, &contributions[..1]).is_err());
+        assert!(combine_partials(pp, cm, &key.key, &nonces, &contributions[0].1).is_err());
+        assert!(combine_weighted_partials(pp, cm, &key, &nonces, &contributions[..2]).is_err());
+
+        // Issuers 0, 1 and 2 together carry weight 5
+        let signature =
+            combine_weighted_partials(pp, cm, &key, &nonces, &contributions[..3]).unwrap();
+        credential.add_signature(signature).unwrap();
+        assert!(credential.verify(pp, &key.key.vk));
+
+        // An issuer withholding one of its points is named
+        let mut partial = contributions[1].clone();
+        partial.1.pop();
+        let incomplete = [partial, contributions[0].clone(), contributions[2].clone()];
+        match combine_weighted_partials(pp, cm, &key, &nonces, &incomplete) {
+            Err(Error::InvalidPartialSignature(issuer)) => assert_eq!(issuer, 1),
+            _ => panic!("Incomplete contribution should be attributed to issuer 1"),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+// mimc_abc/src/verifier_context.rs
+use crate::credential::ShowCredential;
+use crate::public_params::PublicParams;
+use crate::signature::{PreparedVerificationKey, VerificationKey};
+use ark_ec::pairing::Pairing;
+use ark_serialize::CanonicalSerialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Long-lived verifier state that prepares each issuer's key once
+/// Keys are cached by issuer fingerprint together with the public parameters' g_tilde,
+/// since both are folded into the prepared key
+pub struct VerifierContext<E: Pairing> {
+    prepared: HashMap<[u8; 32], PreparedVerificationKey<E>>,
+    preparations: usize,
+}
+
+impl<E: Pairing> Default for VerifierContext<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Pairing> VerifierContext<E> {
+    pub fn new() -> Self {
+        Self {
+            prepared: HashMap::new(),
+            preparations: 0,
+        }
+    }
+
+    /// Verify a presentation, preparing `vk` only the first time it's seen
+    pub fn verify(
+        &mut self,
+        presentation: &ShowCredential<E>,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+    ) -> bool {
+        if !presentation.verify_proof(pp) {
+            return false;
+        }
+        let prepared = self.prepared_key(pp, vk);
+        prepared.verify(
+            pp,
+            &presentation.randomized_signature,
+            &presentation.randomized_commitment,
+        )
+    }
+
+    /// Number of keys prepared so far (cache misses)
+    pub fn preparations(&self) -> usize {
+        self.preparations
+    }
+
+    fn prepared_key(
+        &mut self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+    ) -> &PreparedVerificationKey<E> {
+        let mut hasher = Sha256::new();
+        hasher.update(vk.fingerprint());
+        let mut g_tilde = Vec::new();
+        pp.g_tilde
+            .serialize_compressed(&mut g_tilde)
+            .expect("serializing into a Vec cannot fail");
+        hasher.update(&g_tilde);
+        let key: [u8; 32] = hasher.finalize().into();
+
+        let preparations = &mut self.preparations;
+        self.prepared.entry(key).or_insert_with(|| {
+            *preparations += 1;
+            vk.prepare(pp)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credential::Credential;
+    use crate::protocol::MimcAbc;
+    use crate::signature::{generate_keys, SecretKey};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_verifier_context_caches_prepared_keys() {
+        let mut rng = test_rng();
+        let n = 4;
+        let (protocol, sk_a, vk_a) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let (sk_b, vk_b) = generate_keys(&protocol.pp, &mut rng);
+
+        let present = |sk: &SecretKey<Bls12_381>, rng: &mut _| {
+            let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(rng)).collect();
+            let r = Fr::rand(rng);
+            let mut credential = Credential::new(&protocol.ck, &protocol.pp, &messages, r);
+            let proof = protocol.obtain(&credential, rng);
+            credential
+                .add_signature(protocol.issue(&proof, sk, rng).unwrap())
+                .unwrap();
+            protocol.show(&credential, rng).unwrap()
+        };
+        let from_a: Vec<_> = (0..3).map(|_| present(&sk_a, &mut rng)).collect();
+        let from_b: Vec<_> = (0..2).map(|_| present(&sk_b, &mut rng)).collect();
+
+        let mut context = VerifierContext::new();
+        for presentation in &from_a {
+            assert!(context.verify(presentation, &protocol.pp, &vk_a));
+        }
+        for presentation in &from_b {
+            assert!(context.verify(presentation, &protocol.pp, &vk_b));
+        }
+        assert_eq!(context.preparations(), 2, "Each issuer prepared once");
+
+        // Repeating a round hits the cache
+        assert!(context.verify(&from_a[0], &protocol.pp, &vk_a));
+        assert_eq!(context.preparations(), 2);
+
+        // Wrong issuer and tampered signature are rejected
+        assert!(!context.verify(&from_a[0], &protocol.pp, &vk_b));
+        let mut tampered = from_a[1].clone();
+        tampered.randomized_signature.sigma2 = from_a[2].randomized_signature.sigma2;
+        assert!(!context.verify(&tampered, &protocol.pp, &vk_a));
+        assert_eq!(context.preparations(), 2);
+    }
+}
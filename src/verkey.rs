@@ -1,8 +1,10 @@
 use crate::public_params::PublicParams;
+use crate::transcript::Transcript;
 use ark_ec::pairing::Pairing;
 use ark_ec::AffineRepr;
 use ark_ec::CurveGroup;
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::Mul;
 use ark_std::rand::Rng;
 
@@ -10,7 +12,7 @@ use ark_std::rand::Rng;
 /// Proves:
 /// - sk = g^x and vk = g̃^x (same x)
 /// - For each i, g_i = g^y_i and g̃_i = g̃^y_i (same y_i)
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct VerKeyProof<E: Pairing> {
     pub x_schnorr_com_g: E::G1Affine,
     pub x_schnorr_com_g_tilde: E::G2Affine,
@@ -18,7 +20,6 @@ pub struct VerKeyProof<E: Pairing> {
     pub t1: Vec<E::G1Affine>,
     pub t2: Vec<E::G2Affine>,
     pub responses: Vec<E::ScalarField>,
-    pub challenge: E::ScalarField,
 }
 
 impl<E: Pairing> VerKeyProof<E> {
@@ -42,15 +43,11 @@ impl<E: Pairing> VerKeyProof<E> {
             "Number of y values must match number of commitment key elements"
         );
 
-        // Generate challenge
-        let challenge = E::ScalarField::rand(rng);
-
         // first prove g^x and g_tilde^x by generating schnorr commitments in g, g_tilde
         // then we use vk to prove schnorr in g_tilde, then use pairing e(g, x_schnorr_com_g_tilde) = e(g_tilde, x_schnorr_com_g)
         let x_blinding = E::ScalarField::rand(rng);
         let x_schnorr_com_g = pp.g.mul(x_blinding).into_affine();
         let x_schnorr_com_g_tilde = pp.g_tilde.mul(x_blinding).into_affine();
-        let x_response = x_blinding + challenge * x;
 
         // now we prove ck = ck_tilde
         // we do schnorr for each base in G1, then use the same randomness in G2
@@ -71,6 +68,18 @@ impl<E: Pairing> VerKeyProof<E> {
             .map(|&r| pp.g_tilde.mul(r).into_affine())
             .collect();
 
+        // Fiat-Shamir: the challenge binds the parameters, the key and every announcement
+        let vk_tilde = pp.g_tilde.mul(x).into_affine();
+        let challenge = Self::challenge(
+            pp,
+            &vk_tilde,
+            &x_schnorr_com_g,
+            &x_schnorr_com_g_tilde,
+            &t1,
+            &t2,
+        );
+        let x_response = x_blinding + challenge * x;
+
         // Compute responses s_i = r_i + c * y_i
         let responses: Vec<E::ScalarField> = blindings
             .iter()
@@ -87,7 +96,6 @@ impl<E: Pairing> VerKeyProof<E> {
             t1,
             t2,
             responses,
-            challenge,
         }
     }
 
@@ -97,21 +105,28 @@ impl<E: Pairing> VerKeyProof<E> {
     /// * `pp` - Public parameters
     /// * `vk_tilde` - Verification key (g_tilde^x)
     pub fn verify(&self, pp: &PublicParams<E>, vk_tilde: &E::G2Affine) -> bool {
-        assert_eq!(
-            vk_tilde.mul(self.challenge) + self.x_schnorr_com_g_tilde,
-            pp.g_tilde.mul(self.x_response),
-            "Verification key x is not well-formed, or the proof isn't working"
+        // Check vector lengths
+        if self.t1.len() != pp.n || self.t2.len() != pp.n || self.responses.len() != pp.n {
+            return false;
+        }
+
+        let challenge = Self::challenge(
+            pp,
+            vk_tilde,
+            &self.x_schnorr_com_g,
+            &self.x_schnorr_com_g_tilde,
+            &self.t1,
+            &self.t2,
         );
 
-        let lhs = E::pairing(pp.g, self.x_schnorr_com_g_tilde);
-        let rhs = E::pairing(self.x_schnorr_com_g, pp.g_tilde);
-        if lhs != rhs {
-            println!("false!!!");
+        // Check g_tilde^{x_response} == x_schnorr_com_g_tilde * vk_tilde^c
+        if vk_tilde.mul(challenge) + self.x_schnorr_com_g_tilde != pp.g_tilde.mul(self.x_response) {
             return false;
         }
 
-        // Check vector lengths
-        if self.t1.len() != pp.n || self.t2.len() != pp.n || self.responses.len() != pp.n {
+        let lhs = E::pairing(pp.g, self.x_schnorr_com_g_tilde);
+        let rhs = E::pairing(self.x_schnorr_com_g, pp.g_tilde);
+        if lhs != rhs {
             return false;
         }
 
@@ -122,9 +137,14 @@ impl<E: Pairing> VerKeyProof<E> {
             let s_i = self.responses[i];
             let ck_tilde_i = pp.ck_tilde[i];
 
+            // Check g^{s_i} == t1_i * ck[i]^c
+            if pp.g.mul(s_i) != t1_i.into_group() + pp.ck[i].mul(challenge) {
+                return false;
+            }
+
             // Check g_tilde^{s_i} == t2_i * ck_tilde[i]^c
             let lhs = pp.g_tilde.mul(s_i).into_affine();
-            let rhs = (t2_i.into_group() + ck_tilde_i.mul(self.challenge)).into_affine();
+            let rhs = (t2_i.into_group() + ck_tilde_i.mul(challenge)).into_affine();
             if lhs != rhs {
                 return false;
             }
@@ -139,6 +159,27 @@ impl<E: Pairing> VerKeyProof<E> {
 
         true
     }
+
+    fn challenge(
+        pp: &PublicParams<E>,
+        vk_tilde: &E::G2Affine,
+        x_schnorr_com_g: &E::G1Affine,
+        x_schnorr_com_g_tilde: &E::G2Affine,
+        t1: &[E::G1Affine],
+        t2: &[E::G2Affine],
+    ) -> E::ScalarField {
+        let mut transcript = Transcript::new(b"mimc-abc-verkey");
+        transcript.append_serializable(b"g", &pp.g);
+        transcript.append_serializable(b"g_tilde", &pp.g_tilde);
+        transcript.append_serializable(b"ck", &pp.ck);
+        transcript.append_serializable(b"ck_tilde", &pp.ck_tilde);
+        transcript.append_serializable(b"vk_tilde", vk_tilde);
+        transcript.append_serializable(b"x_schnorr_com_g", x_schnorr_com_g);
+        transcript.append_serializable(b"x_schnorr_com_g_tilde", x_schnorr_com_g_tilde);
+        transcript.append_serializable(b"t1", t1);
+        transcript.append_serializable(b"t2", t2);
+        transcript.challenge_scalar(b"c")
+    }
 }
 
 /// Verification key functionality for the RS signature scheme
@@ -164,3 +205,30 @@ impl VerKey {
         proof.verify(pp, vk_tilde)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::generate_keys;
+    use ark_bls12_381::Bls12_381;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_proof_bound_to_key() {
+        let mut rng = test_rng();
+        let pp = PublicParams::<Bls12_381>::new(&4, &mut rng);
+        let (sk, vk) = generate_keys(&pp, &mut rng);
+        let (_, other_vk) = generate_keys(&pp, &mut rng);
+
+        let proof = VerKey::prove(&pp, &sk.get_x(), &pp.get_y_values(), &mut rng);
+        assert!(VerKey::verify(&proof, &pp, &vk.vk_tilde));
+        assert!(!VerKey::verify(&proof, &pp, &other_vk.vk_tilde));
+
+        // Other parameters fail too, and a tampered response returns false instead of panicking
+        let other_pp = PublicParams::<Bls12_381>::new(&4, &mut rng);
+        assert!(!proof.verify(&other_pp, &vk.vk_tilde));
+        let mut tampered = proof;
+        tampered.x_response += <Bls12_381 as Pairing>::ScalarField::from(1u64);
+        assert!(!tampered.verify(&pp, &vk.vk_tilde));
+    }
+}
@@ -0,0 +1,75 @@
+// Checks the lifecycle fixture written by examples/gen_vectors.rs step by step, pinning
+// both the arithmetic and the encodings
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_ec::CurveGroup;
+use ark_serialize::CanonicalDeserialize;
+use ark_std::ops::Mul;
+use mimc_abc::commitment::{Commitment, CommitmentKey};
+use mimc_abc::credential::ShowCredential;
+use mimc_abc::proof::CommitmentProof;
+use mimc_abc::public_params::PublicParams;
+use mimc_abc::serialization::{from_bytes, to_bytes};
+use mimc_abc::signature::{SecretKey, Signature, VerificationKey};
+use std::collections::HashMap;
+
+const FIXTURE: &str = include_str!("fixtures/lifecycle_vectors.txt");
+
+fn fixture() -> HashMap<&'static str, Vec<u8>> {
+    FIXTURE
+        .lines()
+        .map(|line| {
+            let (name, hex) = line.split_once(": ").expect("`name: hex` lines");
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("hex digits"))
+                .collect();
+            (name, bytes)
+        })
+        .collect()
+}
+
+// Decode, and check the encoding round-trips to the same bytes
+fn decode<T>(vectors: &HashMap<&str, Vec<u8>>, name: &str) -> T
+where
+    T: CanonicalDeserialize + ark_serialize::CanonicalSerialize,
+{
+    let bytes = &vectors[name];
+    let item: T = from_bytes(bytes).unwrap_or_else(|e| panic!("{} doesn't decode: {}", name, e));
+    assert_eq!(&to_bytes(&item), bytes, "{} re-encodes differently", name);
+    item
+}
+
+#[test]
+fn test_lifecycle_vectors() {
+    let vectors = fixture();
+
+    let pp: PublicParams<Bls12_381> = decode(&vectors, "public_params");
+    pp.validate().expect("fixture parameters are well formed");
+    let sk: SecretKey<Bls12_381> = decode(&vectors, "secret_key");
+    let vk: VerificationKey<Bls12_381> = decode(&vectors, "verification_key");
+    assert_eq!(vk.vk_tilde, pp.g_tilde.mul(sk.get_x()).into_affine());
+    assert_eq!(sk.sk, pp.g.mul(sk.get_x()).into_affine());
+
+    // Commitment to the attributes
+    let messages: Vec<Fr> = decode(&vectors, "messages");
+    let r: Fr = decode(&vectors, "r");
+    let commitment: Commitment<Bls12_381> = decode(&vectors, "commitment");
+    let ck = CommitmentKey {
+        ck: pp.ck.clone(),
+        ck_tilde: pp.ck_tilde.clone(),
+    };
+    let recomputed = ck.commit(&pp, &messages, &r);
+    assert_eq!(recomputed.cm, commitment.cm);
+    assert_eq!(recomputed.cm_tilde, commitment.cm_tilde);
+
+    // Issuance
+    let issuance_proof: CommitmentProof<Bls12_381> = decode(&vectors, "issuance_proof");
+    assert_eq!(issuance_proof.commitment.cm, commitment.cm);
+    assert!(issuance_proof.verify());
+    let signature: Signature<Bls12_381> = decode(&vectors, "signature");
+    assert!(vk.verify_with_pairing_checker(&signature, &commitment, &pp));
+
+    // Presentation
+    let presentation: ShowCredential<Bls12_381> = decode(&vectors, "presentation");
+    assert!(presentation.verify(&pp, &vk));
+}